@@ -0,0 +1,65 @@
+/*
+chunk lookup benchmark
+
+fills a world with every chunk inside a square render distance and times
+random get_chunk_blocks lookups against it
+
+the time per lookup should stay flat from 20 to 64 chunk render distance
+
+run with: cargo bench --no-default-features --bench chunk_lookup
+*/
+
+use std::{
+    hint::black_box,
+    time::Instant
+};
+
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crafter::world::world::World;
+
+const LOOKUPS: usize = 1_000_000;
+
+fn bench_render_distance(render_distance: i32) {
+
+    let mut world: World = World::initialize();
+
+    for x in -render_distance..=render_distance {
+        for z in -render_distance..=render_distance {
+            world.add_chunk(x, z);
+        }
+    }
+
+    // the same positions for every distance so only the world size changes
+    let mut rng: StdRng = StdRng::seed_from_u64(123213123);
+
+    let positions: Vec<(i32, i32)> = (0..LOOKUPS).map(|_| {
+        (
+            rng.gen_range(-render_distance..=render_distance),
+            rng.gen_range(-render_distance..=render_distance)
+        )
+    }).collect();
+
+    let start: Instant = Instant::now();
+
+    for (x, z) in positions.iter() {
+        black_box(world.get_chunk_blocks(*x, *z));
+    }
+
+    let elapsed = start.elapsed();
+
+    let chunk_count = (render_distance * 2 + 1).pow(2);
+
+    println!(
+        "render distance {:>2} | {:>5} chunks | {:>8.2} ns per lookup",
+        render_distance,
+        chunk_count,
+        elapsed.as_nanos() as f64 / LOOKUPS as f64
+    );
+}
+
+fn main() {
+    for render_distance in [20, 32, 48, 64] {
+        bench_render_distance(render_distance);
+    }
+}
@@ -27,12 +27,20 @@ crafter = {
     blocks = {},
     -- Holds biome data to be passed into Rust.
     biomes = {},
+    -- Holds dimension data to be passed into Rust, in registration order.
+    dimensions = {},
+    -- Holds decoration data to be passed into Rust, in registration order.
+    decorations = {},
     -- Localization cached and then cached into table.
     operating_system = get_operating_system(),
     -- Current root directory of the program.
     directory = get_working_directory(),
     -- Caches textures for Rust.
-    texture_cache = {}
+    texture_cache = {},
+    -- The mods that were run, in load order. Rust saves this list with the world.
+    loaded_mods = {},
+    -- Event type -> the mods listening to it, Rust only sends events somebody listens to.
+    world_event_callbacks = {}
 }
 
 -- This is debug for testing on other operating systems.
@@ -44,8 +52,39 @@ dofile("lua_libraries/lua_functions.lua")
 -- This is a simple way to hold the file directory without creating an on-disk cache.
 current_loading_mod = "engine"
 
+--[[
+    Rust sets enabled_mods to the mods an existing world was made with.
+
+    When it's nil (a new world) every mod in the mods folder is loaded.
+]]--
+local function mod_is_enabled(mod)
+    if enabled_mods == nil then
+        return true
+    end
+    for _,enabled_mod in ipairs(enabled_mods) do
+        if enabled_mod == mod then
+            return true
+        end
+    end
+    return false
+end
+
+-- Runs a module's entry point and remembers that it was loaded.
+local function load_mod(mod, entry_point)
+    if not mod_is_enabled(mod) then
+        return
+    end
+    -- This is a global assign.
+    current_loading_mod = mod
+    -- Run module's entry point.
+    dofile(entry_point)
+    table.insert(crafter.loaded_mods, mod)
+end
+
 register_air()
 
+register_overworld()
+
 -- The Windows module loader.
 if crafter.operating_system == "windows" then
     -- Open mods folder using built in Windows function.
@@ -53,10 +92,7 @@ if crafter.operating_system == "windows" then
 
     -- Iterate each folder.
     for mod in f:lines() do
-        -- This is a global assign.
-        current_loading_mod = mod
-        -- Run module's entry point.
-        dofile(crafter.directory .. "\\mods\\" .. mod .. "\\main.lua")
+        load_mod(mod, crafter.directory .. "\\mods\\" .. mod .. "\\main.lua")
     end
 -- The Linux module loader.
 elseif crafter.operating_system == "linux" then
@@ -68,10 +104,7 @@ elseif crafter.operating_system == "linux" then
     for mod in pfile:lines() do
         -- If file contains a period, do not run it.
         if not string.find(mod, "%.") then
-            -- This is a global assign.
-            current_loading_mod = mod
-            -- Run module's entry point.
-            dofile(crafter.directory .. "/mods/" .. mod .. "/main.lua")
+            load_mod(mod, crafter.directory .. "/mods/" .. mod .. "/main.lua")
         end
     end
 elseif crafter.operating_system == "mac" then
@@ -79,11 +112,38 @@ elseif crafter.operating_system == "mac" then
     print("I'm not even sure if this comes up as mac.")
 end
 
+-- Let the player know when a world wants a mod that isn't installed anymore.
+if enabled_mods ~= nil then
+    for _,enabled_mod in ipairs(enabled_mods) do
+        local found = false
+        for _,loaded_mod in ipairs(crafter.loaded_mods) do
+            if loaded_mod == enabled_mod then
+                found = true
+            end
+        end
+        if not found then
+            print("WARNING: WORLD USES MOD " .. enabled_mod .. " BUT IT IS NOT INSTALLED!")
+        end
+    end
+end
+
 -- Check biomes to make sure they contain only valid blocks.
 for name,biome in pairs(crafter.biomes) do
     double_check_biome_blocks(biome.mod, name, biome, crafter.blocks)
 
     double_check_biome_ores(biome.mod, name, biome.ores, crafter.blocks)
+
+    double_check_biome_dimension(biome.mod, name, biome, crafter.dimensions)
+end
+
+-- Check dimensions to make sure they can generate.
+for _,dimension in ipairs(crafter.dimensions) do
+    double_check_dimension_biomes(dimension, crafter.biomes)
+end
+
+-- Check decorations to make sure they only place what exists.
+for _,decoration in ipairs(crafter.decorations) do
+    double_check_decoration(decoration, crafter.blocks, crafter.biomes, crafter.dimensions)
 end
 
 print("--- LUA IS NOW DONE ---")
\ No newline at end of file
@@ -1,4 +1,7 @@
 dofile("lua_libraries/registration/register_block.lua")
+dofile("lua_libraries/registration/register_dimension.lua")
+dofile("lua_libraries/registration/register_decoration.lua")
+dofile("lua_libraries/registration/register_world_event.lua")
 
 local function check_layers(mod, table_data)
     -- Biome needs all layers.
@@ -44,6 +47,69 @@ local function check_params(mod, biome_name, params_name, params_table)
     end
 end
 
+local function check_biome_noise_params(mod, table_data)
+    assert(type(table_data.biome_noise_params) == "table", "BIOME " .. mod .. ":" .. table_data.name .. " NEEDS A TABLE AS biome_noise_params!")
+
+    -- Only the terrain shape is needed, where the biome goes is in the climate.
+    for _,name in ipairs({"scale", "frequency"}) do
+        assert(type(table_data.biome_noise_params[name]) == "number", "BIOME " .. mod .. ":" .. table_data.name .. " NEEDS A NUMBER AS " .. name .. " IN biome_noise_params!")
+    end
+end
+
+-- Mirrors ClimateSample in Rust, every value is roughly -1 to 1.
+local climate_values = {"heat", "humidity", "elevation"}
+
+local valid_climate_values = {
+    ["heat"] = true,
+    ["humidity"] = true,
+    ["elevation"] = true,
+}
+
+--[[
+    The climate is where a biome generates.
+
+    Every value can be a {min, max} range, the biome generates where all of its ranges hold.
+    A single number is a point, the biome generates wherever it's the closest one.
+    A value that is left out means the biome doesn't care about it.
+]]--
+local function check_biome_climate(mod, table_data)
+    local params = table_data.biome_noise_params
+
+    -- Biomes without a climate use the heat range in biome_noise_params.
+    if table_data.climate == nil then
+        assert(type(params.heat_min) == "number" and type(params.heat_max) == "number", "BIOME " .. mod .. ":" .. table_data.name .. " NEEDS A climate OR heat_min AND heat_max IN biome_noise_params!")
+
+        table_data.climate = { heat = {params.heat_min, params.heat_max} }
+    end
+
+    assert(type(table_data.climate) == "table", "BIOME " .. mod .. ":" .. table_data.name .. " NEEDS A TABLE AS climate!")
+
+    for name,_ in pairs(table_data.climate) do
+        assert(valid_climate_values[name] == true, "BIOME " .. mod .. ":" .. table_data.name .. " HAS AN INVALID VALUE " .. tostring(name) .. " IN climate! IT MUST BE heat, humidity OR elevation!")
+    end
+
+    local found = false
+
+    for _,name in ipairs(climate_values) do
+        local value = table_data.climate[name]
+
+        if value ~= nil then
+            found = true
+
+            -- Turn points into ranges so Rust only sees one kind.
+            if type(value) == "number" then
+                table_data.climate[name] = {value, value}
+            else
+                assert(type(value) == "table" and #value == 2 and type(value[1]) == "number" and type(value[2]) == "number", "BIOME " .. mod .. ":" .. table_data.name .. " NEEDS A NUMBER OR A {min, max} TABLE AS " .. name .. " IN climate!")
+
+                assert(value[1] <= value[2], "BIOME " .. mod .. ":" .. table_data.name .. " HAS A min BIGGER THAN IT'S max IN " .. name .. " IN climate!")
+            end
+        end
+    end
+
+    assert(found == true, "BIOME " .. mod .. ":" .. table_data.name .. " NEEDS AT LEAST ONE OF heat, humidity OR elevation IN climate!")
+end
+
 local function automate_and_check_biome_parameters(mod, table_data)
 
     -- Default to 30 if forgotten.
@@ -56,7 +122,10 @@ local function automate_and_check_biome_parameters(mod, table_data)
 
     -- Check biome parameters.
     -- print(dump(table_data.biome_noise_params))
-    check_params(mod, table_data.name, "biome_noise_params", table_data.biome_noise_params)
+    check_biome_noise_params(mod, table_data)
+
+    -- Check and automate where the biome generates.
+    check_biome_climate(mod, table_data)
 
     -- Check cave parameters.
     check_params(mod, table_data.name, "cave_noise_params", table_data.cave_noise_params)
@@ -70,6 +139,13 @@ local function automate_and_check_biome_parameters(mod, table_data)
         table_data.snow = false
     end
 
+    -- Biomes go into the overworld unless told otherwise.
+    if table_data.dimension == nil then
+        table_data.dimension = "overworld"
+    end
+
+    assert(type(table_data.dimension) == "string", "BIOME " .. mod .. ":" .. table_data.name .. " NEEDS A STRING AS dimension!")
+
 end
 
 
@@ -122,6 +122,170 @@ local function check_block_flips(mod, block_name, table_data)
     end
 end
 
+-- This requires the entire table pointer.
+local function check_block_light(mod, block_name, table_data)
+
+    -- Blocks do not glow unless told to.
+    if table_data.light == nil then
+        table_data.light = 0
+    end
+
+    assert(type(table_data.light) == "number", mod .. ":" .. block_name .. " LIGHT MUST BE A NUMBER!")
+
+    -- Limit the data value. (0 through 15)
+    assert(
+        table_data.light >= 0 and
+        table_data.light <= 15,
+        mod .. ":" .. block_name .. " LIGHT OUT OF BOUNDS! LIGHT IS LIMITED TO 0 THROUGH 15!"
+    )
+
+    table_data.light = math.floor(table_data.light)
+end
+
+-- This requires the entire table pointer.
+local function check_block_rotation_type(mod, block_name, table_data)
+
+    -- Blocks are always drawn the way they are defined unless told otherwise.
+    if table_data.rotation_type == nil then
+        table_data.rotation_type = "none"
+    end
+
+    -- none: fixed, horizontal: 4 facings like furnaces and stairs, full: all 24 orientations like logs.
+    assert(
+        table_data.rotation_type == "none" or
+        table_data.rotation_type == "horizontal" or
+        table_data.rotation_type == "full",
+        mod .. ":" .. block_name .. " ROTATION TYPE MUST BE \"none\", \"horizontal\", OR \"full\"!"
+    )
+end
+
+--[[
+    Groups are names with a whole number rating, engine behavior hangs off some of them.
+
+    groups = { falling = 1 }
+
+    falling: the block falls when there is nothing under it, like sand.
+]]--
+local function check_block_groups(mod, block_name, table_data)
+    if table_data.groups == nil then
+        table_data.groups = {}
+    end
+
+    assert(type(table_data.groups) == "table", mod .. ":" .. block_name .. " GROUPS MUST BE A TABLE!")
+
+    for group, rating in pairs(table_data.groups) do
+        assert(type(group) == "string", mod .. ":" .. block_name .. " GROUPS MUST BE KEYED BY NAME!")
+
+        assert(type(rating) == "number", mod .. ":" .. block_name .. " GROUP " .. group .. " MUST HAVE A NUMBER RATING!")
+
+        table_data.groups[group] = math.floor(rating)
+    end
+end
+
+--[[
+    Liquids use the "liquid" draw type and a liquid table, everything in it can be left out.
+
+    liquid = {
+        viscosity = 5,      -- Game ticks between each step of the flow, lava is slower than water.
+        range = 7,          -- How many blocks out from a source it reaches on flat ground, 1 through 15.
+        renewable = true,   -- Flowing liquid between two sources with ground under it becomes a source.
+        interactions = {
+            -- What this liquid turns other blocks it touches into, sources and flowing liquid can differ.
+            lava = { source = "obsidian", flowing = "cobble" },
+            fire = "air",
+        },
+    }
+
+    The level of a liquid is its rotation, 0 is a source. world.set_block(x, y, z, "water") places a source.
+]]--
+local function check_block_liquid(mod, block_name, table_data)
+    if table_data.draw_type ~= "liquid" then
+        assert(table_data.liquid == nil, mod .. ":" .. block_name .. " HAS A LIQUID TABLE BUT IS NOT DRAWN AS A LIQUID!")
+        return
+    end
+
+    -- The rotation holds the level.
+    assert(table_data.rotation_type == "none", mod .. ":" .. block_name .. " IS A LIQUID, LIQUIDS CANNOT ROTATE!")
+
+    if table_data.liquid == nil then
+        table_data.liquid = {}
+    end
+
+    local liquid = table_data.liquid
+
+    assert(type(liquid) == "table", mod .. ":" .. block_name .. " LIQUID MUST BE A TABLE!")
+
+    if liquid.viscosity == nil then
+        liquid.viscosity = 5
+    end
+
+    assert(type(liquid.viscosity) == "number" and liquid.viscosity >= 1, mod .. ":" .. block_name .. " LIQUID VISCOSITY MUST BE A NUMBER OF AT LEAST 1!")
+
+    liquid.viscosity = math.floor(liquid.viscosity)
+
+    if liquid.range == nil then
+        liquid.range = 7
+    end
+
+    -- Limit the data value. (1 through 15)
+    assert(
+        type(liquid.range) == "number" and
+        liquid.range >= 1 and
+        liquid.range <= 15,
+        mod .. ":" .. block_name .. " LIQUID RANGE OUT OF BOUNDS! RANGE IS LIMITED TO 1 THROUGH 15!"
+    )
+
+    liquid.range = math.floor(liquid.range)
+
+    if liquid.renewable == nil then
+        liquid.renewable = true
+    end
+
+    assert(type(liquid.renewable) == "boolean", mod .. ":" .. block_name .. " LIQUID RENEWABLE MUST BE A BOOLEAN!")
+
+    if liquid.interactions == nil then
+        liquid.interactions = {}
+    end
+
+    assert(type(liquid.interactions) == "table", mod .. ":" .. block_name .. " LIQUID INTERACTIONS MUST BE A TABLE!")
+
+    -- Turn every interaction into a source and flowing result so Rust only deals with one shape.
+    for other, result in pairs(liquid.interactions) do
+        assert(type(other) == "string", mod .. ":" .. block_name .. " LIQUID INTERACTIONS MUST BE KEYED BY BLOCK NAME!")
+
+        if type(result) == "string" then
+            liquid.interactions[other] = { source = result, flowing = result }
+        else
+            assert(
+                type(result) == "table" and
+                type(result.source) == "string" and
+                type(result.flowing) == "string",
+                mod .. ":" .. block_name .. " LIQUID INTERACTION WITH " .. other .. " MUST BE A BLOCK NAME OR { source = , flowing = }!"
+            )
+        end
+    end
+end
+
+--[[
+    Blocks that change on their own opt in with callbacks, both get called as callback(pos, world).
+
+    on_random_tick: runs when the block is picked at random, a few blocks of every section each tick. Grass and crops.
+    on_scheduled_update: runs once the delay given to world.schedule_update(x, y, z, ticks) is up. Falling blocks.
+
+    pos is {x = , y = , z = }, world can:
+    world.get_block(x, y, z) -> name or nil if not loaded
+    world.set_block(x, y, z, name, rotation) -> true if it was set, rotation can be left out
+    world.get_block_rotation(x, y, z) -> rotation or nil
+    world.get_light(x, y, z) -> sky light, block light or nil
+    world.schedule_update(x, y, z, ticks) -> false if that block already has one waiting
+    world.get_tick() -> how many ticks the dimension has run
+]]--
+local function check_block_ticks(mod, block_name, table_data)
+    assert(table_data.on_random_tick == nil or type(table_data.on_random_tick) == "function", mod .. ":" .. block_name .. " on_random_tick MUST BE A FUNCTION!")
+
+    assert(table_data.on_scheduled_update == nil or type(table_data.on_scheduled_update) == "function", mod .. ":" .. block_name .. " on_scheduled_update MUST BE A FUNCTION!")
+end
+
 
 -- This allows module creators to register blocks easily.
 crafter.register_block = function(table_data)
@@ -133,6 +297,21 @@ crafter.register_block = function(table_data)
     -- Blocks must have a name.
     assert(table_data.name ~= nil, "A BLOCK IN MOD " .. mod .. " IS MISSING A NAME!")
 
+    -- Automate light, light check, and light data limiter.
+    check_block_light(mod, table_data.name, table_data)
+
+    -- Automate rotation type and rotation type check.
+    check_block_rotation_type(mod, table_data.name, table_data)
+
+    -- Tick callbacks have to be functions.
+    check_block_ticks(mod, table_data.name, table_data)
+
+    -- Automate groups and check them.
+    check_block_groups(mod, table_data.name, table_data)
+
+    -- Automate liquid properties and check them.
+    check_block_liquid(mod, table_data.name, table_data)
+
     -- Blocks must have at least one texture. But not air.
     if table_data.name ~= "air" then
         assert(table_data.textures ~= nil and #table_data.textures > 0, mod .. ":" .. table_data.name .." HAS NO TEXTURE DEFINED!")
@@ -163,3 +342,24 @@ crafter.register_block = function(table_data)
     crafter.blocks[table_data.name] = table_data
 end
 
+-- Rust calls this for every block tick that is due, kind is "random" or "scheduled".
+function run_block_tick(kind, name, pos, world)
+    local block = crafter.blocks[name]
+
+    local callback = nil
+    if kind == "random" then
+        callback = block.on_random_tick
+    else
+        callback = block.on_scheduled_update
+    end
+
+    if callback == nil then
+        return
+    end
+
+    local success, error_message = pcall(callback, pos, world)
+
+    if not success then
+        print("BLOCK TICK " .. kind .. " OF " .. block.mod .. ":" .. name .. " FAILED: " .. tostring(error_message) .. "!")
+    end
+end
@@ -0,0 +1,128 @@
+--[[
+    Decorations are the things that sit on top of the terrain, like boulders and trees.
+
+    A decoration is a group of blocks placed around a surface block during world generation.
+    The blocks are offsets from the block right above the surface, so { x = 0, y = 0, z = 0 } sits on the ground.
+
+    Decorations can reach up to 16 blocks past their anchor on x and z, that's what lets them grow into neighboring chunks.
+]]--
+
+-- Mirrors MAX_DECORATION_REACH in Rust.
+local max_reach = 16
+
+local function check_decoration_biomes(mod, table_data)
+    -- Nil means every biome of the dimension.
+    if table_data.biomes == nil then
+        table_data.biomes = {}
+    end
+
+    assert(type(table_data.biomes) == "table", "DECORATION " .. mod .. ":" .. table_data.name .. " NEEDS A TABLE OF BIOME NAMES AS biomes!")
+
+    for index,biome in ipairs(table_data.biomes) do
+        assert(type(biome) == "string", "DECORATION " .. mod .. ":" .. table_data.name .. " HAS INVALID DATA IN biomes IN INDEX " .. index .. "!")
+    end
+end
+
+local function check_decoration_place_on(mod, table_data)
+    -- Nil means it can sit on anything.
+    if table_data.place_on == nil then
+        table_data.place_on = {}
+    end
+
+    assert(type(table_data.place_on) == "table", "DECORATION " .. mod .. ":" .. table_data.name .. " NEEDS A TABLE OF BLOCK NAMES AS place_on!")
+
+    for index,block in ipairs(table_data.place_on) do
+        assert(type(block) == "string", "DECORATION " .. mod .. ":" .. table_data.name .. " HAS INVALID DATA IN place_on IN INDEX " .. index .. "!")
+    end
+end
+
+local function check_decoration_fill_ratio(mod, table_data)
+    assert(type(table_data.fill_ratio) == "number", "DECORATION " .. mod .. ":" .. table_data.name .. " NEEDS A NUMBER AS fill_ratio!")
+
+    assert(table_data.fill_ratio >= 0 and table_data.fill_ratio <= 1, "DECORATION " .. mod .. ":" .. table_data.name .. " HAS A fill_ratio OUTSIDE OF 0 TO 1!")
+end
+
+local function check_decoration_blocks(mod, table_data)
+    assert(type(table_data.blocks) == "table" and #table_data.blocks > 0, "DECORATION " .. mod .. ":" .. table_data.name .. " NEEDS A TABLE OF blocks!")
+
+    for index,block in ipairs(table_data.blocks) do
+        assert(type(block) == "table", "DECORATION " .. mod .. ":" .. table_data.name .. " HAS INVALID DATA IN blocks IN INDEX " .. index .. "!")
+
+        for _,axis in ipairs({"x", "y", "z"}) do
+            assert(type(block[axis]) == "number", "DECORATION " .. mod .. ":" .. table_data.name .. " NEEDS A NUMBER AS " .. axis .. " IN blocks IN INDEX " .. index .. "!")
+            block[axis] = math.floor(block[axis])
+        end
+
+        assert(math.abs(block.x) <= max_reach and math.abs(block.z) <= max_reach, "DECORATION " .. mod .. ":" .. table_data.name .. " REACHES FURTHER THAN " .. max_reach .. " BLOCKS IN blocks IN INDEX " .. index .. "!")
+
+        assert(type(block.name) == "string", "DECORATION " .. mod .. ":" .. table_data.name .. " NEEDS A BLOCK NAME AS name IN blocks IN INDEX " .. index .. "!")
+
+        -- Default to unrotated.
+        if block.rotation == nil then
+            block.rotation = 0
+        end
+
+        assert(type(block.rotation) == "number" and block.rotation >= 0 and block.rotation <= 255, "DECORATION " .. mod .. ":" .. table_data.name .. " HAS AN INVALID rotation IN blocks IN INDEX " .. index .. "!")
+
+        block.rotation = math.floor(block.rotation)
+    end
+end
+
+crafter.register_decoration = function(table_data)
+    -- Cache string pointer.
+    local mod = current_loading_mod
+    table_data.mod = mod
+
+    -- Decoration needs a name.
+    assert(table_data.name ~= nil and type(table_data.name) == "string", mod .. " IS MISSING A NAME IN ONE OF IT'S DECORATIONS!")
+
+    for _,decoration in ipairs(crafter.decorations) do
+        assert(decoration.name ~= table_data.name, "DECORATION " .. mod .. ":" .. table_data.name .. " IS ALREADY REGISTERED BY " .. decoration.mod .. "!")
+    end
+
+    -- Decorations go into the overworld unless told otherwise.
+    if table_data.dimension == nil then
+        table_data.dimension = "overworld"
+    end
+
+    assert(type(table_data.dimension) == "string", "DECORATION " .. mod .. ":" .. table_data.name .. " NEEDS A STRING AS dimension!")
+
+    check_decoration_biomes(mod, table_data)
+
+    check_decoration_place_on(mod, table_data)
+
+    check_decoration_fill_ratio(mod, table_data)
+
+    check_decoration_blocks(mod, table_data)
+
+    -- Kept in registration order, where two decorations want the same block the first one wins.
+    table.insert(crafter.decorations, table_data)
+end
+
+-- Make sure that a decoration only uses blocks, biomes and a dimension that exist.
+function double_check_decoration(decoration, blocks, biomes, dimensions)
+    local name = decoration.mod .. ":" .. decoration.name
+
+    local found = false
+    for _,dimension in ipairs(dimensions) do
+        if dimension.name == decoration.dimension then
+            found = true
+        end
+    end
+
+    assert(found == true, "DECORATION " .. name .. " IS IN AN UNDEFINED DIMENSION: " .. decoration.dimension .. "!")
+
+    for _,biome in ipairs(decoration.biomes) do
+        assert(biomes[biome] ~= nil, "DECORATION " .. name .. " USES AN UNDEFINED BIOME: " .. biome .. "!")
+
+        assert(biomes[biome].dimension == decoration.dimension, "DECORATION " .. name .. " USES BIOME " .. biome .. " WHICH IS NOT IN DIMENSION " .. decoration.dimension .. "!")
+    end
+
+    for _,block in ipairs(decoration.place_on) do
+        assert(blocks[block] ~= nil, "DECORATION " .. name .. " CAN BE PLACED ON AN UNDEFINED BLOCK: " .. block .. "!")
+    end
+
+    for _,block in ipairs(decoration.blocks) do
+        assert(blocks[block.name] ~= nil, "DECORATION " .. name .. " CONTAINS AN UNDEFINED BLOCK: " .. block.name .. "!")
+    end
+end
@@ -0,0 +1,126 @@
+--[[
+    Dimensions are separate worlds inside the same save, like an underground or a sky.
+
+    Every biome belongs to exactly one dimension. The overworld is always registered by the engine.
+]]--
+
+local valid_generators = {
+    ["biomes"] = true,
+    ["flat"] = true,
+}
+
+local function check_dimension_generator(mod, table_data)
+    -- Nil uses the generator the world was created with.
+    if table_data.generator == nil then
+        return
+    end
+
+    assert(type(table_data.generator) == "string" and valid_generators[table_data.generator] == true, "DIMENSION " .. mod .. ":" .. table_data.name .. " HAS AN INVALID generator! IT MUST BE biomes OR flat!")
+end
+
+local function check_dimension_base_height(mod, table_data)
+    -- Default to 90 if forgotten.
+    if table_data.base_height == nil then
+        table_data.base_height = 90
+    end
+
+    assert(type(table_data.base_height) == "number", "DIMENSION " .. mod .. ":" .. table_data.name .. " NEEDS A NUMBER AS base_height!")
+
+    table_data.base_height = math.floor(table_data.base_height)
+end
+
+-- Mirrors MAX_BIOME_BLEND_RADIUS in Rust.
+local max_biome_blend_radius = 32
+
+local function check_biome_blend_radius(name, radius)
+    assert(type(radius) == "number" and radius >= 0 and radius <= max_biome_blend_radius, "DIMENSION " .. name .. " NEEDS A NUMBER FROM 0 TO " .. max_biome_blend_radius .. " AS biome_blend_radius!")
+end
+
+local function check_dimension_biome_blend_radius(mod, table_data)
+    -- Default to 8 if forgotten, 0 turns blending off.
+    if table_data.biome_blend_radius == nil then
+        table_data.biome_blend_radius = 8
+    end
+
+    check_biome_blend_radius(mod .. ":" .. table_data.name, table_data.biome_blend_radius)
+
+    table_data.biome_blend_radius = math.floor(table_data.biome_blend_radius)
+end
+
+crafter.register_dimension = function(table_data)
+    -- Cache string pointer.
+    local mod = current_loading_mod
+    table_data.mod = mod
+
+    -- Dimension needs a name.
+    assert(table_data.name ~= nil and type(table_data.name) == "string", mod .. " IS MISSING A NAME IN ONE OF IT'S DIMENSIONS!")
+
+    -- The name becomes the dimension's directory inside the world.
+    assert(string.match(table_data.name, "^[a-z0-9_]+$") ~= nil, "DIMENSION " .. mod .. ":" .. table_data.name .. " MAY ONLY USE a-z, 0-9 AND _ IN IT'S NAME!")
+
+    for _,dimension in ipairs(crafter.dimensions) do
+        assert(dimension.name ~= table_data.name, "DIMENSION " .. mod .. ":" .. table_data.name .. " IS ALREADY REGISTERED BY " .. dimension.mod .. "!")
+    end
+
+    check_dimension_generator(mod, table_data)
+
+    check_dimension_base_height(mod, table_data)
+
+    check_dimension_biome_blend_radius(mod, table_data)
+
+    -- Kept in registration order, this is the order the player travels through them.
+    table.insert(crafter.dimensions, table_data)
+end
+
+--[[
+    How many blocks around a column the biomes get blended across, so biome borders turn into slopes instead of cliffs.
+
+    This is how mods change it for a dimension they didn't register, like the overworld. Dimension defaults to the overworld.
+]]--
+crafter.set_biome_blend_radius = function(radius, dimension_name)
+    if dimension_name == nil then
+        dimension_name = "overworld"
+    end
+
+    for _,dimension in ipairs(crafter.dimensions) do
+        if dimension.name == dimension_name then
+            check_biome_blend_radius(dimension.mod .. ":" .. dimension.name, radius)
+
+            dimension.biome_blend_radius = math.floor(radius)
+
+            return
+        end
+    end
+
+    error(current_loading_mod .. " TRIED TO SET THE BIOME BLEND RADIUS OF AN UNDEFINED DIMENSION: " .. tostring(dimension_name) .. "!")
+end
+
+-- The overworld is where every world starts.
+function register_overworld()
+    crafter.register_dimension({
+        name = "overworld",
+    })
+end
+
+-- Make sure that a biome goes into a dimension that exists.
+function double_check_biome_dimension(mod, name, biome, dimensions)
+    local found = false
+    for _,dimension in ipairs(dimensions) do
+        if dimension.name == biome.dimension then
+            found = true
+        end
+    end
+
+    assert(found == true, "BIOME " .. mod .. ":" .. name .. " IS IN AN UNDEFINED DIMENSION: " .. biome.dimension .. "!")
+end
+
+-- Make sure that every dimension has something to generate.
+function double_check_dimension_biomes(dimension, biomes)
+    for _,biome in pairs(biomes) do
+        if biome.dimension == dimension.name then
+            return
+        end
+    end
+
+    error("DIMENSION " .. dimension.mod .. ":" .. dimension.name .. " HAS NO BIOMES!")
+end
@@ -0,0 +1,58 @@
+--[[
+    World events let mods react to the world changing.
+
+    crafter.register_on_world_event("block_set", function(event)
+        print(event.dimension .. " " .. event.x .. " " .. event.y .. " " .. event.z .. " is now " .. event.new_block)
+    end)
+
+    Every event has a type and the dimension it happened in, the rest depends on the type:
+
+    chunk_added, chunk_generated, chunk_removed, chunk_edited, chunk_relit: x, z (chunk position)
+    block_set: x, y, z, old_block, new_block (block names)
+    block_rotated: x, y, z, old_rotation, new_rotation
+    block_dropped: x, y, z, block (a falling block that landed somewhere it can't stay, it is gone from the world)
+    light_changed: x, section, z (chunk position and section)
+
+    Events are sent once the world is done changing, never in the middle of an edit.
+]]--
+
+local valid_events = {
+    ["chunk_added"] = true,
+    ["chunk_generated"] = true,
+    ["chunk_removed"] = true,
+    ["block_set"] = true,
+    ["block_rotated"] = true,
+    ["chunk_edited"] = true,
+    ["block_dropped"] = true,
+    ["light_changed"] = true,
+    ["chunk_relit"] = true,
+}
+
+crafter.register_on_world_event = function(event_type, callback)
+    -- Cache string pointer.
+    local mod = current_loading_mod
+
+    assert(type(event_type) == "string" and valid_events[event_type] == true, mod .. " TRIED TO LISTEN TO AN UNKNOWN WORLD EVENT: " .. tostring(event_type) .. "!")
+
+    assert(type(callback) == "function", mod .. " NEEDS A FUNCTION TO LISTEN TO " .. event_type .. "!")
+
+    if crafter.world_event_callbacks[event_type] == nil then
+        crafter.world_event_callbacks[event_type] = {}
+    end
+
+    table.insert(crafter.world_event_callbacks[event_type], {
+        mod = mod,
+        callback = callback,
+    })
+end
+
+-- Rust calls this with every event somebody listens to.
+function dispatch_world_event(event)
+    for _,listener in ipairs(crafter.world_event_callbacks[event.type]) do
+        local success, error_message = pcall(listener.callback, event)
+
+        if not success then
+            print("WORLD EVENT " .. event.type .. " FROM " .. listener.mod .. " FAILED: " .. tostring(error_message) .. "!")
+        end
+    end
+end
@@ -33,7 +33,10 @@ crafter.register_block({
     draw_type = "normal",
     textures = {
         "sand.png"
-    }
+    },
+    groups = {
+        falling = 1,
+    },
 })
 
 crafter.register_block({
@@ -76,13 +79,67 @@ crafter.register_block({
     },
 })
 
+crafter.register_block({
+    name = "cobble",
+    draw_type = "normal",
+    textures = {
+        "cobble.png",
+    },
+})
+
+crafter.register_block({
+    name = "obsidian",
+    draw_type = "normal",
+    textures = {
+        "obsidian.png",
+    },
+})
+
+crafter.register_block({
+    name = "water",
+    draw_type = "liquid",
+    textures = {
+        "water.png",
+    },
+    liquid = {
+        viscosity = 5,
+        range = 7,
+        renewable = true,
+        interactions = {
+            lava = { source = "obsidian", flowing = "cobble" },
+        },
+    },
+})
+
+crafter.register_block({
+    name = "lava",
+    draw_type = "liquid",
+    textures = {
+        "lava.png",
+    },
+    light = 15,
+    liquid = {
+        viscosity = 30,
+        range = 3,
+        renewable = false,
+        interactions = {
+            water = "stone",
+        },
+    },
+})
+
 crafter.register_biome({
     name = "grass_lands",
 
+    -- Where the biome generates, mild and wet. Single numbers are points, wherever the climate is closest to this biome it generates.
+    -- Ranges like heat = {-1.0, 0.0} work too, so does elevation.
+    climate = {
+        heat = -0.2,
+        humidity = 0.3,
+    },
+
     biome_noise_params = {
-        heat_min = 0.0,
-        heat_max = 1.0,
-        -- Multiplies the output of the noise value. 2.0 means -2.0 to 2.0 for your heat values.
+        -- Multiplies the output of the noise value, the terrain rises and sinks with it.
         scale = 1.0,
         -- How often the terrain fluctuates.
         frequency = 0.02554,
@@ -151,10 +208,14 @@ crafter.register_biome({
 crafter.register_biome({
     name = "desert",
 
+    -- Hot and dry.
+    climate = {
+        heat = 0.5,
+        humidity = -0.4,
+    },
+
     biome_noise_params = {
-        heat_min = -1.0,
-        heat_max = 0.0,
-        -- Multiplies the output of the noise value. 2.0 means -2.0 to 2.0 for your heat values.
+        -- Multiplies the output of the noise value, the terrain rises and sinks with it.
         scale = 1.0,
         -- How often the terrain fluctuates.
         frequency = 0.02554,
@@ -218,4 +279,24 @@ crafter.register_biome({
 
     -- Defines if there is rain.
     rain = true,
-})
\ No newline at end of file
+})
+-- Boulders dot the grass lands, the ones near a chunk border grow into the next chunk.
+crafter.register_decoration({
+    name = "boulder",
+
+    biomes = { "grass_lands" },
+
+    place_on = { "grass" },
+
+    -- About one in every two chunks.
+    fill_ratio = 0.002,
+
+    blocks = {
+        { x = -1, y = 0, z = 0, name = "cobble" },
+        { x = 0, y = 0, z = 0, name = "cobble" },
+        { x = 1, y = 0, z = 0, name = "cobble" },
+        { x = 0, y = 0, z = -1, name = "cobble" },
+        { x = 0, y = 0, z = 1, name = "cobble" },
+        { x = 0, y = 1, z = 0, name = "cobble" },
+    },
+})
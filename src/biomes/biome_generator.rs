@@ -1,28 +1,47 @@
 
 use opensimplex_noise_rs::OpenSimplexNoise;
-use rayon::iter::{IntoParallelRefMutIterator, ParallelIterator, IndexedParallelIterator};
-
-use super::generation_component_system::GenerationComponentSystem;
-
-// Convertes u16 1D position into (u8,u8,u8) 3D tuple position
-fn index_to_pos ( i: usize ) -> (f64,f64,f64) {
-    ((i / 2048) as f64, ((i % 2048) % 128) as f64, ((i % 2048) / 128) as f64)
+use rayon::iter::{IntoParallelIterator, IntoParallelRefMutIterator, ParallelIterator, IndexedParallelIterator};
+
+use crate::world::{
+    chunk_storage::ChunkStorage,
+    world_descriptor::GeneratorType,
+    world::{
+        ChunkData,
+        CHUNK_WIDTH,
+        CHUNK_HEIGHT,
+        CHUNK_AREA,
+        MIN_Y,
+        pos_to_index,
+        index_to_pos,
+        column_index
+    }
+};
+
+use super::generation_component_system::{
+    GenerationComponentSystem,
+    ClimateSample
+};
+
+// chunk index -> global y, local x and z as floats for the noise
+fn index_to_noise_pos ( i: usize ) -> (f64,f64,f64) {
+    let (x, y, z) = index_to_pos(i);
+    (x as f64, (y as i32 + MIN_Y) as f64, z as f64)
 }
 
-fn calculate_y_height(noise_input: f64, base_height: f64, terrain_height_flux: f64) -> u32 {
-    ((noise_input * terrain_height_flux) as i32 + base_height as i32) as u32
+fn calculate_y_height(noise_input: f64, base_height: f64, terrain_height_flux: f64) -> i32 {
+    (noise_input * terrain_height_flux) as i32 + base_height as i32
 }
 
 fn calculate_depth(
     noise_input: f64,
     min: u8,
     max: u8
-) -> u32 {
+) -> i32 {
     ((noise_input *
     (max - min) as f64)
     + min as f64)
     .floor()
-    as u32
+    as i32
 }
 
 fn gen_3d(noise: &OpenSimplexNoise, x: f64, y: f64, z: f64, frequency: f64, scale: f64) -> f64 {
@@ -33,10 +52,247 @@ fn gen_2d(noise: &OpenSimplexNoise, x: f64, z: f64, frequency: f64, scale: f64)
     noise.eval_2d(x * frequency, z * frequency) * scale
 }
 
+/*
+the climate biomes are picked from, decorations have to agree with the terrain on which biome a column is in
+
+every value comes from a far off, differently sized part of the same noise so they don't follow each other around
+*/
+pub(crate) fn climate_sample(noise: &OpenSimplexNoise, x: f64, z: f64) -> ClimateSample {
+    ClimateSample {
+        heat: gen_2d(noise, x, z, 0.001, 1.0) as f32,
+        humidity: gen_2d(noise, x + 40_000.0, z - 40_000.0, 0.0013, 1.0) as f32,
+        elevation: gen_2d(noise, x - 80_000.0, z + 80_000.0, 0.0007, 1.0) as f32
+    }
+}
+
+
+// how far apart the biome samples of a blend are, in blocks
+const BLEND_SAMPLE_STEP: i32 = 2;
+
+// how far apart the climate is sampled for a chunk's blend, in blocks - it changes over hundreds of blocks so in between is interpolated
+const CLIMATE_GRID_STEP: i32 = 4;
+
+/*
+the climate around a chunk on a coarse grid, every blend sample of the chunk is interpolated from it
+
+the grid sits on multiples of CLIMATE_GRID_STEP in world space, so neighboring chunks interpolate
+from the same points and their blends agree along the border
+*/
+struct ClimateGrid {
+    // global position of the first sample
+    origin_x: i32,
+    origin_z: i32,
+    // samples on each side
+    size: usize,
+    samples: Vec<ClimateSample>
+}
+
+impl ClimateGrid {
+    // covers the chunk at pos_x,pos_z and everything radius blocks around it
+    fn new(noise: &OpenSimplexNoise, pos_x: i32, pos_z: i32, radius: i32) -> Self {
+
+        let origin_x: i32 = (pos_x * CHUNK_WIDTH - radius).div_euclid(CLIMATE_GRID_STEP) * CLIMATE_GRID_STEP;
+        let origin_z: i32 = (pos_z * CHUNK_WIDTH - radius).div_euclid(CLIMATE_GRID_STEP) * CLIMATE_GRID_STEP;
+
+        // the far edge plus the cell the origin was pulled back by
+        let size: usize = ((CHUNK_WIDTH + 2 * radius) / CLIMATE_GRID_STEP + 3) as usize;
+
+        let mut samples: Vec<ClimateSample> = Vec::with_capacity(size * size);
+
+        for grid_x in 0..size as i32 {
+            for grid_z in 0..size as i32 {
+                samples.push(climate_sample(
+                    noise,
+                    (origin_x + grid_x * CLIMATE_GRID_STEP) as f64,
+                    (origin_z + grid_z * CLIMATE_GRID_STEP) as f64
+                ));
+            }
+        }
+
+        Self {
+            origin_x,
+            origin_z,
+            size,
+            samples
+        }
+    }
+
+    // the climate at global x,z, bilinear between the four grid samples around it
+    fn get(&self, x: f64, z: f64) -> ClimateSample {
+
+        let grid_x: f64 = (x - self.origin_x as f64) / CLIMATE_GRID_STEP as f64;
+        let grid_z: f64 = (z - self.origin_z as f64) / CLIMATE_GRID_STEP as f64;
+
+        let cell_x: usize = (grid_x.floor().max(0.0) as usize).min(self.size - 2);
+        let cell_z: usize = (grid_z.floor().max(0.0) as usize).min(self.size - 2);
+
+        let (fraction_x, fraction_z) = ((grid_x - cell_x as f64) as f32, (grid_z - cell_z as f64) as f32);
+
+        let corner = | offset_x: usize, offset_z: usize | &self.samples[(cell_x + offset_x) * self.size + cell_z + offset_z];
+
+        let lerp = | value: fn(&ClimateSample) -> f32 | {
+            let near: f32 = value(corner(0, 0)) + (value(corner(0, 1)) - value(corner(0, 0))) * fraction_z;
+            let far: f32 = value(corner(1, 0)) + (value(corner(1, 1)) - value(corner(1, 0))) * fraction_z;
+            near + (far - near) * fraction_x
+        };
+
+        ClimateSample {
+            heat: lerp(|sample| sample.heat),
+            humidity: lerp(|sample| sample.humidity),
+            elevation: lerp(|sample| sample.elevation)
+        }
+    }
+}
+
+// a column's terrain once the biomes around it are blended in
+struct BlendedColumn {
+    // the biome the column is really in - stone, bedrock, ores and caves only come from it
+    biome: usize,
+    height: i32,
+    top_layer: u32,
+    top_layer_depth: i32,
+    bottom_layer: u32,
+    bottom_layer_depth: i32
+}
+
+/*
+how much every biome within the blend radius of x,z counts, the weights add up to 1.0
+
+samples closer to the column count for more, with a radius of 0 the column's own biome is all there is
+*/
+fn biome_weights(gcs: &GenerationComponentSystem, climate_grid: &ClimateGrid, x: f64, z: f64) -> Vec<(usize, f64)> {
+
+    let radius: i32 = gcs.get_biome_blend_radius();
+
+    let steps: i32 = radius / BLEND_SAMPLE_STEP;
+
+    let mut weights: Vec<(usize, f64)> = Vec::new();
+
+    let mut total: f64 = 0.0;
+
+    for step_x in -steps..=steps {
+        for step_z in -steps..=steps {
+
+            let (offset_x, offset_z) = ((step_x * BLEND_SAMPLE_STEP) as f64, (step_z * BLEND_SAMPLE_STEP) as f64);
+
+            let distance: f64 = (offset_x * offset_x + offset_z * offset_z).sqrt();
+
+            if distance > radius as f64 {
+                continue;
+            }
+
+            let weight: f64 = (1.0 - distance / (radius as f64 + 1.0)).powi(2);
+
+            let biome: usize = gcs.get_id_within_climate(&climate_grid.get(x + offset_x, z + offset_z));
+
+            match weights.iter_mut().find(|(id, _)| *id == biome) {
+                Some((_, biome_weight)) => *biome_weight += weight,
+                None => weights.push((biome, weight)),
+            }
+
+            total += weight;
+        }
+    }
+
+    for (_, weight) in weights.iter_mut() {
+        *weight /= total;
+    }
+
+    weights
+}
+
+// 0.0 to 1.0, the same for a column every time - picks which biome's surface a blended column gets
+fn column_dither(x: f64, z: f64) -> f64 {
+    let mut hash: u64 = (x as i64 as u64).wrapping_mul(0x9E3779B97F4A7C15) ^ (z as i64 as u64).wrapping_mul(0xC2B2AE3D27D4EB4F);
+    hash ^= hash >> 31;
+    hash = hash.wrapping_mul(0xBF58476D1CE4E5B9);
+    hash ^= hash >> 29;
+
+    (hash >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/*
+the terrain of the column at global x,z
+
+the height and layer depths are the weighted average of what every nearby biome would
+give the column, the surface layers are one nearby biome's picked by weight - so
+borders fade from one biome's blocks into the other's instead of cutting straight across
+*/
+fn blend_column(gcs: &GenerationComponentSystem, generator: GeneratorType, noise: &OpenSimplexNoise, climate_grid: &ClimateGrid, base_height: f64, x: f64, z: f64) -> BlendedColumn {
+
+    let weights: Vec<(usize, f64)> = biome_weights(gcs, climate_grid, x, z);
+
+    let mut height_offset: f64 = 0.0;
+    let mut top_layer_depth: f64 = 0.0;
+    let mut bottom_layer_depth: f64 = 0.0;
+
+    let dither: f64 = column_dither(x, z);
+    let mut surface_biome: Option<usize> = None;
+    let mut covered: f64 = 0.0;
+
+    for (biome, weight) in weights.iter() {
+
+        let (_, biome_noise_params, terrain_height_flux, _, biome_top_layer_depth, _, biome_bottom_layer_depth, ..) = gcs.get(*biome);
+
+        let (biome_scale, biome_frequency) = biome_noise_params.get();
+
+        // flat worlds keep every column at the base height
+        let terrain_2d_noise: f64 = match generator {
+            GeneratorType::Biomes => gen_2d(noise, x, z, biome_frequency as f64, biome_scale as f64),
+            GeneratorType::Flat => 0.0,
+        };
+
+        height_offset += terrain_2d_noise * terrain_height_flux as f64 * weight;
+
+        top_layer_depth += calculate_depth(terrain_2d_noise, biome_top_layer_depth.get_min(), biome_top_layer_depth.get_max() + 1) as f64 * weight;
+
+        bottom_layer_depth += calculate_depth(terrain_2d_noise, biome_bottom_layer_depth.get_min(), biome_bottom_layer_depth.get_max() + 1) as f64 * weight;
+
+        covered += weight;
+
+        if surface_biome.is_none() && dither < covered {
+            surface_biome = Some(*biome);
+        }
+    }
+
+    // rounding can leave the last sliver uncovered
+    let surface_biome: usize = surface_biome.unwrap_or(weights[weights.len() - 1].0);
+
+    let (_, _, _, top_layer, _, bottom_layer, ..) = gcs.get(surface_biome);
+
+    BlendedColumn {
+        // sampled exactly, decorations look the biome up the same way
+        biome: gcs.get_id_within_climate(&climate_sample(noise, x, z)),
+        height: calculate_y_height(height_offset, base_height, 1.0),
+        top_layer,
+        top_layer_depth: top_layer_depth.round() as i32,
+        bottom_layer,
+        bottom_layer_depth: bottom_layer_depth.round() as i32
+    }
+}
+
+// generates a whole chunk on its own, the world only gets it once it is finished
+pub fn generate_chunk(
+    gcs: &GenerationComponentSystem,
+    generator: GeneratorType,
+    pos_x: i32,
+    pos_z: i32,
+    noise: &OpenSimplexNoise
+) -> ChunkData {
+    let mut chunk: ChunkData = ChunkData::new();
+
+    let (block_data, heightmap) = chunk.get_blocks_and_heightmap_mut();
+
+    gen_biome(gcs, generator, block_data, heightmap, pos_x, pos_z, noise);
+
+    chunk
+}
 
 pub fn gen_biome(
     gcs: &GenerationComponentSystem,
-    block_data: &mut Vec<u32>,
+    generator: GeneratorType,
+    block_data: &mut ChunkStorage<u32>,
+    heightmap: &mut [u16],
     pos_x: i32,
     pos_z: i32,
     noise: &OpenSimplexNoise
@@ -49,74 +305,91 @@ pub fn gen_biome(
 
     // simplex_noise.set_frequency(terrain_frequency);
 
-    // the base height - if noise is always 0 the blocks will always generate to 0
-    let base_height = 90.0;
+    // the base height - if noise is always 0 the blocks will always generate to this
+    let base_height: f64 = gcs.get_base_height() as f64;
 
     // the amount of fluctuation the blocks can have from base height
     //let noise_multiplier = 50.0;
 
+    let climate_grid: ClimateGrid = ClimateGrid::new(noise, pos_x, pos_z, gcs.get_biome_blend_radius());
+
+    // the terrain of every column is worked out once, the blocks in it all share it
+    let columns: Vec<BlendedColumn> = (0..CHUNK_AREA).into_par_iter().map(|column| {
+        let (x, z) = (column / CHUNK_WIDTH as usize, column % CHUNK_WIDTH as usize);
+
+        blend_column(
+            gcs,
+            generator,
+            noise,
+            &climate_grid,
+            base_height,
+            (pos_x * CHUNK_WIDTH + x as i32) as f64,
+            (pos_z * CHUNK_WIDTH + z as i32) as f64
+        )
+    }).collect();
+
+    // generation runs in parallel over a flat array, it's packed into the chunk's palette at the end
+    let mut generated_data: Vec<u32> = vec![0; block_data.len()];
+
     // generate unmodified terrain
-    block_data.par_iter_mut().enumerate().for_each(| (index, value) | {
+    generated_data.par_iter_mut().enumerate().for_each(| (index, value) | {
 
-        let (mut x, y, mut z) = index_to_pos(index);
+        let (mut x, y, mut z) = index_to_noise_pos(index);
 
         x += pos_x as f64 * 16.0;
         z += pos_z as f64 * 16.0;
 
-        let biome_noise = gen_2d(&noise, x, z, 0.001, 1.0);
+        let (column_x, _, column_z) = index_to_pos(index);
+
+        let column: &BlendedColumn = &columns[column_index(column_x, column_z)];
+
+        let y_height: i32 = column.height;
+
+        let top_layer: u32 = column.top_layer;
+        let top_layer_depth_random: i32 = column.top_layer_depth;
 
+        let bottom_layer: u32 = column.bottom_layer;
+        let bottom_layer_depth_random: i32 = column.bottom_layer_depth;
+
+        // everything under the surface layers belongs to the column's own biome
         let (
             _,
-            biome_noise_params,
-            terrain_height_flux,
-            top_layer,
-            top_layer_depth,
-            bottom_layer,
-            bottom_layer_depth,
+            _,
+            _,
+            _,
+            _,
+            _,
+            _,
             stone_layer,
             bedrock_layer,
             biome_ores_option,
             caves,
             cave_noise_params,
-            rain,
-            snow
-        ) = gcs.get_within_noise(biome_noise as f32);
-
-        let (biome_heat_min, biome_heat_max, biome_scale, biome_frequency) = biome_noise_params.get();
+            _,
+            _
+        ) = gcs.get(column.biome);
 
         let (cave_heat_min, cave_heat_max, cave_scale, cave_frequency) = cave_noise_params.get();
 
-        let y_u32: u32 = y as u32;
+        let y_i32: i32 = y as i32;
 
-        let terrain_2d_noise = gen_2d(&noise, x, z, biome_frequency as f64, biome_scale as f64);
+        let caves: bool = caves && generator == GeneratorType::Biomes;
         //let terrain_3d_noise = gen_3d(&noise, x, y, z, biome_frequency as f64, biome_scale as f64);
 
         let cave_3d_noise = gen_3d(&noise, x, y, z, cave_frequency as f64, cave_scale as f64);
-                
-        let y_height = calculate_y_height(terrain_2d_noise, base_height, terrain_height_flux as f64);
 
         let bedrock_3d_noise = gen_3d(&noise, x, y, z, 1.5, 0.2);
-        
-
-        let top_layer_depth_random = calculate_depth(
-            terrain_2d_noise,
-            top_layer_depth.get_min(),
-            top_layer_depth.get_max() + 1
-        );
-
-        let bottom_layer_depth_random = calculate_depth(
-            terrain_2d_noise,
-            bottom_layer_depth.get_min(),
-            bottom_layer_depth.get_max() + 1
-        );
 
         // only calculate when inside possible parameter
-        if y_u32 <= y_height {
+        if y_i32 <= y_height {
 
             let mut bedrock = false;
 
-            if y_u32 <= 2 {
-                if y_u32 == 0 {
+            // bedrock sits on the floor of the world
+            let above_floor: i32 = y_i32 - MIN_Y;
+
+            if above_floor <= 2 {
+                if above_floor == 0 {
 
                     bedrock = true;
 
@@ -138,15 +411,15 @@ pub fn gen_biome(
                     *value = 0;
                 } else {
                     // top layer
-                    if y_u32 >= y_height - top_layer_depth_random {
+                    if y_i32 >= y_height - top_layer_depth_random {
                         *value = top_layer;
                     }
                     // bottom layer
-                    else if y_u32 < y_height - top_layer_depth_random &&  y_u32 >= y_height - top_layer_depth_random - bottom_layer_depth_random {
+                    else if y_i32 < y_height - top_layer_depth_random &&  y_i32 >= y_height - top_layer_depth_random - bottom_layer_depth_random {
                         *value = bottom_layer;
                     }
                     // stone layer
-                    else if y_u32 < y_height - top_layer_depth_random - bottom_layer_depth_random {
+                    else if y_i32 < y_height - top_layer_depth_random - bottom_layer_depth_random {
                         *value = stone_layer;
                     }
                 }
@@ -164,10 +437,8 @@ pub fn gen_biome(
 
                     let (heat_min, heat_max, _, _) = heat.get();
 
-                    let y_u32: u32 = y as u32;
-
                     // set to 0 for debugging
-                    if *value == stone_layer && y_u32 >= min_depth as u32 && y_u32 <= max_depth as u32 {
+                    if *value == stone_layer && y_i32 >= min_depth as i32 && y_i32 <= max_depth as i32 {
 
                             let ore_noise: f64 = gen_3d(&noise, x, y, z, frequency as f64, scale as f64);
 
@@ -180,4 +451,142 @@ pub fn gen_biome(
             None => (),
         }
     });
-}
\ No newline at end of file
+
+    // the highest block in each column, caves can carve the surface so this is read back out
+    for x in 0..CHUNK_WIDTH as usize {
+        for z in 0..CHUNK_WIDTH as usize {
+            heightmap[column_index(x, z)] = (0..CHUNK_HEIGHT as usize)
+                .rev()
+                .find(|y| generated_data[pos_to_index(x, *y, z)] != 0)
+                .unwrap_or(0) as u16;
+        }
+    }
+
+    *block_data = ChunkStorage::from_slice(&generated_data);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::biomes::generation_component_system::{
+        Climate,
+        ClimateRange,
+        LayerDepth,
+        NoiseParams,
+        TerrainNoiseParams
+    };
+
+    use super::*;
+
+    // a flat cold valley next to hot peaks that stand about 40 blocks over it
+    fn valley_and_peaks(blend_radius: i32) -> GenerationComponentSystem {
+        let mut gcs: GenerationComponentSystem = GenerationComponentSystem::new();
+
+        gcs.set_base_height(50);
+        gcs.set_biome_blend_radius(blend_radius);
+
+        for (name, terrain_height_flux, heat) in [("valley", 0, ClimateRange::new(-1.0, 0.0)), ("peaks", 60, ClimateRange::new(0.0, 1.0))] {
+            gcs.register_biome(
+                name.to_string(), TerrainNoiseParams::new(1.0, 0.004), Climate::new(Some(heat), None, None), terrain_height_flux, String::from("test"),
+                1, LayerDepth::new(1, 1), 1, LayerDepth::new(1, 1), 1, 1, None, false, NoiseParams::new(0.0, 0.0, 0.0, 0.0), false, false
+            );
+        }
+
+        gcs
+    }
+
+    // the surface height of every column of the chunks from chunk_x to chunk_x + 4 along z chunk 0, x then z
+    fn surface_heights(gcs: &GenerationComponentSystem, noise: &OpenSimplexNoise, chunk_x: i32) -> Vec<Vec<u16>> {
+        let mut heights: Vec<Vec<u16>> = Vec::new();
+
+        for pos_x in chunk_x..chunk_x + 5 {
+            let mut chunk: ChunkData = generate_chunk(gcs, GeneratorType::Biomes, pos_x, 0, noise);
+            let (_, heightmap) = chunk.get_blocks_and_heightmap_mut();
+
+            for x in 0..CHUNK_WIDTH as usize {
+                heights.push((0..CHUNK_WIDTH as usize).map(|z| heightmap[column_index(x, z)]).collect());
+            }
+        }
+
+        heights
+    }
+
+    // the biggest height difference between two columns next to each other
+    fn steepest_step(heights: &[Vec<u16>]) -> i32 {
+        let mut steepest: i32 = 0;
+
+        for x in 0..heights.len() {
+            for z in 0..heights[x].len() {
+                if x > 0 {
+                    steepest = steepest.max((heights[x][z] as i32 - heights[x - 1][z] as i32).abs());
+                }
+                if z > 0 {
+                    steepest = steepest.max((heights[x][z] as i32 - heights[x][z - 1] as i32).abs());
+                }
+            }
+        }
+
+        steepest
+    }
+
+    #[test]
+    fn the_climate_grid_follows_the_climate_noise() {
+        let noise: OpenSimplexNoise = OpenSimplexNoise::new(Some(3));
+
+        for (pos_x, pos_z, radius) in [(0, 0, 8), (-3, 5, 32), (7, -1, 0)] {
+            let climate_grid: ClimateGrid = ClimateGrid::new(&noise, pos_x, pos_z, radius);
+
+            let (min_x, min_z) = (pos_x * CHUNK_WIDTH - radius, pos_z * CHUNK_WIDTH - radius);
+
+            for x in min_x..min_x + CHUNK_WIDTH + 2 * radius {
+                for z in min_z..min_z + CHUNK_WIDTH + 2 * radius {
+                    let (exact, interpolated) = (climate_sample(&noise, x as f64, z as f64), climate_grid.get(x as f64, z as f64));
+
+                    assert!((exact.heat - interpolated.heat).abs() < 0.001);
+                    assert!((exact.humidity - interpolated.humidity).abs() < 0.001);
+                    assert!((exact.elevation - interpolated.elevation).abs() < 0.001);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn neighboring_climate_grids_agree_on_their_border() {
+        let noise: OpenSimplexNoise = OpenSimplexNoise::new(Some(3));
+
+        let (left, right) = (ClimateGrid::new(&noise, 0, 0, 8), ClimateGrid::new(&noise, 1, 0, 8));
+
+        for x in CHUNK_WIDTH - 8..CHUNK_WIDTH + 8 {
+            for z in -8..CHUNK_WIDTH + 8 {
+                let (a, b) = (left.get(x as f64, z as f64), right.get(x as f64, z as f64));
+
+                assert_eq!((a.heat, a.humidity, a.elevation), (b.heat, b.humidity, b.elevation));
+            }
+        }
+    }
+
+    #[test]
+    fn blending_turns_a_biome_border_cliff_into_a_slope() {
+        let noise: OpenSimplexNoise = OpenSimplexNoise::new(Some(3));
+
+        // the first place along the row where the heat crosses from one biome into the other
+        let border_x: i32 = (1..10_000)
+            .find(|x| (climate_sample(&noise, *x as f64, 8.0).heat < 0.0) != (climate_sample(&noise, (*x - 1) as f64, 8.0).heat < 0.0))
+            .unwrap();
+
+        let chunk_x: i32 = border_x.div_euclid(CHUNK_WIDTH) - 2;
+
+        let cliff: Vec<Vec<u16>> = surface_heights(&valley_and_peaks(0), &noise, chunk_x);
+        let slope: Vec<Vec<u16>> = surface_heights(&valley_and_peaks(16), &noise, chunk_x);
+
+        // without blending the peaks stand straight up out of the valley
+        assert!(steepest_step(&cliff) >= 30, "{}", steepest_step(&cliff));
+
+        // blended, the same climb is spread over the columns around the border
+        assert!(steepest_step(&slope) <= 6, "{}", steepest_step(&slope));
+
+        // and it still climbs all the way from the valley to the peaks
+        let lowest: u16 = slope.iter().flatten().copied().min().unwrap();
+        let highest: u16 = slope.iter().flatten().copied().max().unwrap();
+        assert!(highest - lowest >= 30, "{} {}", lowest, highest);
+    }
+}
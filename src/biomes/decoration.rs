@@ -0,0 +1,584 @@
+/*
+decorations - boulders, trees and anything else that sits on top of the terrain
+
+decorating is its own generation stage after gen_biome, a decoration is anchored on
+the surface of the chunk being generated but its blocks can reach up to a chunk
+past its border in every direction
+
+decorate_chunk only works out the writes, it never touches a world so it can run
+anywhere the terrain is - the DecorationQueue puts them into the world:
+writes into the chunk itself go in before the chunk does, writes into a chunk that
+is already in the world go straight in, the rest wait until their chunk is
+generated or loaded
+
+writes only go into air, and where two decorations want the same block the one
+with the lowest key (source chunk, then decoration, then block) wins - so what ends
+up in the world is the same no matter which order the chunks are generated in
+
+writes that are still waiting are saved with the dimension as block names, the
+claims that can still be taken over are saved next to them
+*/
+
+use std::{
+    collections::{
+        HashMap,
+        HashSet
+    },
+    fs,
+    io::{
+        self,
+        Error,
+        ErrorKind
+    },
+    path::Path
+};
+
+use opensimplex_noise_rs::OpenSimplexNoise;
+
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng
+};
+
+use crate::{
+    blocks::block_component_system::BlockComponentSystem,
+    world::{
+        lighting::set_block_lit,
+        world::{
+            World,
+            ChunkData,
+            CHUNK_WIDTH,
+            MIN_Y,
+            global_to_chunk,
+            pos_to_index
+        }
+    }
+};
+
+use super::{
+    biome_generator::climate_sample,
+    generation_component_system::GenerationComponentSystem
+};
+
+// how far a decoration's blocks can be from its anchor on x and z, this keeps every write within a chunk of its source
+pub const MAX_DECORATION_REACH: i32 = CHUNK_WIDTH;
+
+const PENDING_DECORATIONS_FILE: &str = "pending_decorations.txt";
+
+const DECORATION_CLAIMS_FILE: &str = "decoration_claims.txt";
+
+// (source chunk x, source chunk z, decoration, block) - lower keys win
+pub type DecorationKey = (i32, i32, u32, u32);
+
+pub struct Decoration {
+    name: String,
+    game_mod: String,
+    // biome names, empty means every biome of the dimension
+    biomes: Vec<String>,
+    // block ids the decoration can sit on, empty means anything
+    place_on: Vec<u32>,
+    // chance of a surface column getting one, 0.0 to 1.0
+    fill_ratio: f32,
+    // offset from the block above the surface, block id, rotation
+    blocks: Vec<((i32, i32, i32), u32, u8)>
+}
+
+impl Decoration {
+    pub fn new(name: String, game_mod: String, biomes: Vec<String>, place_on: Vec<u32>, fill_ratio: f32, blocks: Vec<((i32, i32, i32), u32, u8)>) -> Self {
+
+        // double check in case lua misses it
+        if blocks.iter().any(|((x, _, z), _, _)| x.abs() > MAX_DECORATION_REACH || z.abs() > MAX_DECORATION_REACH) {
+            panic!("DECORATION {} REACHES FURTHER THAN {} BLOCKS!", name, MAX_DECORATION_REACH);
+        }
+
+        Self {
+            name,
+            game_mod,
+            biomes,
+            place_on,
+            fill_ratio,
+            blocks
+        }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_mod(&self) -> &String {
+        &self.game_mod
+    }
+}
+
+// one block a decoration wants, global position
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct DecorationWrite {
+    x: i32,
+    y: i32,
+    z: i32,
+    id: u32,
+    rotation: u8,
+    key: DecorationKey
+}
+
+impl DecorationWrite {
+    pub fn get_pos(&self) -> (i32, i32, i32) {
+        (self.x, self.y, self.z)
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_key(&self) -> DecorationKey {
+        self.key
+    }
+}
+
+// fnv-1a over the chunk and decoration, stable between runs unlike the std hasher
+fn decoration_seed(seed: u64, pos_x: i32, pos_z: i32, decoration: usize) -> u64 {
+    [pos_x as u64, pos_z as u64, decoration as u64]
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .fold(0xcbf29ce484222325 ^ seed, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+        })
+}
+
+/*
+every block the decorations of the chunk at pos_x, pos_z want, chunk is its terrain straight out of gen_biome
+
+only the terrain of this chunk and the seed decide where decorations go
+*/
+pub fn decorate_chunk(gcs: &GenerationComponentSystem, seed: u64, pos_x: i32, pos_z: i32, chunk: &ChunkData, noise: &OpenSimplexNoise) -> Vec<DecorationWrite> {
+
+    let mut writes: Vec<DecorationWrite> = Vec::new();
+
+    for (decoration_index, decoration) in gcs.get_decorations().iter().enumerate() {
+
+        let mut rng: StdRng = StdRng::seed_from_u64(decoration_seed(seed, pos_x, pos_z, decoration_index));
+
+        let mut block_index: u32 = 0;
+
+        for x in 0..CHUNK_WIDTH as usize {
+            for z in 0..CHUNK_WIDTH as usize {
+
+                // every column rolls, so skipping one never changes the rolls of the next
+                let roll: f32 = rng.gen();
+
+                if roll >= decoration.fill_ratio {
+                    continue;
+                }
+
+                let height: usize = chunk.get_height(x, z);
+
+                let surface: u32 = chunk.get_block(x, height, z);
+
+                if surface == 0 || (!decoration.place_on.is_empty() && !decoration.place_on.contains(&surface)) {
+                    continue;
+                }
+
+                let global_x: i32 = pos_x * CHUNK_WIDTH + x as i32;
+                let global_z: i32 = pos_z * CHUNK_WIDTH + z as i32;
+
+                if !decoration.biomes.is_empty() {
+                    let (biome_name, ..) = gcs.get_within_climate(&climate_sample(noise, global_x as f64, global_z as f64));
+
+                    if !decoration.biomes.contains(biome_name) {
+                        continue;
+                    }
+                }
+
+                let anchor_y: i32 = MIN_Y + height as i32 + 1;
+
+                for ((offset_x, offset_y, offset_z), id, rotation) in decoration.blocks.iter() {
+                    writes.push(DecorationWrite {
+                        x: global_x + offset_x,
+                        y: anchor_y + offset_y,
+                        z: global_z + offset_z,
+                        id: *id,
+                        rotation: *rotation,
+                        key: (pos_x, pos_z, decoration_index as u32, block_index)
+                    });
+
+                    block_index += 1;
+                }
+            }
+        }
+    }
+
+    writes
+}
+
+// the decoration writes of a dimension that haven't found their way into the world yet
+pub struct DecorationQueue {
+    // chunk -> writes waiting for it
+    pending: HashMap<(i32, i32), Vec<DecorationWrite>>,
+    /*
+    chunk -> block index -> key of the write that put the block there
+
+    only kept while a neighbor of the chunk can still send it writes, so a lower key
+    that shows up late can still take the block over
+    */
+    claims: HashMap<(i32, i32), HashMap<usize, DecorationKey>>,
+    // pending or claims changed since they were last saved
+    modified: bool
+}
+
+impl DecorationQueue {
+    pub fn new() -> Self {
+        Self {
+            pending: HashMap::new(),
+            claims: HashMap::new(),
+            modified: false
+        }
+    }
+
+    // how many writes are waiting for a chunk
+    pub fn get_number_of_pending(&self) -> usize {
+        self.pending.values().map(|writes| writes.len()).sum()
+    }
+
+    /*
+    puts the writes of a freshly generated chunk where they belong, before the chunk goes into the world
+
+    its own writes and the ones that were waiting for it go straight into chunk,
+    writes into chunks in the world go in lit, the rest wait
+    */
+    pub fn decorate_generated_chunk(&mut self, world: &mut World, bcs: &BlockComponentSystem, pos_x: i32, pos_z: i32, chunk: &mut ChunkData, writes: Vec<DecorationWrite>) {
+
+        let mut own_writes: Vec<DecorationWrite> = self.pending.remove(&(pos_x, pos_z)).unwrap_or_default();
+
+        if !own_writes.is_empty() {
+            self.modified = true;
+        }
+
+        for write in writes {
+
+            let chunk_pos: (i32, i32) = match global_to_chunk(write.x, write.y, write.z) {
+                Some((chunk_pos, _)) => chunk_pos,
+                // out of the top or bottom of the world
+                None => continue,
+            };
+
+            if chunk_pos == (pos_x, pos_z) {
+                own_writes.push(write);
+            } else if world.chunk_exists(chunk_pos.0, chunk_pos.1) {
+                self.write_into_world(world, bcs, &write);
+            } else {
+                self.pending.entry(chunk_pos).or_default().push(write);
+                self.modified = true;
+            }
+        }
+
+        // lowest key first, whatever comes after can't take its blocks
+        own_writes.sort_by_key(|write| write.key);
+
+        let claims: &mut HashMap<usize, DecorationKey> = self.claims.entry((pos_x, pos_z)).or_default();
+
+        for write in own_writes {
+
+            let (_, (local_x, local_y, local_z)) = global_to_chunk(write.x, write.y, write.z).unwrap();
+
+            let index: usize = pos_to_index(local_x, local_y, local_z);
+
+            if chunk.get_block(local_x, local_y, local_z) != 0 {
+                continue;
+            }
+
+            chunk.set_block(local_x, local_y, local_z, write.id, write.rotation);
+            claims.insert(index, write.key);
+            self.modified = true;
+        }
+    }
+
+    // a chunk came off the disk, the writes that were waiting for it go in - the chunk gets lit afterwards
+    pub fn decorate_loaded_chunk(&mut self, world: &mut World, bcs: &BlockComponentSystem, pos_x: i32, pos_z: i32) {
+
+        let mut writes: Vec<DecorationWrite> = match self.pending.remove(&(pos_x, pos_z)) {
+            Some(writes) => writes,
+            None => return,
+        };
+
+        self.modified = true;
+
+        writes.sort_by_key(|write| write.key);
+
+        for write in writes {
+            self.write_into_world(world, bcs, &write);
+        }
+    }
+
+    // goes into air, or takes over a block from a decoration with a higher key
+    fn write_into_world(&mut self, world: &mut World, bcs: &BlockComponentSystem, write: &DecorationWrite) {
+
+        let (chunk_pos, (local_x, local_y, local_z)) = match global_to_chunk(write.x, write.y, write.z) {
+            Some(position) => position,
+            None => return,
+        };
+
+        let index: usize = pos_to_index(local_x, local_y, local_z);
+
+        let claims: &mut HashMap<usize, DecorationKey> = self.claims.entry(chunk_pos).or_default();
+
+        let wins: bool = match claims.get(&index) {
+            Some(claim) => write.key < *claim,
+            None => world.get_block(write.x, write.y, write.z) == Some(0),
+        };
+
+        if !wins {
+            return;
+        }
+
+        if set_block_lit(world, bcs, write.x, write.y, write.z, write.id) {
+            world.set_block_rotation(write.x, write.y, write.z, write.rotation);
+            claims.insert(index, write.key);
+            self.modified = true;
+        }
+    }
+
+    /*
+    once every chunk around one has been generated nothing can write into it anymore, its claims can go
+
+    is_generated has to count chunks that were unloaded too, not just the ones in the world
+    */
+    pub fn forget_finished<F: FnMut(i32, i32) -> bool>(&mut self, pos_x: i32, pos_z: i32, mut is_generated: F) {
+
+        let mut surrounded = | x: i32, z: i32 | (-1..=1).all(|offset_x| (-1..=1).all(|offset_z| is_generated(x + offset_x, z + offset_z)));
+
+        for offset_x in -1..=1 {
+            for offset_z in -1..=1 {
+                if surrounded(pos_x + offset_x, pos_z + offset_z) && self.claims.remove(&(pos_x + offset_x, pos_z + offset_z)).is_some() {
+                    self.modified = true;
+                }
+            }
+        }
+    }
+
+    /*
+    pending decorations file, one write per line:
+        x y z rotation source_x source_z decoration block name
+
+    decoration claims file, one claim per line:
+        chunk_x chunk_z index source_x source_z decoration block
+    */
+    pub fn load(directory: &Path, bcs: &BlockComponentSystem) -> io::Result<Self> {
+
+        let mut queue: DecorationQueue = DecorationQueue::new();
+
+        let mut unknown: HashSet<String> = HashSet::new();
+
+        for (line, parts) in read_lines(&directory.join(PENDING_DECORATIONS_FILE), 9)? {
+
+            let numbers: Vec<i64> = parse_numbers(&directory.join(PENDING_DECORATIONS_FILE), &line, &parts[..8])?;
+
+            // the mods changed since this was saved
+            let id: u32 = match bcs.find_id_of(&parts[8]) {
+                Some(id) => id,
+                None => {
+                    unknown.insert(parts[8].to_string());
+                    continue;
+                },
+            };
+
+            let write: DecorationWrite = DecorationWrite {
+                x: numbers[0] as i32,
+                y: numbers[1] as i32,
+                z: numbers[2] as i32,
+                id,
+                rotation: numbers[3] as u8,
+                key: (numbers[4] as i32, numbers[5] as i32, numbers[6] as u32, numbers[7] as u32)
+            };
+
+            if let Some((chunk_pos, _)) = global_to_chunk(write.x, write.y, write.z) {
+                queue.pending.entry(chunk_pos).or_default().push(write);
+            }
+        }
+
+        for name in unknown {
+            println!("PENDING DECORATIONS USE UNKNOWN BLOCK: {}! THEY WERE DROPPED!", name);
+        }
+
+        for (line, parts) in read_lines(&directory.join(DECORATION_CLAIMS_FILE), 7)? {
+
+            let numbers: Vec<i64> = parse_numbers(&directory.join(DECORATION_CLAIMS_FILE), &line, &parts)?;
+
+            queue.claims
+                .entry((numbers[0] as i32, numbers[1] as i32))
+                .or_default()
+                .insert(numbers[2] as usize, (numbers[3] as i32, numbers[4] as i32, numbers[5] as u32, numbers[6] as u32));
+        }
+
+        Ok(queue)
+    }
+
+    // writes the pending decorations and claims next to the dimension's regions, only if they changed
+    pub fn save(&mut self, directory: &Path, bcs: &BlockComponentSystem) -> io::Result<()> {
+
+        if !self.modified {
+            return Ok(());
+        }
+
+        let mut pending_text: String = String::new();
+
+        for write in self.pending.values().flatten() {
+            let (source_x, source_z, decoration, block) = write.key;
+            pending_text.push_str(&format!(
+                "{} {} {} {} {} {} {} {} {}\n",
+                write.x, write.y, write.z, write.rotation, source_x, source_z, decoration, block, bcs.get_name(write.id)
+            ));
+        }
+
+        write_or_remove(&directory.join(PENDING_DECORATIONS_FILE), pending_text)?;
+
+        let mut claims_text: String = String::new();
+
+        for ((chunk_x, chunk_z), claims) in self.claims.iter() {
+            for (index, (source_x, source_z, decoration, block)) in claims.iter() {
+                claims_text.push_str(&format!(
+                    "{} {} {} {} {} {} {}\n",
+                    chunk_x, chunk_z, index, source_x, source_z, decoration, block
+                ));
+            }
+        }
+
+        write_or_remove(&directory.join(DECORATION_CLAIMS_FILE), claims_text)?;
+
+        self.modified = false;
+
+        Ok(())
+    }
+}
+
+impl Default for DecorationQueue {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// the lines of a queue file split on whitespace, every one has to have parts parts - a missing file has no lines
+fn read_lines(path: &Path, parts: usize) -> io::Result<Vec<(String, Vec<String>)>> {
+
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let mut lines: Vec<(String, Vec<String>)> = Vec::new();
+
+    for line in fs::read_to_string(path)?.lines() {
+
+        let line_parts: Vec<String> = line.split_whitespace().map(|part| part.to_string()).collect();
+
+        if line_parts.len() != parts {
+            return Err(Error::new(ErrorKind::InvalidData, format!("{} HAS A BROKEN LINE: {}", path.display(), line)));
+        }
+
+        lines.push((line.to_string(), line_parts));
+    }
+
+    Ok(lines)
+}
+
+fn parse_numbers(path: &Path, line: &str, parts: &[String]) -> io::Result<Vec<i64>> {
+    match parts.iter().map(|part| part.parse::<i64>()).collect() {
+        Ok(numbers) => Ok(numbers),
+        Err(_) => Err(Error::new(ErrorKind::InvalidData, format!("{} HAS A BROKEN LINE: {}", path.display(), line))),
+    }
+}
+
+// written next to the real file and swapped in, a crash never leaves half a file - nothing to write removes it
+fn write_or_remove(path: &Path, text: String) -> io::Result<()> {
+
+    if text.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        return Ok(());
+    }
+
+    let temporary_path = path.with_extension("txt.tmp");
+    fs::write(&temporary_path, text)?;
+    fs::rename(&temporary_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::block_component_system::{
+        DrawType,
+        RotationType
+    };
+
+    use super::*;
+
+    // a stone floor at y 10
+    fn terrain(stone: u32) -> ChunkData {
+        let mut chunk: ChunkData = ChunkData::new();
+
+        for x in 0..CHUNK_WIDTH as usize {
+            for z in 0..CHUNK_WIDTH as usize {
+                chunk.set_block(x, (10 - MIN_Y) as usize, z, stone, 0);
+            }
+        }
+
+        chunk
+    }
+
+    // generates and decorates the chunks in order, the way a dimension does
+    fn generate(gcs: &GenerationComponentSystem, bcs: &BlockComponentSystem, stone: u32, order: &[(i32, i32)]) -> World {
+        let noise: OpenSimplexNoise = OpenSimplexNoise::new(Some(0));
+        let mut world: World = World::initialize();
+        let mut queue: DecorationQueue = DecorationQueue::new();
+
+        for (x, z) in order {
+            let mut chunk: ChunkData = terrain(stone);
+            let writes: Vec<DecorationWrite> = decorate_chunk(gcs, 5, *x, *z, &chunk, &noise);
+
+            queue.decorate_generated_chunk(&mut world, bcs, *x, *z, &mut chunk, writes);
+            world.insert_generated_chunk(*x, *z, chunk);
+        }
+
+        world
+    }
+
+    #[test]
+    fn decorations_across_a_border_are_the_same_in_either_generation_order() {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+        let stone: u32 = bcs.register_block(String::from("test"), String::from("stone"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+        let log: u32 = bcs.register_block(String::from("test"), String::from("log"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+        let leaves: u32 = bcs.register_block(String::from("test"), String::from("leaves"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+
+        // trees with wide crowns and boulders, both sprawl over the chunk borders and into each other
+        let mut tree: Vec<((i32, i32, i32), u32, u8)> = (0..3).map(|y| ((0, y, 0), log, 0)).collect();
+        for x in -3..=3 {
+            for z in -3..=3 {
+                tree.push(((x, 3, z), leaves, 0));
+            }
+        }
+        let boulder: Vec<((i32, i32, i32), u32, u8)> = (-2..=2).flat_map(|x| (0..4).map(move |y| ((x, y, 1), stone, 1))).collect();
+
+        let mut gcs: GenerationComponentSystem = GenerationComponentSystem::new();
+        gcs.register_decoration(Decoration::new(String::from("tree"), String::from("test"), vec![], vec![stone], 0.1, tree));
+        gcs.register_decoration(Decoration::new(String::from("boulder"), String::from("test"), vec![], vec![], 0.1, boulder));
+
+        let forward: World = generate(&gcs, &bcs, stone, &[(0, 0), (1, 0)]);
+        let backward: World = generate(&gcs, &bcs, stone, &[(1, 0), (0, 0)]);
+
+        let mut decorated: [bool; 2] = [false, false];
+
+        for x in 0..2 * CHUNK_WIDTH {
+            for z in 0..CHUNK_WIDTH {
+                for y in 11..16 {
+                    assert_eq!(forward.get_block(x, y, z), backward.get_block(x, y, z), "BLOCKS DIFFER AT {} {} {}!", x, y, z);
+                    assert_eq!(forward.get_block_rotation(x, y, z), backward.get_block_rotation(x, y, z), "ROTATIONS DIFFER AT {} {} {}!", x, y, z);
+
+                    if forward.get_block(x, y, z) != Some(0) {
+                        decorated[(x / CHUNK_WIDTH) as usize] = true;
+                    }
+                }
+            }
+        }
+
+        assert_eq!(decorated, [true, true]);
+    }
+}
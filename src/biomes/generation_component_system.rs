@@ -1,4 +1,7 @@
-use rayon::iter::{IntoParallelRefIterator, ParallelIterator, IndexedParallelIterator};
+use super::decoration::Decoration;
+
+// every column samples the biomes this far around it, past this generation slows down for little gain
+pub const MAX_BIOME_BLEND_RADIUS: i32 = 32;
 
 pub struct LayerDepth {
     min: u8,
@@ -66,6 +69,109 @@ impl NoiseParams {
     }
 }
 
+// how a biome's terrain rolls, the noise is scaled by scale and sampled at frequency
+pub struct TerrainNoiseParams {
+    scale: f32,
+    frequency: f32
+}
+
+impl TerrainNoiseParams {
+    pub fn new(scale: f32, frequency: f32) -> Self {
+        Self {
+            scale,
+            frequency
+        }
+    }
+
+    pub fn get(&self) -> (f32, f32) {
+        (self.scale, self.frequency)
+    }
+
+    pub fn get_scale(&self) -> f32 {
+        self.scale
+    }
+
+    pub fn get_frequency(&self) -> f32 {
+        self.frequency
+    }
+}
+
+// the climate at one spot of the world, every value is roughly -1.0 to 1.0
+#[derive(Clone, Copy)]
+pub struct ClimateSample {
+    pub heat: f32,
+    pub humidity: f32,
+    pub elevation: f32
+}
+
+// a stretch of one climate value a biome wants, a single point when min and max are the same
+pub struct ClimateRange {
+    min: f32,
+    max: f32
+}
+
+impl ClimateRange {
+    pub fn new(min: f32, max: f32) -> Self {
+        Self {
+            min,
+            max
+        }
+    }
+
+    pub fn get_min(&self) -> f32 {
+        self.min
+    }
+
+    pub fn get_max(&self) -> f32 {
+        self.max
+    }
+
+    // how far the value is outside of the range, 0.0 when it's inside
+    pub fn distance(&self, value: f32) -> f32 {
+        (self.min - value).max(value - self.max).max(0.0)
+    }
+
+    fn distance_to_center(&self, value: f32) -> f32 {
+        (value - (self.min + self.max) / 2.0).abs()
+    }
+}
+
+// the climate a biome generates in, None means the biome doesn't care about that value
+pub struct Climate {
+    heat: Option<ClimateRange>,
+    humidity: Option<ClimateRange>,
+    elevation: Option<ClimateRange>
+}
+
+impl Climate {
+    pub fn new(heat: Option<ClimateRange>, humidity: Option<ClimateRange>, elevation: Option<ClimateRange>) -> Self {
+        Self {
+            heat,
+            humidity,
+            elevation
+        }
+    }
+
+    fn ranges_with<'a>(&'a self, sample: &ClimateSample) -> impl Iterator<Item = (&'a ClimateRange, f32)> {
+        [
+            (&self.heat, sample.heat),
+            (&self.humidity, sample.humidity),
+            (&self.elevation, sample.elevation)
+        ]
+        .into_iter()
+        .filter_map(|(range, value)| range.as_ref().map(|range| (range, value)))
+    }
+
+    // squared distance from the sample to the closest climate the biome generates in, 0.0 when the sample is within every range
+    pub fn distance_squared(&self, sample: &ClimateSample) -> f32 {
+        self.ranges_with(sample).map(|(range, value)| range.distance(value).powi(2)).sum()
+    }
+
+    // squared distance from the sample to the middle of the biome's climate, settles overlapping ranges
+    pub fn center_distance_squared(&self, sample: &ClimateSample) -> f32 {
+        self.ranges_with(sample).map(|(range, value)| range.distance_to_center(value).powi(2)).sum()
+    }
+}
 
 pub struct BiomeOres {
     // held as block ID
@@ -113,7 +219,10 @@ pub struct GenerationComponentSystem {
 
     id: Vec<u32>,
 
-    biome_noise_params: Vec<NoiseParams>,
+    biome_noise_params: Vec<TerrainNoiseParams>,
+
+    // where the biome generates
+    climate: Vec<Climate>,
 
     // how high or low the terrain can fluctuate
     terrain_height_flux: Vec<u8>,
@@ -144,7 +253,16 @@ pub struct GenerationComponentSystem {
     rain: Vec<bool>,
 
     // defines if there is snow
-    snow: Vec<bool>
+    snow: Vec<bool>,
+
+    // the height terrain sits at when the noise is 0, one per dimension so it's not per biome
+    base_height: i32,
+
+    // how far in blocks the biomes around a column blend into its terrain, 0 gives hard borders
+    biome_blend_radius: i32,
+
+    // in registration order, the order is part of what makes decoration deterministic
+    decorations: Vec<Decoration>
 
 }
 
@@ -153,6 +271,7 @@ impl GenerationComponentSystem {
         Self {
             id: Vec::new(),
             biome_noise_params: Vec::new(),
+            climate: Vec::new(),
             terrain_height_flux: Vec::new(),
             game_mod: Vec::new(),
             name: Vec::new(),
@@ -167,7 +286,42 @@ impl GenerationComponentSystem {
             cave_noise_params: Vec::new(),
             rain: Vec::new(),
             snow: Vec::new(),
+            base_height: 90,
+            biome_blend_radius: 8,
+            decorations: Vec::new()
+        }
+    }
+
+    pub fn set_base_height(&mut self, base_height: i32) {
+        self.base_height = base_height;
+    }
+
+    pub fn get_base_height(&self) -> i32 {
+        self.base_height
+    }
+
+    pub fn set_biome_blend_radius(&mut self, biome_blend_radius: i32) {
+        // double check in case lua misses it
+        if !(0..=MAX_BIOME_BLEND_RADIUS).contains(&biome_blend_radius) {
+            panic!("BIOME BLEND RADIUS {} IS OUTSIDE OF 0 TO {}!", biome_blend_radius, MAX_BIOME_BLEND_RADIUS);
         }
+        self.biome_blend_radius = biome_blend_radius;
+    }
+
+    pub fn get_biome_blend_radius(&self) -> i32 {
+        self.biome_blend_radius
+    }
+
+    pub fn register_decoration(&mut self, decoration: Decoration) {
+        self.decorations.push(decoration);
+    }
+
+    pub fn get_decorations(&self) -> &Vec<Decoration> {
+        &self.decorations
+    }
+
+    pub fn get_number_of_biomes(&self) -> usize {
+        self.id.len()
     }
 
     pub fn register_biome(
@@ -175,7 +329,9 @@ impl GenerationComponentSystem {
 
         name: String,
 
-        biome_noise_params: NoiseParams,
+        biome_noise_params: TerrainNoiseParams,
+
+        climate: Climate,
 
         terrain_height_flux: u8,
 
@@ -209,6 +365,8 @@ impl GenerationComponentSystem {
 
         self.biome_noise_params.push(biome_noise_params);
 
+        self.climate.push(climate);
+
         self.terrain_height_flux.push(terrain_height_flux);
 
         self.game_mod.push(game_mod);
@@ -241,7 +399,7 @@ impl GenerationComponentSystem {
     // this is debug
     // in production this will search by heatmap of 2D
     // this is also a mess
-    pub fn get(&self, id: usize) -> (&String, &NoiseParams, u8, u32, &LayerDepth, u32, &LayerDepth, u32, u32, &Option<BiomeOres>, bool, &NoiseParams, bool, bool) {
+    pub fn get(&self, id: usize) -> (&String, &TerrainNoiseParams, u8, u32, &LayerDepth, u32, &LayerDepth, u32, u32, &Option<BiomeOres>, bool, &NoiseParams, bool, bool) {
         (
             &self.name[id],
 
@@ -271,20 +429,95 @@ impl GenerationComponentSystem {
         )
     }
 
-    pub fn get_within_noise(&self, noise: f32) -> (&String, &NoiseParams, u8, u32, &LayerDepth, u32, &LayerDepth, u32, u32, &Option<BiomeOres>, bool, &NoiseParams, bool, bool) {
+    pub fn get_within_climate(&self, sample: &ClimateSample) -> (&String, &TerrainNoiseParams, u8, u32, &LayerDepth, u32, &LayerDepth, u32, u32, &Option<BiomeOres>, bool, &NoiseParams, bool, bool) {
+        self.get(self.get_id_within_climate(sample))
+    }
 
-        let value: usize;
+    /*
+    the id of the biome the climate sample falls into
+
+    a sample inside a biome's ranges is 0.0 away from it, so ranges match like ranges and single
+    points match whatever is nearest - where ranges overlap the biome with its middle closest wins,
+    anything still tied goes by name so the order lua registered the biomes in never matters
+    */
+    pub fn get_id_within_climate(&self, sample: &ClimateSample) -> usize {
+        (0..self.id.len())
+            .min_by(|a, b| {
+                let (climate_a, climate_b) = (&self.climate[*a], &self.climate[*b]);
+
+                climate_a.distance_squared(sample).total_cmp(&climate_b.distance_squared(sample))
+                    .then(climate_a.center_distance_squared(sample).total_cmp(&climate_b.center_distance_squared(sample)))
+                    .then(self.name[*a].cmp(&self.name[*b]))
+            })
+            // whatever is first
+            .unwrap_or(0)
+    }
+}
 
-        let gotten_biome=  self.biome_noise_params.par_iter().enumerate().find_first(|value|{
-            value.1.in_range(noise)
-        });
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        match gotten_biome {
-            Some(biome) => value = biome.0,
-            // whatever is first
-            None => value = 0,
+    fn add_biome(gcs: &mut GenerationComponentSystem, name: &str, climate: Climate) {
+        gcs.register_biome(
+            name.to_string(), TerrainNoiseParams::new(1.0, 0.01), climate, 0, String::from("test"),
+            1, LayerDepth::new(1, 1), 1, LayerDepth::new(1, 1), 1, 1, None, false, NoiseParams::new(0.0, 0.0, 0.0, 0.0), false, false
+        );
+    }
+
+    fn heat(min: f32, max: f32) -> Climate {
+        Climate::new(Some(ClimateRange::new(min, max)), None, None)
+    }
+
+    fn sample(heat: f32, humidity: f32) -> ClimateSample {
+        ClimateSample {
+            heat,
+            humidity,
+            elevation: 0.0
         }
+    }
 
-        self.get(value)
+    fn biome_at(gcs: &GenerationComponentSystem, climate_sample: ClimateSample) -> &String {
+        gcs.get(gcs.get_id_within_climate(&climate_sample)).0
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn the_nearest_climate_wins() {
+        let mut gcs: GenerationComponentSystem = GenerationComponentSystem::new();
+
+        add_biome(&mut gcs, "cold", heat(-1.0, -0.5));
+        add_biome(&mut gcs, "hot", heat(0.5, 1.0));
+        add_biome(&mut gcs, "swamp", Climate::new(Some(ClimateRange::new(0.0, 0.0)), Some(ClimateRange::new(0.8, 0.8)), None));
+
+        assert_eq!(biome_at(&gcs, sample(-0.7, 0.0)), "cold");
+        assert_eq!(biome_at(&gcs, sample(0.2, 0.0)), "hot");
+        assert_eq!(biome_at(&gcs, sample(-0.2, 0.0)), "cold");
+        // a single point matches whatever is nearest to it
+        assert_eq!(biome_at(&gcs, sample(0.1, 0.7)), "swamp");
+    }
+
+    #[test]
+    fn overlapping_ranges_go_to_the_closest_middle() {
+        let mut gcs: GenerationComponentSystem = GenerationComponentSystem::new();
+
+        add_biome(&mut gcs, "wide", heat(-1.0, 1.0));
+        add_biome(&mut gcs, "narrow", heat(0.2, 0.4));
+
+        assert_eq!(biome_at(&gcs, sample(0.3, 0.0)), "narrow");
+        assert_eq!(biome_at(&gcs, sample(-0.3, 0.0)), "wide");
+    }
+
+    #[test]
+    fn ties_go_by_name_whatever_the_registration_order() {
+        for names in [["beta", "alpha"], ["alpha", "beta"]] {
+            let mut gcs: GenerationComponentSystem = GenerationComponentSystem::new();
+
+            for name in names {
+                add_biome(&mut gcs, name, heat(0.0, 0.5));
+            }
+
+            assert_eq!(biome_at(&gcs, sample(0.1, 0.0)), "alpha");
+            assert_eq!(biome_at(&gcs, sample(0.9, 0.0)), "alpha");
+        }
+    }
+}
@@ -0,0 +1,436 @@
+/*
+the chunk generation service - generates chunks off the main thread, and reads saved ones back
+
+chunks are requested by position, a worker on the generation pool picks up the
+closest waiting request to the center (the camera) every time it is free, so moving
+the center reprioritizes everything that is still waiting
+
+a worker runs gen_biome and works out the chunk's decorations, or decompresses the
+chunk if it was saved, the finished chunk comes back through a channel - putting it
+into the world (decoration writes into other chunks, lighting) stays on the main thread,
+its mesh is built back on the pool by the chunk mesh service
+
+requests that end up out of range of the center are cancelled, the ones that were
+already being generated are thrown away when they come back
+*/
+
+use std::{
+    collections::{
+        HashMap,
+        HashSet
+    },
+    io,
+    sync::{
+        Arc,
+        Mutex,
+        mpsc::{
+            self,
+            Sender,
+            Receiver
+        }
+    },
+    thread
+};
+
+use opensimplex_noise_rs::OpenSimplexNoise;
+
+use rayon::{
+    ThreadPool,
+    ThreadPoolBuilder
+};
+
+use crate::world::{
+    world::ChunkData,
+    world_descriptor::GeneratorType,
+    world_save::read_saved_chunk
+};
+
+use super::{
+    biome_generator::generate_chunk,
+    decoration::{
+        DecorationWrite,
+        decorate_chunk
+    },
+    generation_component_system::GenerationComponentSystem
+};
+
+// one pool for every dimension's generation and meshing, it leaves a core for the main thread
+pub fn new_generation_pool() -> Arc<ThreadPool> {
+    let threads: usize = thread::available_parallelism()
+        .map(|threads| threads.get().saturating_sub(1).max(1))
+        .unwrap_or(1);
+
+    Arc::new(
+        ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("chunk generation {}", index))
+            .build()
+            .expect("FAILED TO BUILD THE CHUNK GENERATION POOL!")
+    )
+}
+
+// a chunk straight out of the generator with the decoration writes it wants
+pub struct GeneratedChunk {
+    x: i32,
+    z: i32,
+    chunk: ChunkData,
+    writes: Vec<DecorationWrite>
+}
+
+impl GeneratedChunk {
+    pub fn get_pos(&self) -> (i32, i32) {
+        (self.x, self.z)
+    }
+
+    pub fn unpack(self) -> (ChunkData, Vec<DecorationWrite>) {
+        (self.chunk, self.writes)
+    }
+}
+
+// what comes back from a worker
+pub enum FinishedChunk {
+    Generated(GeneratedChunk),
+    // a saved chunk read back from its compressed data, the error if it was broken
+    Loaded(i32, i32, io::Result<ChunkData>)
+}
+
+impl FinishedChunk {
+    pub fn get_pos(&self) -> (i32, i32) {
+        match self {
+            FinishedChunk::Generated(generated_chunk) => generated_chunk.get_pos(),
+            FinishedChunk::Loaded(x, z, _) => (*x, *z),
+        }
+    }
+}
+
+// everything a worker needs to generate a chunk of the dimension, it never changes once the dimension is open
+struct GenerationContext {
+    gcs: Arc<GenerationComponentSystem>,
+    generator: GeneratorType,
+    seed: u64,
+    noise: OpenSimplexNoise
+}
+
+impl GenerationContext {
+    fn generate(&self, x: i32, z: i32) -> GeneratedChunk {
+        let chunk: ChunkData = generate_chunk(&self.gcs, self.generator, x, z, &self.noise);
+
+        let writes: Vec<DecorationWrite> = decorate_chunk(&self.gcs, self.seed, x, z, &chunk, &self.noise);
+
+        GeneratedChunk {
+            x,
+            z,
+            chunk,
+            writes
+        }
+    }
+}
+
+// (position, compressed chunk) - a request taken off the waiting list, the chunk is None if it's generated
+type WaitingRequest = ((i32, i32), Option<Vec<u8>>);
+
+// the requests the workers share with the main thread
+struct GenerationRequests {
+    // waiting for a worker, with the compressed chunk if it was saved - None is generated
+    waiting: HashMap<(i32, i32), Option<Vec<u8>>>,
+    // the chunk the camera is in
+    center: (i32, i32),
+    // in chunks, None means everything is in range and a negative range means nothing is
+    range: Option<i32>
+}
+
+impl GenerationRequests {
+    fn distance_squared(&self, x: i32, z: i32) -> i64 {
+        let (offset_x, offset_z) = ((x - self.center.0) as i64, (z - self.center.1) as i64);
+        offset_x * offset_x + offset_z * offset_z
+    }
+
+    fn in_range(&self, x: i32, z: i32) -> bool {
+        match self.range {
+            Some(range) => range >= 0 && self.distance_squared(x, z) <= range as i64 * range as i64,
+            None => true,
+        }
+    }
+
+    // the closest waiting request, ties go to the lowest position so the order never depends on the hash set
+    fn take_closest(&mut self) -> Option<WaitingRequest> {
+        let closest: (i32, i32) = *self.waiting.keys().min_by_key(|(x, z)| (self.distance_squared(*x, *z), *x, *z))?;
+
+        self.waiting.remove_entry(&closest)
+    }
+}
+
+pub struct ChunkGenerationService {
+    pool: Arc<ThreadPool>,
+    context: Arc<GenerationContext>,
+    requests: Arc<Mutex<GenerationRequests>>,
+    sender: Sender<FinishedChunk>,
+    receiver: Receiver<FinishedChunk>,
+    // waiting or being generated, everything that hasn't come back yet
+    requested: HashSet<(i32, i32)>
+}
+
+impl ChunkGenerationService {
+    pub fn new(pool: Arc<ThreadPool>, gcs: Arc<GenerationComponentSystem>, generator: GeneratorType, seed: u64) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            pool,
+            context: Arc::new(GenerationContext {
+                gcs,
+                generator,
+                seed,
+                noise: OpenSimplexNoise::new(Some(seed as i64))
+            }),
+            requests: Arc::new(Mutex::new(GenerationRequests {
+                waiting: HashMap::new(),
+                center: (0, 0),
+                range: None
+            })),
+            sender,
+            receiver,
+            requested: HashSet::new()
+        }
+    }
+
+    // generates a chunk right here on the calling thread, it never goes through the pool
+    pub fn generate_now(&self, x: i32, z: i32) -> GeneratedChunk {
+        self.context.generate(x, z)
+    }
+
+    pub fn is_requested(&self, x: i32, z: i32) -> bool {
+        self.requested.contains(&(x, z))
+    }
+
+    // how many chunks haven't come back yet
+    pub fn get_number_of_requested(&self) -> usize {
+        self.requested.len()
+    }
+
+    // asks the pool to generate the chunk at x,z - nothing happens if it was already asked for or is out of range
+    pub fn request(&mut self, x: i32, z: i32) {
+        self.queue(x, z, None);
+    }
+
+    // asks the pool to read back the chunk at x,z from its compressed data, the same way as request
+    pub fn request_saved(&mut self, x: i32, z: i32, compressed: Vec<u8>) {
+        self.queue(x, z, Some(compressed));
+    }
+
+    fn queue(&mut self, x: i32, z: i32, saved: Option<Vec<u8>>) {
+
+        if self.requested.contains(&(x, z)) {
+            return;
+        }
+
+        {
+            let mut requests = self.requests.lock().unwrap();
+
+            if !requests.in_range(x, z) {
+                return;
+            }
+
+            requests.waiting.insert((x, z), saved);
+        }
+
+        self.requested.insert((x, z));
+
+        let context: Arc<GenerationContext> = self.context.clone();
+        let requests: Arc<Mutex<GenerationRequests>> = self.requests.clone();
+        let sender: Sender<FinishedChunk> = self.sender.clone();
+
+        /*
+        every request spawns one job, but a job doesn't have to generate the chunk it was spawned for -
+        it takes whatever is closest when it runs, there's never fewer jobs than chunks waiting
+        */
+        self.pool.spawn(move || {
+            let closest: Option<WaitingRequest> = requests.lock().unwrap().take_closest();
+
+            // None means it was cancelled
+            if let Some(((x, z), saved)) = closest {
+                let finished_chunk: FinishedChunk = match saved {
+                    Some(compressed) => FinishedChunk::Loaded(x, z, read_saved_chunk(x, z, &compressed)),
+                    None => FinishedChunk::Generated(context.generate(x, z)),
+                };
+
+                // the service is gone, nobody wants the chunk anymore
+                let _ = sender.send(finished_chunk);
+            }
+        });
+    }
+
+    // moves the center the requests are prioritized around, waiting requests further than range chunks away are cancelled
+    pub fn set_center(&mut self, x: i32, z: i32, range: i32) {
+        let mut requests = self.requests.lock().unwrap();
+
+        requests.center = (x, z);
+        requests.range = Some(range);
+
+        let cancelled: Vec<(i32, i32)> = requests.waiting
+            .keys()
+            .filter(|(x, z)| !requests.in_range(*x, *z))
+            .copied()
+            .collect();
+
+        for position in cancelled {
+            requests.waiting.remove(&position);
+            self.requested.remove(&position);
+        }
+    }
+
+    // cancels everything that's waiting and throws away everything that's being generated, until the center is set again
+    pub fn cancel_all(&mut self) {
+        let mut requests = self.requests.lock().unwrap();
+
+        requests.range = Some(-1);
+
+        for (position, _) in requests.waiting.drain() {
+            self.requested.remove(&position);
+        }
+    }
+
+    // the next finished chunk that's still in range, None if there isn't one yet
+    pub fn try_receive(&mut self) -> Option<FinishedChunk> {
+        loop {
+            let finished_chunk: FinishedChunk = self.receiver.try_recv().ok()?;
+
+            let (x, z) = finished_chunk.get_pos();
+
+            self.requested.remove(&(x, z));
+
+            // it went out of range while it was being generated
+            if self.requests.lock().unwrap().in_range(x, z) {
+                return Some(finished_chunk);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::mpsc::SyncSender,
+        time::Duration
+    };
+
+    use super::{
+        super::generation_component_system::{
+            Climate,
+            LayerDepth,
+            NoiseParams,
+            TerrainNoiseParams
+        },
+        *
+    };
+
+    // one flat biome of block 1, enough for the workers to have something to generate
+    fn test_service() -> (ChunkGenerationService, SyncSender<()>) {
+        let mut gcs: GenerationComponentSystem = GenerationComponentSystem::new();
+
+        gcs.register_biome(
+            String::from("plains"), TerrainNoiseParams::new(10.0, 0.01), Climate::new(None, None, None), 0, String::from("test"),
+            1, LayerDepth::new(1, 1), 1, LayerDepth::new(1, 1), 1, 1, None, false, NoiseParams::new(0.0, 0.0, 0.0, 0.0), false, false
+        );
+
+        let pool: Arc<ThreadPool> = Arc::new(ThreadPoolBuilder::new().num_threads(1).build().unwrap());
+
+        // the only worker waits here until the test lets it go, so requests pile up
+        let (gate, gate_receiver) = mpsc::sync_channel::<()>(0);
+        pool.spawn(move || {
+            let _ = gate_receiver.recv();
+        });
+
+        (ChunkGenerationService::new(pool, Arc::new(gcs), GeneratorType::Flat, 0), gate)
+    }
+
+    // receives until everything that was requested came back, returns the positions of the chunks that were kept
+    fn receive_all(service: &mut ChunkGenerationService) -> Vec<(i32, i32)> {
+        let mut received: Vec<(i32, i32)> = Vec::new();
+
+        for _ in 0..1000 {
+            if service.get_number_of_requested() == 0 {
+                return received;
+            }
+
+            match service.try_receive() {
+                Some(finished_chunk) => received.push(finished_chunk.get_pos()),
+                None => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+
+        panic!("GENERATION NEVER FINISHED!");
+    }
+
+    #[test]
+    fn the_closest_request_is_taken_first() {
+        let mut requests: GenerationRequests = GenerationRequests {
+            waiting: [(5, 5), (0, 3), (1, 1), (-2, 2), (2, 2), (3, 0), (2, 1)].into_iter().map(|position| (position, None)).collect(),
+            center: (1, 1),
+            range: None
+        };
+
+        let mut order: Vec<(i32, i32)> = Vec::new();
+        while let Some((closest, _)) = requests.take_closest() {
+            order.push(closest);
+        }
+
+        // equal distances go by x, then z
+        assert_eq!(order, vec![(1, 1), (2, 1), (2, 2), (0, 3), (3, 0), (-2, 2), (5, 5)]);
+    }
+
+    #[test]
+    fn moving_the_center_cancels_requests_out_of_range() {
+        let (mut service, gate) = test_service();
+
+        service.set_center(0, 0, 2);
+
+        service.request(0, 0);
+        service.request(2, 0);
+        service.request(3, 0);
+        service.request(1, 1);
+
+        // already out of range, never asked for
+        assert!(!service.is_requested(3, 0));
+        assert_eq!(service.get_number_of_requested(), 3);
+
+        service.set_center(2, 1, 1);
+
+        assert!(!service.is_requested(0, 0));
+        assert!(service.is_requested(2, 0));
+        assert!(service.is_requested(1, 1));
+
+        gate.send(()).unwrap();
+
+        let mut received: Vec<(i32, i32)> = receive_all(&mut service);
+        received.sort();
+
+        assert_eq!(received, vec![(1, 1), (2, 0)]);
+    }
+
+    #[test]
+    fn cancelling_everything_throws_away_chunks_being_generated() {
+        let (mut service, gate) = test_service();
+
+        service.request(0, 0);
+        service.request(1, 0);
+
+        gate.send(()).unwrap();
+
+        // wait for the workers to take both, they're being generated or done now
+        while !service.requests.lock().unwrap().waiting.is_empty() {
+            thread::sleep(Duration::from_millis(1));
+        }
+
+        service.cancel_all();
+
+        assert!(receive_all(&mut service).is_empty());
+
+        // nothing is in range until the center is set again
+        service.request(0, 0);
+        assert!(!service.is_requested(0, 0));
+
+        service.set_center(0, 0, 4);
+        service.request(0, 0);
+        assert_eq!(receive_all(&mut service), vec![(0, 0)]);
+    }
+}
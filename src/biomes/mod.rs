@@ -1,2 +1,4 @@
 pub mod biome_generator;
-pub mod generation_component_system;
\ No newline at end of file
+pub mod generation_component_system;
+pub mod decoration;
+pub mod generation_service;
@@ -1,3 +1,5 @@
+use std::collections::HashMap;
+
 
 #[derive(Debug)]
 pub struct AtlasTextureMap {
@@ -52,6 +54,83 @@ impl BlockBox {
 }
 
 
+// what a liquid turns another liquid it touches into - (water touching lava makes stone)
+pub struct LiquidInteraction {
+    liquid: u32,
+    // what a source of the other liquid becomes
+    source_result: u32,
+    // what flowing other liquid becomes
+    flowing_result: u32
+}
+
+impl LiquidInteraction {
+    pub fn new(liquid: u32, source_result: u32, flowing_result: u32) -> Self {
+        LiquidInteraction {
+            liquid,
+            source_result,
+            flowing_result
+        }
+    }
+
+    pub fn get_liquid(&self) -> u32 {
+        self.liquid
+    }
+
+    pub fn get_result(&self, source: bool) -> u32 {
+        if source {
+            self.source_result
+        } else {
+            self.flowing_result
+        }
+    }
+}
+
+// how a liquid flows, the level of each liquid block lives in its rotation byte
+pub struct LiquidDefinition {
+    // game ticks between each step of the flow
+    viscosity: u32,
+    // how many blocks out from a source the liquid reaches on flat ground, 1 through 15
+    range: u8,
+    // flowing liquid between two sources with ground under it becomes a source
+    renewable: bool,
+    interactions: Vec<LiquidInteraction>
+}
+
+impl LiquidDefinition {
+    pub fn new(viscosity: u32, range: u8, renewable: bool, interactions: Vec<LiquidInteraction>) -> Self {
+
+        // double check in case lua misses it
+        if range == 0 || range > 15 {
+            panic!("LIQUID RANGE MUST BE 1 THROUGH 15!");
+        }
+
+        LiquidDefinition {
+            viscosity: viscosity.max(1),
+            range,
+            renewable,
+            interactions
+        }
+    }
+
+    pub fn get_viscosity(&self) -> u32 {
+        self.viscosity
+    }
+
+    pub fn get_range(&self) -> u8 {
+        self.range
+    }
+
+    pub fn is_renewable(&self) -> bool {
+        self.renewable
+    }
+
+    // what this liquid does to the other liquid when they touch, None if nothing happens
+    pub fn get_interaction(&self, liquid: u32) -> Option<&LiquidInteraction> {
+        self.interactions.iter().find(|interaction| interaction.liquid == liquid)
+    }
+}
+
+
 pub struct BlockComponentSystem {
     id: Vec<u32>,
     game_mod: Vec<String>,
@@ -59,13 +138,36 @@ pub struct BlockComponentSystem {
     draw_type: Vec<DrawType>,
     texture: Vec<Vec<String>>,
     block_box: Vec<Option<BlockBox>>,
-    mapping: Vec<Vec<AtlasTextureMap>>
+    mapping: Vec<Vec<AtlasTextureMap>>,
+    // how much block light this gives off, 0 through 15
+    light: Vec<u8>,
+    // which facings the block can be placed with
+    rotation_type: Vec<RotationType>,
+    // the block has an on_random_tick in lua
+    random_tick: Vec<bool>,
+    // the block has an on_scheduled_update in lua
+    scheduled_update: Vec<bool>,
+    // only blocks drawn as liquids have one
+    liquid: Vec<Option<LiquidDefinition>>,
+    // group name -> rating, like falling = 1 - blocks are not in any group unless lua says so
+    groups: Vec<HashMap<String, i32>>
 }
 
 pub enum DrawType {
     None,
     Normal,
-    BlockBox
+    BlockBox,
+    // a full cube with its top lowered by how much liquid is in it
+    Liquid
+}
+
+pub enum RotationType {
+    // always drawn as defined
+    None,
+    // 4 facings around +y
+    Horizontal,
+    // all 24 orientations
+    Full
 }
 
 impl BlockComponentSystem {
@@ -77,11 +179,17 @@ impl BlockComponentSystem {
             draw_type: Vec::new(),
             texture: Vec::new(),
             block_box: Vec::new(),
-            mapping: Vec::new()
+            mapping: Vec::new(),
+            light: Vec::new(),
+            rotation_type: Vec::new(),
+            random_tick: Vec::new(),
+            scheduled_update: Vec::new(),
+            liquid: Vec::new(),
+            groups: Vec::new()
         };
 
         // built in definition for air
-        component_system.register_block(String::from("engine"), String::from("air"), DrawType::None, vec![], None, vec![]);
+        component_system.register_block(String::from("engine"), String::from("air"), DrawType::None, vec![], None, vec![], 0, RotationType::None);
 
         component_system
     }
@@ -93,8 +201,10 @@ impl BlockComponentSystem {
         draw_type: DrawType,
         mut textures: Vec<String>,
         block_box: Option<BlockBox>,
-        mapping: Vec<AtlasTextureMap>
-    ) {
+        mapping: Vec<AtlasTextureMap>,
+        light: u8,
+        rotation_type: RotationType
+    ) -> u32 {
         println!("{} is ID: {}", &name, self.id.len());
 
         self.id.push(self.id.len() as u32);
@@ -115,12 +225,101 @@ impl BlockComponentSystem {
         self.block_box.push(block_box);
 
         self.mapping.push(mapping);
+
+        self.light.push(light);
+
+        self.rotation_type.push(rotation_type);
+
+        // nothing ticks until lua says so
+        self.random_tick.push(false);
+        self.scheduled_update.push(false);
+
+        // liquids get their definition once every block they interact with is registered
+        self.liquid.push(None);
+
+        self.groups.push(HashMap::new());
+
+        self.id.len() as u32 - 1
+    }
+
+    // which tick callbacks the block has in lua
+    pub fn set_block_ticks(&mut self, id: u32, random_tick: bool, scheduled_update: bool) {
+        self.random_tick[id as usize] = random_tick;
+        self.scheduled_update[id as usize] = scheduled_update;
+    }
+
+    pub fn has_random_tick(&self, id: u32) -> bool {
+        self.random_tick[id as usize]
+    }
+
+    pub fn has_scheduled_update(&self, id: u32) -> bool {
+        self.scheduled_update[id as usize]
+    }
+
+    // if any block at all ticks randomly, when none do the random ticks are skipped entirely
+    pub fn any_random_tick(&self) -> bool {
+        self.random_tick.iter().any(|random_tick| *random_tick)
+    }
+
+    pub fn set_block_groups(&mut self, id: u32, groups: HashMap<String, i32>) {
+        self.groups[id as usize] = groups;
+    }
+
+    // the rating the block has in the group, 0 if it's not in it
+    pub fn get_group(&self, id: u32, group: &str) -> i32 {
+        self.groups[id as usize].get(group).copied().unwrap_or(0)
+    }
+
+    pub fn set_liquid(&mut self, id: u32, liquid: LiquidDefinition) {
+        if !matches!(self.draw_type[id as usize], DrawType::Liquid) {
+            panic!("TRIED TO MAKE {} A LIQUID WITHOUT THE LIQUID DRAW TYPE!", self.name[id as usize]);
+        }
+        self.liquid[id as usize] = Some(liquid);
+    }
+
+    pub fn get_liquid(&self, id: u32) -> Option<&LiquidDefinition> {
+        self.liquid[id as usize].as_ref()
+    }
+
+    pub fn is_liquid(&self, id: u32) -> bool {
+        self.liquid[id as usize].is_some()
     }
 
     pub fn get_mapping(&self, id: u32) -> &Vec<AtlasTextureMap> {
         self.mapping.get(id as usize).unwrap()
     }
 
+    // full cubes hide whatever is behind them
+    pub fn is_opaque(&self, id: u32) -> bool {
+        matches!(self.draw_type[id as usize], DrawType::Normal)
+    }
+
+    // the block light level this block gives off
+    pub fn get_light(&self, id: u32) -> u8 {
+        self.light[id as usize]
+    }
+
+    pub fn get_rotation_type(&self, id: u32) -> &RotationType {
+        &self.rotation_type[id as usize]
+    }
+
+    pub fn get_name(&self, id: u32) -> &String {
+        self.name.get(id as usize).unwrap()
+    }
+
+    // how many blocks are registered, including air
+    pub fn get_number_of_blocks(&self) -> u32 {
+        self.id.len() as u32
+    }
+
+    // the non-panicking version of get_id_of
+    pub fn find_id_of(&self, name: &str) -> Option<u32> {
+        self.name
+            .iter()
+            .position(|test| test.eq(name))
+            .map(|id| id as u32)
+    }
+
     pub fn get_id_of(&self, name: String) -> u32 {
         self.name
             .iter()
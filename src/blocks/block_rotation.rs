@@ -0,0 +1,240 @@
+/*
+block facing
+
+a rotation byte picks one of 24 orientations - rotation / 4 is which way the
+block's top (its up face) points, rotation % 4 is how many quarter turns it's
+spun around its own top first
+
+the front of a block is its south face (z+), a quarter turn swings the front
+z+ -> x+ -> z- -> x-
+
+horizontal blocks like furnaces and stairs keep their top pointing up so they
+only ever use 0 through 3
+*/
+
+use super::block_component_system::RotationType;
+
+pub const ROTATION_COUNT: u8 = 24;
+
+// face directions in block mapping order - up (y+), down (y-), south (z+), north (z-), then x+ and x- (drawn by face_west and face_east)
+pub const FACE_DIRECTIONS: [[i32; 3]; 6] = [
+    [0, 1, 0],
+    [0, -1, 0],
+    [0, 0, 1],
+    [0, 0, -1],
+    [1, 0, 0],
+    [-1, 0, 0]
+];
+
+// spins a vector around +y, each turn carries z+ to x+
+fn turn_y(vector: [i32; 3], turns: u8) -> [i32; 3] {
+    let mut turned: [i32; 3] = vector;
+    for _ in 0..turns {
+        turned = [turned[2], turned[1], -turned[0]];
+    }
+    turned
+}
+
+// tips a vector over so +y ends up pointing at FACE_DIRECTIONS[up]
+fn point_up(vector: [i32; 3], up: usize) -> [i32; 3] {
+    let [x, y, z] = vector;
+    match up {
+        0 => [x, y, z],
+        1 => [x, -y, -z],
+        2 => [x, -z, y],
+        3 => [x, z, -y],
+        4 => [y, -x, z],
+        _ => [-y, x, z]
+    }
+}
+
+// block space -> world space
+pub fn rotate(rotation: u8, vector: [i32; 3]) -> [i32; 3] {
+    point_up(turn_y(vector, rotation % 4), (rotation / 4) as usize)
+}
+
+// world space -> block space, the rotations are orthogonal so this is the transpose
+pub fn inverse_rotate(rotation: u8, vector: [i32; 3]) -> [i32; 3] {
+    let mut result: [i32; 3] = [0; 3];
+
+    for (axis, value) in result.iter_mut().enumerate() {
+        let mut basis: [i32; 3] = [0; 3];
+        basis[axis] = 1;
+
+        let column: [i32; 3] = rotate(rotation, basis);
+
+        *value = (column[0] * vector[0]) + (column[1] * vector[1]) + (column[2] * vector[2]);
+    }
+
+    result
+}
+
+// the face index of a unit direction
+pub fn face_of_direction(direction: [i32; 3]) -> Option<usize> {
+    FACE_DIRECTIONS.iter().position(|face_direction| *face_direction == direction)
+}
+
+// which of the block's own faces ends up showing on world_face
+pub fn get_local_face(rotation: u8, world_face: usize) -> usize {
+    face_of_direction(inverse_rotate(rotation, FACE_DIRECTIONS[world_face])).unwrap()
+}
+
+/*
+the rotation a block ends up with when everything around it is turned or mirrored by transform
+
+the block's top and front are carried through transform and the orientation that
+matches both is found - a mirrored block can't be turned inside out so this is the closest fit
+*/
+pub fn transform_rotation<F: Fn([i32; 3]) -> [i32; 3]>(rotation: u8, transform: F) -> u8 {
+
+    let up: [i32; 3] = transform(rotate(rotation, FACE_DIRECTIONS[0]));
+    let front: [i32; 3] = transform(rotate(rotation, FACE_DIRECTIONS[2]));
+
+    (0..ROTATION_COUNT)
+        .find(|test| rotate(*test, FACE_DIRECTIONS[0]) == up && rotate(*test, FACE_DIRECTIONS[2]) == front)
+        .unwrap_or(rotation)
+}
+
+// makes sure a stored rotation is one the block can actually have
+pub fn limit_rotation(rotation_type: &RotationType, rotation: u8) -> u8 {
+    match rotation_type {
+        RotationType::None => 0,
+        RotationType::Horizontal => rotation % 4,
+        RotationType::Full => if rotation < ROTATION_COUNT { rotation } else { 0 },
+    }
+}
+
+// a horizontal block with its front pointing along direction, anything vertical keeps the default
+pub fn horizontal_rotation_facing(direction: [i32; 3]) -> u8 {
+    match direction {
+        [0, _, 1] => 0,
+        [1, _, 0] => 1,
+        [0, _, -1] => 2,
+        [-1, _, 0] => 3,
+        _ => 0
+    }
+}
+
+// a block with its top pointing along direction - logs placed against the side of another block
+pub fn rotation_facing(direction: [i32; 3]) -> u8 {
+    match face_of_direction(direction) {
+        Some(face) => (face * 4) as u8,
+        None => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // where the x, y and z axes of the block end up
+    fn columns(rotation: u8) -> [[i32; 3]; 3] {
+        [rotate(rotation, [1, 0, 0]), rotate(rotation, [0, 1, 0]), rotate(rotation, [0, 0, 1])]
+    }
+
+    fn determinant(matrix: [[i32; 3]; 3]) -> i32 {
+        let [a, b, c] = matrix;
+        a[0] * (b[1] * c[2] - b[2] * c[1]) - a[1] * (b[0] * c[2] - b[2] * c[0]) + a[2] * (b[0] * c[1] - b[1] * c[0])
+    }
+
+    #[test]
+    fn the_24_rotations_are_distinct_proper_rotations() {
+        let mut seen: Vec<[[i32; 3]; 3]> = Vec::new();
+
+        for rotation in 0..ROTATION_COUNT {
+            let matrix: [[i32; 3]; 3] = columns(rotation);
+
+            // every axis lands on an axis, nothing is stretched or turned inside out
+            for column in matrix {
+                assert!(face_of_direction(column).is_some(), "{} {:?}", rotation, column);
+            }
+            assert_eq!(determinant(matrix), 1, "{}", rotation);
+
+            assert!(!seen.contains(&matrix), "{}", rotation);
+            seen.push(matrix);
+        }
+
+        assert_eq!(columns(0), [[1, 0, 0], [0, 1, 0], [0, 0, 1]]);
+    }
+
+    #[test]
+    fn the_top_and_spin_are_packed_as_documented() {
+        for rotation in 0..ROTATION_COUNT {
+            assert_eq!(rotate(rotation, FACE_DIRECTIONS[0]), FACE_DIRECTIONS[(rotation / 4) as usize]);
+        }
+
+        // a quarter turn swings the front z+ -> x+ -> z- -> x-
+        for (rotation, front) in [[0, 0, 1], [1, 0, 0], [0, 0, -1], [-1, 0, 0]].into_iter().enumerate() {
+            assert_eq!(rotate(rotation as u8, FACE_DIRECTIONS[2]), front);
+        }
+    }
+
+    #[test]
+    fn inverse_rotate_undoes_rotate() {
+        let vectors: [[i32; 3]; 4] = [[1, 0, 0], [0, -1, 0], [1, 2, 3], [-3, 5, -7]];
+
+        for rotation in 0..ROTATION_COUNT {
+            for vector in vectors {
+                assert_eq!(inverse_rotate(rotation, rotate(rotation, vector)), vector, "{}", rotation);
+                assert_eq!(rotate(rotation, inverse_rotate(rotation, vector)), vector, "{}", rotation);
+            }
+        }
+    }
+
+    #[test]
+    fn every_rotation_shows_each_local_face_once() {
+        for rotation in 0..ROTATION_COUNT {
+            let mut local_faces: Vec<usize> = (0..6).map(|world_face| get_local_face(rotation, world_face)).collect();
+
+            for (world_face, local_face) in local_faces.iter().enumerate() {
+                assert_eq!(rotate(rotation, FACE_DIRECTIONS[*local_face]), FACE_DIRECTIONS[world_face]);
+            }
+
+            local_faces.sort();
+            assert_eq!(local_faces, vec![0, 1, 2, 3, 4, 5], "{}", rotation);
+        }
+
+        assert_eq!((0..6).map(|world_face| get_local_face(0, world_face)).collect::<Vec<usize>>(), vec![0, 1, 2, 3, 4, 5]);
+
+        // tipped over so the top points south, the north face ends up on top
+        assert_eq!(get_local_face(8, 2), 0);
+        assert_eq!(get_local_face(8, 0), 3);
+    }
+
+    #[test]
+    fn horizontal_blocks_face_the_way_they_were_placed() {
+        for (direction, rotation) in [([0, 0, 1], 0), ([1, 0, 0], 1), ([0, 0, -1], 2), ([-1, 0, 0], 3)] {
+            assert_eq!(horizontal_rotation_facing(direction), rotation);
+            assert_eq!(rotate(rotation, FACE_DIRECTIONS[2]), direction);
+            assert_eq!(rotate(rotation, FACE_DIRECTIONS[0]), [0, 1, 0]);
+        }
+
+        // looking straight up or down keeps the default
+        assert_eq!(horizontal_rotation_facing([0, 1, 0]), 0);
+        assert_eq!(horizontal_rotation_facing([0, -1, 0]), 0);
+    }
+
+    #[test]
+    fn full_blocks_point_their_top_the_way_they_were_placed() {
+        for direction in FACE_DIRECTIONS {
+            assert_eq!(rotate(rotation_facing(direction), FACE_DIRECTIONS[0]), direction);
+        }
+
+        assert_eq!(rotation_facing([1, 1, 0]), 0);
+    }
+
+    #[test]
+    fn leaving_everything_in_place_keeps_the_rotation() {
+        for rotation in 0..ROTATION_COUNT {
+            assert_eq!(transform_rotation(rotation, |direction| direction), rotation);
+        }
+    }
+
+    #[test]
+    fn stored_rotations_are_limited_to_the_rotation_type() {
+        assert_eq!(limit_rotation(&RotationType::None, 5), 0);
+        assert_eq!(limit_rotation(&RotationType::Horizontal, 6), 2);
+        assert_eq!(limit_rotation(&RotationType::Full, 23), 23);
+        assert_eq!(limit_rotation(&RotationType::Full, 24), 0);
+    }
+}
@@ -1 +1,2 @@
-pub mod block_component_system;
\ No newline at end of file
+pub mod block_component_system;
+pub mod block_rotation;
\ No newline at end of file
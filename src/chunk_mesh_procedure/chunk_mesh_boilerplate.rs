@@ -27,7 +27,13 @@ this is extremely similar to RAID-0 with hard drive/ssd technology
 
 use std::sync::atomic::{AtomicUsize, Ordering};
 
-use crate::blocks::block_component_system::{AtlasTextureMap};
+use crate::blocks::{
+    block_component_system::AtlasTextureMap,
+    block_rotation::{
+        get_local_face,
+        inverse_rotate
+    }
+};
 
 // pushes the adjusted xyz into the vertex data
 fn set_pos(pos: &mut [f32], x: f32, y: f32, z: f32) {
@@ -598,10 +604,93 @@ pub fn face_east(
 
 
 
+// builds face number face (up, down, south, north, west, east) - the block mapping order
+fn add_face(
+    face: usize,
+
+    atlas_map: &AtlasTextureMap,
+
+    float_data: &mut Vec<f32>,
+    indices_data: &mut Vec<u32>,
+
+    float_count: &mut usize,
+    indices_count: &mut usize,
+    face_count: &mut usize,
+
+    x: f32,
+    y: f32,
+    z: f32,
+    light: f32
+) {
+    let face_function = match face {
+        0 => face_up,
+        1 => face_down,
+        2 => face_south,
+        3 => face_north,
+        4 => face_west,
+        _ => face_east
+    };
+
+    face_function(atlas_map, float_data, indices_data, float_count, indices_count, face_count, x, y, z, light);
+}
+
+/*
+a rotated block shows one of its own faces on world_face, but the texture has to
+turn with the block
+
+the face the block is showing is built on its own unrotated, then every corner of
+the face that was just written takes the texture coordinate of the corner it came from
+*/
+fn rotate_face_texture(
+    block_atlas_map: &[AtlasTextureMap],
+    rotation: u8,
+    local_face: usize,
+    float_data: &mut [f32],
+    float_count: usize,
+    x: f32,
+    y: f32,
+    z: f32
+) {
+
+    // pos 3, color 3, texture 2
+    const STRIDE: usize = 8;
+
+    let mut local_float_data: Vec<f32> = vec![0.0; STRIDE * 4];
+    let mut local_indices_data: Vec<u32> = vec![0; 6];
+
+    add_face(local_face, &block_atlas_map[local_face], &mut local_float_data, &mut local_indices_data, &mut 0, &mut 0, &mut 0, 0.0, 0.0, 0.0, 0.0);
+
+    let face_start: usize = float_count - (STRIDE * 4);
+
+    for vertex in 0..4 {
+
+        let vertex_start: usize = face_start + (vertex * STRIDE);
+
+        // corners sit on 0 or 1, centered on the block they're -1 or 1 and can be rotated as integers
+        let corner: [i32; 3] = [
+            ((float_data[vertex_start] - x) * 2.0 - 1.0).round() as i32,
+            ((float_data[vertex_start + 1] - y) * 2.0 - 1.0).round() as i32,
+            ((float_data[vertex_start + 2] - z) * 2.0 - 1.0).round() as i32
+        ];
+
+        let local_corner: [f32; 3] = inverse_rotate(rotation, corner).map(|value| ((value + 1) / 2) as f32);
+
+        for local_vertex in 0..4 {
+
+            let local_vertex_start: usize = local_vertex * STRIDE;
+
+            if local_float_data[local_vertex_start..local_vertex_start + 3] == local_corner {
+                float_data[vertex_start + 6] = local_float_data[local_vertex_start + 6];
+                float_data[vertex_start + 7] = local_float_data[local_vertex_start + 7];
+            }
+        }
+    }
+}
+
 // the packed boilerplate to allow a single function call
 pub fn add_block(
     
-    block_atlas_map: &Vec<AtlasTextureMap>,
+    block_atlas_map: &[AtlasTextureMap],
 
     float_data: &mut Vec<f32>,
     indices_data: &mut Vec<u32>,
@@ -620,14 +709,61 @@ pub fn add_block(
     x: f32,
     y: f32,
     z: f32,
-    light: f32
+    // brightness of each face - (x+, x-, y+, y-, z+, z-)
+    face_light: [f32; 6],
+    // facing from the world's rotation data - 0 is drawn as defined
+    rotation: u8
 ) {
 
+    let [
+        x_plus_light,
+        x_minus_light,
+        y_plus_light,
+        y_minus_light,
+        z_plus_light,
+        z_minus_light
+    ] = face_light;
+
     let side_face_light_subtraction =  0.75 / 16.0;
 
-    if y_plus {
-        face_up(
-            &block_atlas_map[0],
+    /*
+
+    +y = up
+    -y = down
+    
+    +z = south
+    -z = north
+
+    +x = west
+    -x = east
+
+    */
+
+    // in face order - up, down, south, north, west, east
+    let visible: [bool; 6] = [y_plus, y_minus, z_plus, z_minus, x_plus, x_minus];
+
+    let light: [f32; 6] = [
+        y_plus_light,
+        y_minus_light,
+        z_plus_light - side_face_light_subtraction,
+        z_minus_light - side_face_light_subtraction,
+        x_plus_light - side_face_light_subtraction,
+        x_minus_light - side_face_light_subtraction
+    ];
+
+    for face in 0..6 {
+
+        if !visible[face] {
+            continue;
+        }
+
+        // the block's own face that is pointing this way
+        let local_face: usize = if rotation == 0 { face } else { get_local_face(rotation, face) };
+
+        add_face(
+            face,
+
+            &block_atlas_map[local_face],
 
             float_data,
             indices_data,
@@ -639,114 +775,113 @@ pub fn add_block(
             x,
             y,
             z,
-            light
+            light[face]
         );
+
+        if rotation != 0 {
+            rotate_face_texture(block_atlas_map, rotation, local_face, float_data, *float_count, x, y, z);
+        }
     }
-    
-    if y_minus {
-        face_down(
-            &block_atlas_map[1],
+}
 
-            float_data,
-            indices_data,
+// pulls the top of a block that was just added down to height (0.0 to 1.0), liquids aren't always full
+pub fn lower_block_top(block_float_data: &mut [f32], y: f32, height: f32) {
 
-            float_count,
-            indices_count,
-            face_count,
+    // pos 3, color 3, texture 2
+    const STRIDE: usize = 8;
 
-            x,
-            y,
-            z,
-            light
-        );
+    for vertex_start in (0..block_float_data.len()).step_by(STRIDE) {
+        if float_eq(block_float_data[vertex_start + 1], y + 1.0) {
+            block_float_data[vertex_start + 1] = y + height;
+        }
     }
-    
-    
-    if z_plus {
-        face_south(
-            &block_atlas_map[2],
+}
 
-            float_data,
-            indices_data,
+#[cfg(test)]
+mod tests {
+    use crate::blocks::block_rotation::{
+        ROTATION_COUNT,
+        rotate
+    };
 
-            float_count,
-            indices_count,
-            face_count,
+    use super::*;
 
-            x,
-            y,
-            z,
-            light - side_face_light_subtraction
-        );
+    // every face gets its own eighth of the atlas along x
+    fn test_atlas() -> Vec<AtlasTextureMap> {
+        (0..6).map(|face| AtlasTextureMap::new(face as f32 * 0.125, 0.0, (face + 1) as f32 * 0.125, 0.125, 0, 0)).collect()
     }
 
-    
-    if z_minus {
-        face_north(
-            &block_atlas_map[3],
+    // the 4 vertices (pos 3, color 3, texture 2) of a single world face of a block at 3,5,7
+    fn draw_face(rotation: u8, face: usize) -> Vec<[f32; 8]> {
+        let mut float_data: Vec<f32> = vec![0.0; 32];
+        let mut indices_data: Vec<u32> = vec![0; 6];
 
-            float_data,
-            indices_data,
+        let mut visible: [bool; 6] = [false; 6];
+        visible[face] = true;
+        let [y_plus, y_minus, z_plus, z_minus, x_plus, x_minus] = visible;
 
-            float_count,
-            indices_count,
-            face_count,
+        add_block(&test_atlas(), &mut float_data, &mut indices_data, &mut 0, &mut 0, &mut 0, x_plus, x_minus, y_plus, y_minus, z_plus, z_minus, 3.0, 5.0, 7.0, [1.0; 6], rotation);
 
-            x,
-            y,
-            z,
-            light - side_face_light_subtraction
-        );
+        float_data.chunks(8).map(|vertex| vertex.try_into().unwrap()).collect()
     }
 
-    
-    /*
+    // a vertex's corner with the block centered on 0, so it can be rotated as integers
+    fn corner(vertex: &[f32; 8]) -> [i32; 3] {
+        [((vertex[0] - 3.0) * 2.0 - 1.0) as i32, ((vertex[1] - 5.0) * 2.0 - 1.0) as i32, ((vertex[2] - 7.0) * 2.0 - 1.0) as i32]
+    }
 
-    +y = up
-    -y = down
-    
-    +z = south
-    -z = north
+    #[test]
+    fn turned_faces_take_the_texture_corners_they_came_from() {
+        for rotation in 0..ROTATION_COUNT {
+            for face in 0..6 {
 
-    +x = west
-    -x = east
+                let local_face: usize = get_local_face(rotation, face);
 
-    */
+                let drawn: Vec<[f32; 8]> = draw_face(rotation, face);
+                let unturned: Vec<[f32; 8]> = draw_face(0, face);
+                let local: Vec<[f32; 8]> = draw_face(0, local_face);
 
-    if x_plus {
-        face_west(
-            &block_atlas_map[4],
+                // the face itself stays where it is, only the texture moves
+                for (vertex, unturned_vertex) in drawn.iter().zip(unturned.iter()) {
+                    assert_eq!(vertex[0..6], unturned_vertex[0..6]);
+                }
 
-            float_data,
-            indices_data,
+                let mut textures: Vec<[f32; 2]> = Vec::new();
 
-            float_count,
-            indices_count,
-            face_count,
+                for vertex in drawn.iter() {
+                    let local_corner: [i32; 3] = inverse_rotate(rotation, corner(vertex));
 
-            x,
-            y,
-            z,
-            light - side_face_light_subtraction
-        );
+                    let local_vertex: &[f32; 8] = local.iter().find(|local_vertex| corner(local_vertex) == local_corner).unwrap();
+
+                    assert_eq!(vertex[6..8], local_vertex[6..8], "rotation {} face {}", rotation, face);
+
+                    // the texture of the block's own face, never a neighbor in the atlas
+                    assert!(vertex[6] >= local_face as f32 * 0.125 && vertex[6] <= (local_face + 1) as f32 * 0.125);
+
+                    if !textures.contains(&[vertex[6], vertex[7]]) {
+                        textures.push([vertex[6], vertex[7]]);
+                    }
+                }
+
+                // all four corners of the texture are still used
+                assert_eq!(textures.len(), 4, "rotation {} face {}", rotation, face);
+            }
+        }
     }
-    
-    
-    if x_minus {
-        face_east(
-            &block_atlas_map[5],
 
-            float_data,
-            indices_data,
+    #[test]
+    fn a_quarter_turn_spins_the_top_texture() {
+        let unturned: Vec<[f32; 8]> = draw_face(0, 0);
+        let turned: Vec<[f32; 8]> = draw_face(1, 0);
 
-            float_count,
-            indices_count,
-            face_count,
+        // the corner that was at x+ z+ is drawn at x+ z- after the front swings from z+ to x+
+        let texture_at = |vertices: &[[f32; 8]], corner_pos: [i32; 3]| -> [f32; 2] {
+            let vertex: &[f32; 8] = vertices.iter().find(|vertex| corner(vertex) == corner_pos).unwrap();
+            [vertex[6], vertex[7]]
+        };
 
-            x,
-            y,
-            z,
-            light - side_face_light_subtraction
-        );
-    }    
-}
\ No newline at end of file
+        assert_eq!(rotate(1, [1, 1, 1]), [1, 1, -1]);
+        assert_eq!(texture_at(&turned, [1, 1, -1]), texture_at(&unturned, [1, 1, 1]));
+        assert_ne!(texture_at(&turned, [1, 1, 1]), texture_at(&unturned, [1, 1, 1]));
+    }
+}
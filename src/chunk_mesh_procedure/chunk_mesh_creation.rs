@@ -5,16 +5,58 @@ use rayon::prelude::*;
 use crate::{
     chunk_mesh_procedure::chunk_mesh_boilerplate::dry_run,
     world::{
-        world::World
-    }, graphics::mesh_component_system::MeshComponentSystem, blocks::block_component_system::BlockComponentSystem
+        world::{
+            World,
+            CHUNK_WIDTH,
+            CHUNK_HEIGHT,
+            pos_to_index
+        },
+        liquid::get_liquid_height,
+        lighting::{
+            MAX_LIGHT,
+            pack_light,
+            get_light_level
+        },
+        chunk_storage::{
+            ChunkStorage,
+            SECTION_HEIGHT,
+            SECTION_SIZE,
+            section_index_to_pos
+        }
+    }, blocks::{
+        block_component_system::BlockComponentSystem,
+        block_rotation::limit_rotation
+    }
 };
 
 use super::chunk_mesh_boilerplate::{
-    add_block
+    add_block,
+    lower_block_top
 };
 
 // this is procedurally generated
 
+// the CPU side of a chunk mesh - uploading this to the GPU is the client's job
+pub struct ChunkMeshData {
+    float_data: Vec<f32>,
+    indices_data: Vec<u32>
+}
+
+impl ChunkMeshData {
+    pub fn get_float_data(&self) -> &Vec<f32> {
+        &self.float_data
+    }
+
+    pub fn get_indices_data(&self) -> &Vec<u32> {
+        &self.indices_data
+    }
+
+    // hands the vectors over without copying them
+    pub fn unpack(self) -> (Vec<f32>, Vec<u32>) {
+        (self.float_data, self.indices_data)
+    }
+}
+
 /*
 positions,
 colors,
@@ -23,121 +65,198 @@ texture_coordinates,
 this_texture
 */
 
+// the 4 chunks around the one being meshed - None if they're not loaded
+struct Neighbors<'a> {
+    plus_x: Option<&'a ChunkStorage<u32>>,
+    minus_x: Option<&'a ChunkStorage<u32>>,
+    plus_z: Option<&'a ChunkStorage<u32>>,
+    minus_z: Option<&'a ChunkStorage<u32>>,
+
+    // light of the same 4 chunks
+    plus_x_light: Option<&'a ChunkStorage<u8>>,
+    minus_x_light: Option<&'a ChunkStorage<u8>>,
+    plus_z_light: Option<&'a ChunkStorage<u8>>,
+    minus_z_light: Option<&'a ChunkStorage<u8>>
+}
 
-// Convertes u16 1D position into (i8,i8,i8) 3D tuple position
-fn index_to_pos(i: usize) -> (usize,usize,usize) {
-    (i / 2048,
-    (i % 2048) % 128,
-    (i % 2048) / 128)
+impl<'a> Neighbors<'a> {
+    fn new(world: &'a World, pos_x: i32, pos_z: i32) -> Self {
+        Self {
+            plus_x: world.get_chunk_blocks(pos_x + 1, pos_z),
+            minus_x: world.get_chunk_blocks(pos_x - 1, pos_z),
+            plus_z: world.get_chunk_blocks(pos_x, pos_z + 1),
+            minus_z: world.get_chunk_blocks(pos_x, pos_z - 1),
+
+            plus_x_light: world.get_chunk_light(pos_x + 1, pos_z),
+            minus_x_light: world.get_chunk_light(pos_x - 1, pos_z),
+            plus_z_light: world.get_chunk_light(pos_x, pos_z + 1),
+            minus_z_light: world.get_chunk_light(pos_x, pos_z - 1)
+        }
+    }
 }
 
-// Converts x,y,z (i8) 3D position into u16 1D position.
-pub fn pos_to_index ( x: usize, y: usize, z: usize ) -> usize {
-    (x * 2048) + (z * 128) + y
+// how bright a face lit at this level is drawn, each level down is 80% of the one above
+fn light_brightness(level: u8) -> f32 {
+    0.8_f32.powi((MAX_LIGHT - level) as i32)
 }
 
+/*
+the brightness of each face of the block at x,y,z - (x+, x-, y+, y-, z+, z-)
 
+a face is lit by the block it looks out into, above the world is open sky
+*/
+fn face_light(light: &ChunkStorage<u8>, neighbors: &Neighbors, x: usize, y: usize, z: usize) -> [f32; 6] {
+
+    let edge: usize = (CHUNK_WIDTH - 1) as usize;
+    let top: usize = (CHUNK_HEIGHT - 1) as usize;
 
+    let neighbor_light = | neighbor_option: Option<&ChunkStorage<u8>>, index: usize | {
+        match neighbor_option {
+            Some(neighbor) => neighbor.get(index),
+            None => 0,
+        }
+    };
 
+    let x_plus = if x < edge { light.get(pos_to_index(x + 1, y, z)) } else { neighbor_light(neighbors.plus_x_light, pos_to_index(0, y, z)) };
+    let x_minus = if x > 0 { light.get(pos_to_index(x - 1, y, z)) } else { neighbor_light(neighbors.minus_x_light, pos_to_index(edge, y, z)) };
 
+    let y_plus = if y < top { light.get(pos_to_index(x, y + 1, z)) } else { pack_light(MAX_LIGHT, 0) };
+    let y_minus = if y > 0 { light.get(pos_to_index(x, y - 1, z)) } else { 0 };
 
-// borrow the entire world
-pub fn create_chunk_mesh(bcs: &BlockComponentSystem, mcs: &mut MeshComponentSystem, world: &World,pos_x: i32, pos_z: i32, texture_id: u32) -> Option<u32> {      
+    let z_plus = if z < edge { light.get(pos_to_index(x, y, z + 1)) } else { neighbor_light(neighbors.plus_z_light, pos_to_index(x, y, 0)) };
+    let z_minus = if z > 0 { light.get(pos_to_index(x, y, z - 1)) } else { neighbor_light(neighbors.minus_z_light, pos_to_index(x, y, edge)) };
 
-    // dry run to get capacities
+    [x_plus, x_minus, y_plus, y_minus, z_plus, z_minus].map(|face| light_brightness(get_light_level(face)))
+}
+
+/*
+a face shows when the block it looks out into can be seen through - air, or a liquid
+that isn't the block's own (water doesn't draw faces inside itself, stone under it still shows)
+*/
+fn shows_through(bcs: &BlockComponentSystem, id: u32, neighbor: u32) -> bool {
+    neighbor == 0 || (neighbor != id && bcs.is_liquid(neighbor))
+}
+
+// which faces of the block id at x,y,z can be seen - (x+, x-, y+, y-, z+, z-)
+fn visible_faces(bcs: &BlockComponentSystem, chunk: &ChunkStorage<u32>, neighbors: &Neighbors, id: u32, x: usize, y: usize, z: usize) -> (bool, bool, bool, bool, bool, bool) {
+
+    let shows = | neighbor: u32 | shows_through(bcs, id, neighbor);
+
+    let edge: usize = (CHUNK_WIDTH - 1) as usize;
+    let top: usize = (CHUNK_HEIGHT - 1) as usize;
+
+    // internal
+    let mut x_plus = x < edge && shows(chunk.get(pos_to_index(x + 1, y, z)));
+    let mut x_minus = x > 0 && shows(chunk.get(pos_to_index(x - 1, y, z)));
+
+    // the bottom of the world is never looked at from below
+    let y_plus = y == top || (y < top && shows(chunk.get(pos_to_index(x, y + 1, z))));
+    let y_minus = y > 1 && shows(chunk.get(pos_to_index(x, y - 1, z)));
 
-    let block_vector_option: Option<&[u32]> = world.get_chunk_blocks_slice(pos_x, pos_z);
+    let mut z_plus = z < edge && shows(chunk.get(pos_to_index(x, y, z + 1)));
+    let mut z_minus = z > 0 && shows(chunk.get(pos_to_index(x, y, z - 1)));
 
-    match block_vector_option {
-        Some(_) => (),
-        None => return None,
+    // external
+
+    // x
+    if x == 0 {
+        if let Some(neighbor_minus_x) = neighbors.minus_x {
+            x_minus = shows(neighbor_minus_x.get(pos_to_index(edge, y, z)));
+        }
+    }
+    if x == edge {
+        if let Some(neighbor_plus_x) = neighbors.plus_x {
+            x_plus = shows(neighbor_plus_x.get(pos_to_index(0, y, z)));
+        }
     }
 
+    // z
+    if z == 0 {
+        if let Some(neighbor_minus_z) = neighbors.minus_z {
+            z_minus = shows(neighbor_minus_z.get(pos_to_index(x, y, edge)));
+        }
+    }
+    if z == edge {
+        if let Some(neighbor_plus_z) = neighbors.plus_z {
+            z_plus = shows(neighbor_plus_z.get(pos_to_index(x, y, 0)));
+        }
+    }
 
-    let float_count: AtomicUsize = AtomicUsize::new(0);
-    let indices_count: AtomicUsize = AtomicUsize::new(0);
+    (x_plus, x_minus, y_plus, y_minus, z_plus, z_minus)
+}
 
-    let chunk: &[u32] = block_vector_option.unwrap();
+// every block in the section is a full opaque cube
+fn section_is_opaque(bcs: &BlockComponentSystem, chunk: &ChunkStorage<u32>, section: usize) -> bool {
+    chunk.get_section(section).get_used_values().all(|id| bcs.is_opaque(id))
+}
 
-    let neighbor_plus_x_option: Option<&[u32]> = world.get_chunk_blocks_slice(pos_x + 1, pos_z);
-    let neighbor_minus_x_option: Option<&[u32]> = world.get_chunk_blocks_slice(pos_x - 1, pos_z);
+/*
+a section is buried when it and everything touching it is opaque - nothing inside can be seen
 
-    let neighbor_plus_z_option: Option<&[u32]> = world.get_chunk_blocks_slice(pos_x ,pos_z + 1);
-    let neighbor_minus_z_option: Option<&[u32]> = world.get_chunk_blocks_slice(pos_x, pos_z - 1);
+unloaded neighbors count as opaque, faces are never drawn against them
+the top of the world is always open so the top section can never be buried
+*/
+fn section_is_buried(bcs: &BlockComponentSystem, chunk: &ChunkStorage<u32>, neighbors: &Neighbors, section: usize) -> bool {
 
+    if section + 1 >= chunk.get_section_count() {
+        return false;
+    }
 
-    chunk.par_iter().enumerate().for_each( | ( index, value ) | {
+    let neighbor_opaque = | neighbor_option: Option<&ChunkStorage<u32>> | {
+        match neighbor_option {
+            Some(neighbor) => section_is_opaque(bcs, neighbor, section),
+            None => true,
+        }
+    };
+
+    section_is_opaque(bcs, chunk, section) &&
+    section_is_opaque(bcs, chunk, section + 1) &&
+    (section == 0 || section_is_opaque(bcs, chunk, section - 1)) &&
+    neighbor_opaque(neighbors.plus_x) &&
+    neighbor_opaque(neighbors.minus_x) &&
+    neighbor_opaque(neighbors.plus_z) &&
+    neighbor_opaque(neighbors.minus_z)
+}
 
-        // if it does not equal air
-        if *value != 0 {
+// meshes one 16x16x16 section, None if there is nothing to draw
+fn mesh_section(bcs: &BlockComponentSystem, chunk: &ChunkStorage<u32>, rotation: &ChunkStorage<u8>, light: &ChunkStorage<u8>, neighbors: &Neighbors, section: usize) -> Option<ChunkMeshData> {
 
-            let (x,y,z) = index_to_pos(index);
-            
-            // internal
-            if x + 1 <= 15 && chunk[pos_to_index(x + 1, y, z)] == 0 {
-                dry_run(&float_count, &indices_count)
-            }
-            if x >= 1 && chunk[pos_to_index(x - 1, y, z)] == 0 {
-                dry_run(&float_count, &indices_count)
-            }
+    // fast path - a section of pure air has nothing to draw
+    if chunk.get_section(section).get_single() == Some(0) {
+        return None;
+    }
 
-            if y == 127 || (y < 127 && chunk[pos_to_index(x, y + 1, z)] == 0) {
-                dry_run(&float_count, &indices_count)
-            }
-            if y > 0 && y - 1 >= 1 && chunk[pos_to_index(x, y - 1, z)] == 0 {
-                dry_run(&float_count, &indices_count)
-            }
+    if section_is_buried(bcs, chunk, neighbors, section) {
+        return None;
+    }
 
-            if z + 1 <= 15 && chunk[pos_to_index(x, y, z + 1)] == 0 {
-                dry_run(&float_count, &indices_count)
-            }
-            if z >= 1 && chunk[pos_to_index(x, y, z - 1)] == 0 {
-                dry_run(&float_count, &indices_count)
-            }
+    let section_base_y: usize = section * SECTION_HEIGHT as usize;
 
-            // external
-
-            // x
-            if x == 0 {
-                match neighbor_minus_x_option {
-                    Some(neighbor_minus_x) => {
-                        if neighbor_minus_x[pos_to_index(15, y, z)] == 0 {
-                            dry_run(&float_count, &indices_count);
-                        }
-                    },
-                    None => (),
-                }
-            }
-            if x == 15 {
-                match neighbor_plus_x_option {
-                    Some(neighbor_plus_x) => {
-                        if neighbor_plus_x[pos_to_index(0, y, z)] == 0 {
-                            dry_run(&float_count, &indices_count);
-                        }
-                    },
-                    None => (),
-                }
-            }
+    // section index -> chunk position
+    let section_pos = | index: usize | {
+        let (x, y, z) = section_index_to_pos(index);
+        (x, y + section_base_y, z)
+    };
 
-            // z
-            if z == 0 {
-                match neighbor_minus_z_option {
-                    Some(neighbor_minus_z) => {
-                        if neighbor_minus_z[pos_to_index(x, y, 15)] == 0 {
-                            dry_run(&float_count, &indices_count);
-                        }
-                    },
-                    None => (),
-                }
-            }
-            if z == 15 {
-                match neighbor_plus_z_option {
-                    Some(neighbor_plus_z) => {
-                        if neighbor_plus_z[pos_to_index(x, y, 0)] == 0 {
-                            dry_run(&float_count, &indices_count);
-                        }
-                    },
-                    None => (),
+    // dry run to get capacities
+
+    let float_count: AtomicUsize = AtomicUsize::new(0);
+    let indices_count: AtomicUsize = AtomicUsize::new(0);
+
+    (0..SECTION_SIZE).into_par_iter().for_each( | index | {
+
+        let (x,y,z) = section_pos(index);
+
+        let value: u32 = chunk.get(pos_to_index(x, y, z));
+
+        // if it does not equal air
+        if value != 0 {
+
+            let (x_plus, x_minus, y_plus, y_minus, z_plus, z_minus) = visible_faces(bcs, chunk, neighbors, value, x, y, z);
+
+            for face in [x_plus, x_minus, y_plus, y_minus, z_plus, z_minus] {
+                if face {
+                    dry_run(&float_count, &indices_count);
                 }
             }
         }
@@ -164,73 +283,23 @@ pub fn create_chunk_mesh(bcs: &BlockComponentSystem, mcs: &mut MeshComponentSyst
     // this part is EXTREMELY important, this allows all the vertex points to link together
     let mut face_count: usize = 0;
 
-    chunk.iter().enumerate().for_each(|(index, value)| {
+    (0..SECTION_SIZE).for_each(|index| {
 
-        // let block_id: u32 = chunk[pos_to_index(x, y, z)];
+        let (x,y,z) = section_pos(index);
+
+        let value: u32 = chunk.get(pos_to_index(x, y, z));
 
         // if it does not equal air
-        if *value != 0 {
+        if value != 0 {
 
-            let (x,y,z) = index_to_pos(index);
+            let (x_plus, x_minus, y_plus, y_minus, z_plus, z_minus) = visible_faces(bcs, chunk, neighbors, value, x, y, z);
 
-            let light = 16.0/16.0;
-            
-            let mut x_plus = x + 1 <= 15 && chunk[pos_to_index(x + 1, y, z)] == 0;
-            let mut x_minus =    x >= 1  && chunk[pos_to_index(x - 1, y, z)] == 0;
-
-            let y_plus = y == 127 || (y < 127 && chunk[pos_to_index(x, y + 1, z)] == 0);
-            let y_minus = y > 0 && y - 1 >= 1 && chunk[pos_to_index(x, y - 1, z)] == 0;
-
-            let mut z_plus = z + 1 <= 15 && chunk[pos_to_index(x, y, z + 1)] == 0;
-            let mut z_minus =    z >= 1  && chunk[pos_to_index(x, y, z - 1)] == 0;
-
-            // x
-            if x == 0 {
-                match neighbor_minus_x_option {
-                    Some(neighbor_minus_x) => {
-                        if neighbor_minus_x[pos_to_index(15, y, z)] == 0 {
-                            x_minus = true;
-                        }
-                    },
-                    None => (),
-                }
-            }
-            if x == 15 {
-                match neighbor_plus_x_option {
-                    Some(neighbor_plus_x) => {                        
-                        if neighbor_plus_x[pos_to_index(0, y, z)] == 0 {
-                            x_plus = true;
-                        }
-                    },
-                    None => (),
-                }
-            }
+            if x_plus || x_minus || y_plus || y_minus || z_plus || z_minus {
 
-            // z
-            if z == 0 {
-                match neighbor_minus_z_option {
-                    Some(neighbor_minus_z) => {
-                        if neighbor_minus_z[pos_to_index(x, y, 15)] == 0 {
-                            z_minus = true;
-                        }
-                    },
-                    None => (),
-                }
-            }
-            if z == 15 {
-                match neighbor_plus_z_option {
-                    Some(neighbor_plus_z) => {
-                        if neighbor_plus_z[pos_to_index(x, y, 0)] == 0 {
-                            z_plus = true;
-                        }
-                    },
-                    None => (),
-                }
-            }
+                let block_float_start: usize = new_float_count;
 
-            if x_plus || x_minus || y_plus || y_minus || z_plus || z_minus {
                 add_block(
-                    bcs.get_mapping(*value),
+                    bcs.get_mapping(value),
                     &mut float_data,
                     &mut indices_data,
 
@@ -248,13 +317,77 @@ pub fn create_chunk_mesh(bcs: &BlockComponentSystem, mcs: &mut MeshComponentSyst
                     x as f32,
                     y as f32,
                     z as f32,
-                    light
+                    face_light(light, neighbors, x, y, z),
+                    // the rotation byte of a liquid is its level
+                    if bcs.is_liquid(value) { 0 } else { limit_rotation(bcs.get_rotation_type(value), rotation.get(pos_to_index(x, y, z))) }
                 );
+
+                if let Some(liquid) = bcs.get_liquid(value) {
+                    let top: usize = (CHUNK_HEIGHT - 1) as usize;
+
+                    // under more of itself the liquid fills the block, a waterfall has no steps in it
+                    let height: f32 = if y < top && chunk.get(pos_to_index(x, y + 1, z)) == value {
+                        1.0
+                    } else {
+                        get_liquid_height(rotation.get(pos_to_index(x, y, z)), liquid.get_range())
+                    };
+
+                    lower_block_top(&mut float_data[block_float_start..new_float_count], y as f32, height);
+                }
             }
         }
     });
 
-    let returning_mesh: u32 = mcs.new_mesh(float_data, indices_data, texture_id);
+    Some(ChunkMeshData {
+        float_data,
+        indices_data
+    })
+}
+
+/*
+meshes a single section of a chunk
+
+positions are in chunk space (y 0 to CHUNK_HEIGHT) so every section of a chunk shares the chunk's model matrix,
+the model matrix puts the bottom of the chunk at MIN_Y
+
+returns None if the chunk is not loaded, or the section has nothing to draw
+*/
+pub fn create_section_mesh(bcs: &BlockComponentSystem, world: &World, pos_x: i32, section: usize, pos_z: i32) -> Option<ChunkMeshData> {
+
+    let chunk: &ChunkStorage<u32> = world.get_chunk_blocks(pos_x, pos_z)?;
+
+    let rotation: &ChunkStorage<u8> = world.get_chunk_rotation(pos_x, pos_z)?;
+
+    let light: &ChunkStorage<u8> = world.get_chunk_light(pos_x, pos_z)?;
+
+    let neighbors: Neighbors = Neighbors::new(world, pos_x, pos_z);
+
+    mesh_section(bcs, chunk, rotation, light, &neighbors, section)
+}
+
+/*
+borrow the entire world
+
+returns None if the chunk is not loaded, otherwise a mesh option per section
+*/
+pub fn create_chunk_mesh(bcs: &BlockComponentSystem, world: &World, pos_x: i32, pos_z: i32) -> Option<Vec<Option<ChunkMeshData>>> {
+
+    let chunk: &ChunkStorage<u32> = world.get_chunk_blocks(pos_x, pos_z)?;
+
+    let rotation: &ChunkStorage<u8> = world.get_chunk_rotation(pos_x, pos_z)?;
+
+    let light: &ChunkStorage<u8> = world.get_chunk_light(pos_x, pos_z)?;
+
+    let neighbors: Neighbors = Neighbors::new(world, pos_x, pos_z);
+
+    // fast path - a chunk of pure air has nothing to draw
+    if chunk.get_single() == Some(0) {
+        return Some((0..chunk.get_section_count()).map(|_| None).collect());
+    }
 
-    Some(returning_mesh)
+    Some(
+        (0..chunk.get_section_count())
+            .map(|section| mesh_section(bcs, chunk, rotation, light, &neighbors, section))
+            .collect()
+    )
 }
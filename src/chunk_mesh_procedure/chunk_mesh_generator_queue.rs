@@ -1,10 +1,23 @@
-use std::collections::VecDeque;
+use std::collections::{
+    HashSet,
+    VecDeque
+};
+
+use crate::world::{
+    world::World,
+    world_event::{
+        WorldEvent,
+        WorldObserver
+    }
+};
 
 
 // mesh updates hold data on which chunks should be updated
 pub struct MeshUpdate {
     x: i32,
     z: i32,
+    // None rebuilds every section in the chunk
+    section: Option<usize>,
     update_neighbors: bool
 }
 
@@ -15,13 +28,20 @@ impl MeshUpdate {
     pub fn get_z(&self) -> i32 {
         self.z
     }
+    pub fn get_section(&self) -> Option<usize> {
+        self.section
+    }
     pub fn update_neighbors(&self) -> bool {
         self.update_neighbors
     }
 }
 
 pub struct ChunkMeshGeneratorQueue {
-    queue: VecDeque<MeshUpdate>
+    queue: VecDeque<MeshUpdate>,
+    // chunks with a whole chunk update waiting, one is enough - it keeps its place and updates its neighbors if any of them asked to
+    pending: HashSet<(i32, i32)>,
+    // sections with a section update waiting
+    pending_sections: HashSet<(i32, usize, i32)>
 }
 
 // this is a lot like Deque in Java - at least the parts that I use
@@ -29,6 +49,8 @@ impl ChunkMeshGeneratorQueue {
     pub fn new() -> Self {
         ChunkMeshGeneratorQueue {
             queue: VecDeque::new(),
+            pending: HashSet::new(),
+            pending_sections: HashSet::new(),
         }
     }
     // stores an update for all neighbors - does not recursively generate more
@@ -40,27 +62,154 @@ impl ChunkMeshGeneratorQueue {
     }
 
     pub fn push_front(&mut self, x: i32, z: i32, update_neighbors: bool){
+        if !self.pending.insert((x, z)) {
+            self.upgrade(x, z, update_neighbors);
+            return;
+        }
         let update: MeshUpdate = MeshUpdate {
             x,
             z,
+            section: None,
             update_neighbors,
         };
         self.queue.push_front(update);
     }
 
     pub fn push_back(&mut self, x: i32, z: i32, update_neighbors: bool){
+        if !self.pending.insert((x, z)) {
+            self.upgrade(x, z, update_neighbors);
+            return;
+        }
         let update: MeshUpdate = MeshUpdate {
             x,
             z,
+            section: None,
             update_neighbors,
         };
         self.queue.push_back(update);
     }
 
+    // a single section update - used by block edits so only what changed is rebuilt
+    pub fn push_section_front(&mut self, x: i32, section: usize, z: i32){
+        if !self.pending_sections.insert((x, section, z)) {
+            return;
+        }
+        let update: MeshUpdate = MeshUpdate {
+            x,
+            z,
+            section: Some(section),
+            update_neighbors: false,
+        };
+        self.queue.push_front(update);
+    }
+
     pub fn pop_front(&mut self) -> Option<MeshUpdate> {
-        self.queue.pop_front()
+        let update: MeshUpdate = self.queue.pop_front()?;
+        self.popped(&update);
+        Some(update)
     }
     pub fn pop_back(&mut self) -> Option<MeshUpdate> {
-        self.queue.pop_back()
+        let update: MeshUpdate = self.queue.pop_back()?;
+        self.popped(&update);
+        Some(update)
+    }
+
+    // forgets every update for the chunk, whole chunk and section updates alike
+    pub fn remove(&mut self, x: i32, z: i32) {
+        self.queue.retain(|update| update.x != x || update.z != z);
+        self.pending.remove(&(x, z));
+        self.pending_sections.retain(|(section_x, _, section_z)| *section_x != x || *section_z != z);
+    }
+
+    // the chunk already has a whole chunk update waiting, it updates the neighbors too if this one would have
+    fn upgrade(&mut self, x: i32, z: i32, update_neighbors: bool) {
+        if !update_neighbors {
+            return;
+        }
+        if let Some(update) = self.queue.iter_mut().find(|update| update.x == x && update.z == z && update.section.is_none()) {
+            update.update_neighbors = true;
+        }
+    }
+
+    fn popped(&mut self, update: &MeshUpdate) {
+        match update.section {
+            Some(section) => self.pending_sections.remove(&(update.x, section, update.z)),
+            None => self.pending.remove(&(update.x, update.z)),
+        };
+    }
+}
+
+// the queue keeps itself up to date by watching the world
+impl WorldObserver for ChunkMeshGeneratorQueue {
+    fn on_world_event(&mut self, _world: &World, event: &WorldEvent) {
+        match *event {
+            // a new chunk is meshed in load order, its neighbors can see it now
+            WorldEvent::ChunkAdded { x, z } | WorldEvent::ChunkGenerated { x, z } => self.push_back(x, z, true),
+
+            WorldEvent::BlockSet { x, y, z, .. } | WorldEvent::BlockRotated { x, y, z, .. } => {
+                World::for_each_remesh_section(x, y, z, |chunk_x, section, chunk_z| {
+                    self.push_section_front(chunk_x, section, chunk_z);
+                });
+            },
+
+            WorldEvent::ChunkEdited { x, z } | WorldEvent::ChunkRelit { x, z } => self.push_front(x, z, false),
+
+            WorldEvent::LightChanged { x, section, z } => self.push_section_front(x, section, z),
+
+            // nothing left to mesh
+            WorldEvent::ChunkRemoved { x, z } => self.remove(x, z),
+
+            // the block was never put back, nothing to draw
+            WorldEvent::BlockDropped { .. } => (),
+        }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn a_generated_chunk_still_updates_its_neighbors_when_an_update_is_waiting() {
+        let mut queue: ChunkMeshGeneratorQueue = ChunkMeshGeneratorQueue::new();
+
+        queue.push_front(0, 0, false);
+        queue.push_back(0, 0, true);
+        queue.push_back(1, 0, true);
+        queue.push_front(1, 0, false);
+
+        let first: MeshUpdate = queue.pop_front().unwrap();
+        assert_eq!((first.get_x(), first.get_z()), (0, 0));
+        assert!(first.update_neighbors());
+
+        // a later update without neighbors never takes them away
+        let second: MeshUpdate = queue.pop_front().unwrap();
+        assert_eq!((second.get_x(), second.get_z()), (1, 0));
+        assert!(second.update_neighbors());
+
+        assert!(queue.pop_front().is_none());
+    }
+
+    #[test]
+    fn a_section_is_only_queued_once_until_it_is_popped() {
+        let mut queue: ChunkMeshGeneratorQueue = ChunkMeshGeneratorQueue::new();
+
+        queue.push_section_front(0, 2, 0);
+        queue.push_section_front(0, 2, 0);
+        queue.push_section_front(0, 3, 0);
+
+        assert_eq!(queue.pop_back().unwrap().get_section(), Some(2));
+        assert_eq!(queue.pop_back().unwrap().get_section(), Some(3));
+        assert!(queue.pop_back().is_none());
+
+        queue.push_section_front(0, 2, 0);
+        assert_eq!(queue.pop_front().unwrap().get_section(), Some(2));
+
+        // a removed chunk can queue its sections again
+        queue.push_section_front(1, 2, 0);
+        queue.remove(1, 0);
+        queue.push_section_front(1, 2, 0);
+        assert_eq!(queue.pop_front().unwrap().get_section(), Some(2));
+        assert!(queue.pop_front().is_none());
+    }
+}
@@ -0,0 +1,320 @@
+/*
+the chunk mesh service - meshes chunks off the main thread
+
+a mesh update is handed over with a snapshot of the chunk and its neighbors, a worker
+on the generation pool meshes the snapshot and the finished sections come back through
+a channel - uploading them to the GPU stays on the main thread
+
+the world keeps changing while a mesh is being built, every change queues another
+update, so a mesh can come back after a newer one of the same section - every request
+is stamped and a section only ever takes a mesh newer than the one it has
+*/
+
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        mpsc::{
+            self,
+            Sender,
+            Receiver
+        }
+    }
+};
+
+use rayon::ThreadPool;
+
+use crate::{
+    blocks::block_component_system::BlockComponentSystem,
+    world::world::World
+};
+
+use super::{
+    chunk_mesh_creation::{
+        ChunkMeshData,
+        create_chunk_mesh,
+        create_section_mesh
+    },
+    chunk_mesh_generator_queue::MeshUpdate
+};
+
+// the sections of a chunk a worker meshed
+pub struct FinishedMesh {
+    x: i32,
+    z: i32,
+    // the order the request was made in
+    stamp: u64,
+    // (section, mesh data) - None if the section has nothing to draw
+    sections: Vec<(usize, Option<ChunkMeshData>)>
+}
+
+impl FinishedMesh {
+    pub fn get_pos(&self) -> (i32, i32) {
+        (self.x, self.z)
+    }
+
+    pub fn unpack(self) -> Vec<(usize, Option<ChunkMeshData>)> {
+        self.sections
+    }
+}
+
+// meshes what the update asks for out of the snapshot, nothing if the chunk wasn't loaded
+fn mesh_update(bcs: &BlockComponentSystem, snapshot: &World, x: i32, z: i32, section: Option<usize>) -> Vec<(usize, Option<ChunkMeshData>)> {
+    match section {
+        // block edits only rebuild their section
+        Some(section) => {
+            if snapshot.chunk_exists(x, z) {
+                vec![(section, create_section_mesh(bcs, snapshot, x, section, z))]
+            } else {
+                Vec::new()
+            }
+        },
+        None => match create_chunk_mesh(bcs, snapshot, x, z) {
+            Some(chunk_meshes) => chunk_meshes.into_iter().enumerate().collect(),
+            None => Vec::new(),
+        },
+    }
+}
+
+pub struct ChunkMeshService {
+    pool: Arc<ThreadPool>,
+    sender: Sender<FinishedMesh>,
+    receiver: Receiver<FinishedMesh>,
+    // requests that haven't come back yet
+    in_flight: usize,
+    next_stamp: u64,
+    // (x, section, z) -> the stamp of the mesh the section has now
+    mesh_stamps: HashMap<(i32, usize, i32), u64>
+}
+
+impl ChunkMeshService {
+    pub fn new(pool: Arc<ThreadPool>) -> Self {
+        let (sender, receiver) = mpsc::channel();
+
+        Self {
+            pool,
+            sender,
+            receiver,
+            in_flight: 0,
+            next_stamp: 0,
+            mesh_stamps: HashMap::new()
+        }
+    }
+
+    // how many meshes are being built
+    pub fn get_number_in_flight(&self) -> usize {
+        self.in_flight
+    }
+
+    // enough to keep every worker busy without snapshotting the whole queue at once
+    pub fn get_max_in_flight(&self) -> usize {
+        self.pool.current_num_threads() * 2
+    }
+
+    // meshes what update asks for on the pool, from a snapshot of the world as it is right now
+    pub fn request(&mut self, bcs: &Arc<BlockComponentSystem>, world: &World, update: &MeshUpdate) {
+
+        let (x, z, section) = (update.get_x(), update.get_z(), update.get_section());
+
+        let snapshot: World = world.snapshot(x, z);
+
+        let stamp: u64 = self.next_stamp;
+        self.next_stamp += 1;
+        self.in_flight += 1;
+
+        let bcs: Arc<BlockComponentSystem> = bcs.clone();
+        let sender: Sender<FinishedMesh> = self.sender.clone();
+
+        self.pool.spawn(move || {
+            let sections: Vec<(usize, Option<ChunkMeshData>)> = mesh_update(&bcs, &snapshot, x, z, section);
+
+            // the service is gone, nobody wants the mesh anymore
+            let _ = sender.send(FinishedMesh {
+                x,
+                z,
+                stamp,
+                sections
+            });
+        });
+    }
+
+    /*
+    the next finished mesh of a chunk still in world, with only the sections that are newer
+    than what they have - None if there isn't one yet
+
+    the sections kept are taken as the ones the chunk has now
+    */
+    pub fn try_receive(&mut self, world: &World) -> Option<FinishedMesh> {
+        loop {
+            let finished_mesh: FinishedMesh = self.receiver.try_recv().ok()?;
+
+            self.in_flight -= 1;
+
+            // it was unloaded while it was being meshed
+            if world.chunk_exists(finished_mesh.x, finished_mesh.z) {
+                return Some(self.keep_newer(finished_mesh));
+            }
+        }
+    }
+
+    fn keep_newer(&mut self, mut finished_mesh: FinishedMesh) -> FinishedMesh {
+        let (x, z, stamp) = (finished_mesh.x, finished_mesh.z, finished_mesh.stamp);
+
+        finished_mesh.sections.retain(|(section, _)| {
+            match self.mesh_stamps.get(&(x, *section, z)) {
+                Some(current) if *current > stamp => false,
+                _ => {
+                    self.mesh_stamps.insert((x, *section, z), stamp);
+                    true
+                },
+            }
+        });
+
+        finished_mesh
+    }
+
+    // the chunk was taken out of the world, its sections start over when it comes back
+    pub fn forget(&mut self, x: i32, z: i32) {
+        self.mesh_stamps.retain(|(section_x, _, section_z), _| *section_x != x || *section_z != z);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        thread,
+        time::Duration
+    };
+
+    use rayon::ThreadPoolBuilder;
+
+    use crate::{
+        blocks::block_component_system::{
+            AtlasTextureMap,
+            DrawType,
+            RotationType
+        },
+        chunk_mesh_procedure::chunk_mesh_generator_queue::ChunkMeshGeneratorQueue
+    };
+
+    use super::*;
+
+    // air and stone, the mesher needs a texture for every face
+    fn test_bcs() -> (Arc<BlockComponentSystem>, u32) {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+
+        let mapping: Vec<AtlasTextureMap> = (0..6).map(|_| AtlasTextureMap::new(0.0, 0.0, 1.0, 1.0, 0, 0)).collect();
+
+        let stone: u32 = bcs.register_block(String::from("test"), String::from("stone"), DrawType::Normal, vec![], None, mapping, 0, RotationType::None);
+
+        (Arc::new(bcs), stone)
+    }
+
+    fn test_service() -> ChunkMeshService {
+        ChunkMeshService::new(Arc::new(ThreadPoolBuilder::new().num_threads(2).build().unwrap()))
+    }
+
+    // the update the queue hands out for a whole chunk, or one section of it
+    fn update(x: i32, z: i32, section: Option<usize>) -> MeshUpdate {
+        let mut queue: ChunkMeshGeneratorQueue = ChunkMeshGeneratorQueue::new();
+
+        match section {
+            Some(section) => queue.push_section_front(x, section, z),
+            None => queue.push_front(x, z, false),
+        }
+
+        queue.pop_front().unwrap()
+    }
+
+    // receives until nothing is in flight
+    fn receive_all(service: &mut ChunkMeshService, world: &World) -> Vec<FinishedMesh> {
+        let mut received: Vec<FinishedMesh> = Vec::new();
+
+        for _ in 0..1000 {
+            if service.get_number_in_flight() == 0 {
+                return received;
+            }
+
+            match service.try_receive(world) {
+                Some(finished_mesh) => received.push(finished_mesh),
+                None => thread::sleep(Duration::from_millis(5)),
+            }
+        }
+
+        panic!("MESHING NEVER FINISHED!");
+    }
+
+    fn float_data(mesh_data: &Option<ChunkMeshData>) -> Option<Vec<f32>> {
+        mesh_data.as_ref().map(|mesh_data| mesh_data.get_float_data().clone())
+    }
+
+    #[test]
+    fn a_worker_meshes_the_same_as_the_main_thread() {
+        let (bcs, stone) = test_bcs();
+        let mut service: ChunkMeshService = test_service();
+
+        let mut world: World = World::initialize();
+        world.add_chunk(0, 0);
+        world.add_chunk(1, 0);
+        world.set_block(15, 20, 3, stone);
+        world.set_block(16, 20, 3, stone);
+        world.set_block(4, 70, 9, stone);
+
+        service.request(&bcs, &world, &update(0, 0, None));
+        service.request(&bcs, &world, &update(0, 0, Some(1)));
+        // never loaded, nothing comes back
+        service.request(&bcs, &world, &update(5, 5, None));
+
+        // changed after the request, the worker meshes the world as it was
+        let expected: Vec<Option<ChunkMeshData>> = create_chunk_mesh(&bcs, &world, 0, 0).unwrap();
+        world.set_block(4, 20, 9, stone);
+
+        let mut received: Vec<FinishedMesh> = receive_all(&mut service, &world);
+        received.sort_by_key(|finished_mesh| finished_mesh.stamp);
+
+        assert_eq!(received.len(), 2);
+
+        let sections: Vec<(usize, Option<ChunkMeshData>)> = received.remove(0).unpack();
+        assert_eq!(sections.len(), expected.len());
+        for ((section, mesh_data), expected_mesh_data) in sections.iter().zip(expected.iter()) {
+            assert_eq!(float_data(mesh_data), float_data(expected_mesh_data), "section {}", section);
+        }
+
+        // the section update came back after the whole chunk, it still replaces section 1
+        let sections: Vec<(usize, Option<ChunkMeshData>)> = received.remove(0).unpack();
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].0, 1);
+        assert_eq!(float_data(&sections[0].1), float_data(&expected[1]));
+    }
+
+    #[test]
+    fn an_older_mesh_coming_back_late_is_dropped() {
+        let mut service: ChunkMeshService = test_service();
+
+        let finished = |stamp: u64, sections: &[usize]| FinishedMesh {
+            x: 0,
+            z: 0,
+            stamp,
+            sections: sections.iter().map(|section| (*section, None)).collect()
+        };
+
+        let sections_of = |finished_mesh: FinishedMesh| -> Vec<usize> {
+            finished_mesh.unpack().into_iter().map(|(section, _)| section).collect()
+        };
+
+        assert_eq!(sections_of(service.keep_newer(finished(3, &[0, 1, 2]))), vec![0, 1, 2]);
+
+        // section 1 was edited and meshed again after the whole chunk
+        assert_eq!(sections_of(service.keep_newer(finished(5, &[1]))), vec![1]);
+
+        // a whole chunk mesh between the two only replaces the sections it's newer than
+        assert_eq!(sections_of(service.keep_newer(finished(4, &[0, 1, 2]))), vec![0, 2]);
+
+        // and one from before all of them replaces nothing
+        assert!(sections_of(service.keep_newer(finished(1, &[0, 1, 2]))).is_empty());
+
+        // once the chunk is gone the next mesh for it is taken whatever its stamp
+        service.forget(0, 0);
+        assert_eq!(sections_of(service.keep_newer(finished(2, &[1]))), vec![1]);
+    }
+}
@@ -1,3 +1,4 @@
 pub mod chunk_mesh_creation;
 pub mod chunk_mesh_boilerplate;
-pub mod chunk_mesh_generator_queue;
\ No newline at end of file
+pub mod chunk_mesh_generator_queue;
+pub mod chunk_mesh_service;
@@ -2,7 +2,14 @@ use std::f32::consts::PI;
 
 use glam::{Vec3, IVec2};
 
-use crate::controls::{mouse::Mouse, keyboard::Keyboard};
+use crate::{
+    controls::{mouse::Mouse, keyboard::Keyboard},
+    world::{
+        world::World,
+        raycast::{raycast, RaycastHit},
+        dimension::OVERWORLD
+    }
+};
 
 
 pub struct Camera {
@@ -14,7 +21,10 @@ pub struct Camera {
 
     rotation: Vec3,
     rotation_vector: Vec3,
-    fov: f32
+    fov: f32,
+
+    // the dimension the camera is in, the renderer draws this one
+    dimension: String
 }
 
 impl Camera {
@@ -28,6 +38,7 @@ impl Camera {
             rotation: Vec3::new(0.0, 0.0,0.0),
             rotation_vector: Vec3::new(0.0, 0.0,0.0),
             fov: 60.0,
+            dimension: String::from(OVERWORLD)
         }
     }
 
@@ -77,6 +88,15 @@ impl Camera {
         self.fov
     }
 
+    pub fn get_dimension(&self) -> &String {
+        &self.dimension
+    }
+
+    // moves the camera into another dimension, it keeps its position
+    pub fn set_dimension(&mut self, dimension: &str) {
+        self.dimension = dimension.to_string();
+    }
+
     // methods
 
     // the rotation vector of the rotation
@@ -100,6 +120,11 @@ impl Camera {
 
     }
 
+    // the block the camera is looking at within reach
+    pub fn raycast(&self, world: &World, reach: f32) -> Option<RaycastHit> {
+        raycast(world, self.position, self.rotation_vector, reach)
+    }
+
     pub fn on_tick(&mut self, mouse: &Mouse, keyboard: &Keyboard, delta: f32) -> bool {
 
         self.old_pos_floored.clone_from(&self.pos_floored);
@@ -163,6 +188,8 @@ impl Camera {
             self.rotation.y += 360.0;
         }
 
+        // keep the look direction in step with the rotation
+        self.calculate_rotation_vector();
 
         // check if the world needs to resort the mesh order
 
@@ -3,7 +3,7 @@ use std::collections::HashMap;
 use glam::Vec3;
 use glfw::Window;
 
-use crate::{world::world::World};
+use crate::{world::{world::{World, MIN_Y}, dimension::Dimensions}};
 
 use super::{gl_safety_wrappers, shader_program::{ShaderProgram}, transformation::{Transformation}, camera::{Camera}, mesh_component_system::MeshComponentSystem};
 
@@ -54,11 +54,17 @@ impl Renderer {
     }    
 
     // this is a test
-    pub fn render(&mut self, mcs: &MeshComponentSystem, window: &Window, world: &mut World) {
+    // draws the dimension the camera is in
+    pub fn render(&mut self, mcs: &MeshComponentSystem, window: &Window, dimensions: &Dimensions) {
         
         gl_safety_wrappers::clear_depth_and_color(135.0 / 255.0, 206.0 / 255.0, 235.0 / 255.0, 1.0);
         // gl_safety_wrappers::clear_depth_and_color(113.0 / 255.0, 112.0 / 255.0, 114.0 / 255.0, 1.0);
 
+        let world: &World = match dimensions.get(self.camera.get_dimension()) {
+            Some(dimension) => dimension.get_world(),
+            None => return,
+        };
+
     
         let default_shader = self.shaders.get("default").unwrap();
 
@@ -76,37 +82,38 @@ impl Renderer {
         let mut worker_pos_vec = Vec3::splat(0.0);
         let worker_rot_vec = Vec3::splat(0.0);
 
-        // ((&pos_x, &pos_z), &Option<mesh_id>)
-        // ((&i32, &i32), &Option<i32>)
+        // (section mesh ids, (pos_x, pos_z))
         //for chunk_pos_mesh in world.iter_map() {
 
         for chunk_mesh_pos in world.get_map_sorted() {
         // for chunk in world.iter_map(){
-            match chunk_mesh_pos.0{
-
-                Some(mesh_id) => {
-
-                    worker_pos_vec.x = chunk_mesh_pos.1.0 as f32 * 16.0;
-                    worker_pos_vec.z = chunk_mesh_pos.1.1 as f32 * 16.0;
-
-                    default_shader.set_uniform_mat4(
-                        "model_matrix", 
-                        self.transformation.update_model_matrix(
-                            worker_pos_vec,
-                            worker_rot_vec
-                        )
-                    );
-
-                    // inialize batch
-                    if !batch_hook {
-                        batch_hook = true;
-                        mcs.batch_hook_texture(mesh_id);
-                    }
-
-                    mcs.batch_render(mesh_id);
-                    
-                },
-                None => (),
+
+            // every section mesh of a chunk is in chunk space, they share the model matrix
+            if chunk_mesh_pos.0.is_empty() {
+                continue;
+            }
+
+            worker_pos_vec.x = chunk_mesh_pos.1.0 as f32 * 16.0;
+            worker_pos_vec.y = MIN_Y as f32;
+            worker_pos_vec.z = chunk_mesh_pos.1.1 as f32 * 16.0;
+
+            default_shader.set_uniform_mat4(
+                "model_matrix", 
+                self.transformation.update_model_matrix(
+                    worker_pos_vec,
+                    worker_rot_vec
+                )
+            );
+
+            for mesh_id in chunk_mesh_pos.0.iter() {
+
+                // inialize batch
+                if !batch_hook {
+                    batch_hook = true;
+                    mcs.batch_hook_texture(*mesh_id);
+                }
+
+                mcs.batch_render(*mesh_id);
             }
         }
 
@@ -0,0 +1,22 @@
+/*
+the engine library
+
+everything in here runs without a window or an OpenGL context so tools,
+tests and servers can generate, mesh and inspect chunks headlessly
+
+the client feature adds the windowing, rendering and input modules on top
+*/
+
+pub mod helper;
+pub mod chunk_mesh_procedure;
+pub mod world;
+pub mod blocks;
+pub mod lua;
+pub mod biomes;
+
+#[cfg(feature = "client")]
+pub mod graphics;
+#[cfg(feature = "client")]
+pub mod controls;
+#[cfg(feature = "client")]
+pub mod time;
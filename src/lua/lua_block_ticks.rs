@@ -0,0 +1,121 @@
+use std::cell::RefCell;
+
+use mlua::{
+    Lua,
+    Table,
+    Function
+};
+
+use crate::{
+    blocks::block_component_system::BlockComponentSystem,
+    world::{
+        world::World,
+        lighting::set_block_lit,
+        tick_scheduler::{
+            TickScheduler,
+            BlockTick,
+            TickKind
+        }
+    }
+};
+
+/*
+runs block ticks through the lua callbacks of the blocks they belong to
+
+the callbacks get a world table to look around and change things with, it only
+exists while the ticks run - the world and scheduler are lent to lua for that
+long and no longer
+
+blocks set from lua go through set_block_lit, so light and world events follow
+like any other edit
+*/
+pub fn run_block_ticks(lua: &Lua, bcs: &BlockComponentSystem, world: &mut World, tick_scheduler: &mut TickScheduler, ticks: &[BlockTick]) {
+
+    if ticks.is_empty() {
+        return;
+    }
+
+    let world_cell: RefCell<&mut World> = RefCell::new(world);
+    let tick_scheduler_cell: RefCell<&mut TickScheduler> = RefCell::new(tick_scheduler);
+
+    let result: mlua::Result<()> = lua.scope(|scope| {
+
+        let world_table: Table = lua.create_table()?;
+
+        world_table.set("get_block", scope.create_function(|_, (x, y, z): (i32, i32, i32)| {
+            Ok(world_cell.borrow().get_block(x, y, z).map(|id| bcs.get_name(id).clone()))
+        })?)?;
+
+        world_table.set("set_block", scope.create_function(|_, (x, y, z, name, rotation): (i32, i32, i32, String, Option<u8>)| {
+            // a bad name is not worth taking the game down over
+            let id: u32 = match bcs.find_id_of(&name) {
+                Some(id) => id,
+                None => {
+                    println!("LUA TRIED TO SET UNKNOWN BLOCK: {}!", name);
+                    return Ok(false);
+                },
+            };
+
+            let mut world = world_cell.borrow_mut();
+
+            if !set_block_lit(&mut world, bcs, x, y, z, id) {
+                return Ok(false);
+            }
+
+            world.set_block_rotation(x, y, z, rotation.unwrap_or(0));
+
+            Ok(true)
+        })?)?;
+
+        world_table.set("get_block_rotation", scope.create_function(|_, (x, y, z): (i32, i32, i32)| {
+            Ok(world_cell.borrow().get_block_rotation(x, y, z))
+        })?)?;
+
+        // sky light, block light
+        world_table.set("get_light", scope.create_function(|_, (x, y, z): (i32, i32, i32)| {
+            Ok(match world_cell.borrow().get_block_light(x, y, z) {
+                Some(light) => (Some(light >> 4), Some(light & 0x0F)),
+                None => (None, None),
+            })
+        })?)?;
+
+        world_table.set("schedule_update", scope.create_function(|_, (x, y, z, ticks): (i32, i32, i32, u64)| {
+            Ok(tick_scheduler_cell.borrow_mut().schedule(x, y, z, ticks))
+        })?)?;
+
+        world_table.set("get_tick", scope.create_function(|_, ()| {
+            Ok(tick_scheduler_cell.borrow().get_tick())
+        })?)?;
+
+        let run_block_tick: Function = lua.globals().get("run_block_tick")?;
+
+        for tick in ticks {
+
+            // liquids are ticked for their flow too, lua only gets the blocks with a callback
+            let has_callback: bool = match tick.get_kind() {
+                TickKind::Random => bcs.has_random_tick(tick.get_id()),
+                TickKind::Scheduled => bcs.has_scheduled_update(tick.get_id()),
+            };
+
+            if !has_callback {
+                continue;
+            }
+
+            let (x, y, z) = tick.get_pos();
+
+            let pos: Table = lua.create_table()?;
+            pos.set("x", x)?;
+            pos.set("y", y)?;
+            pos.set("z", z)?;
+
+            run_block_tick.call::<_, ()>((tick.get_kind().get_name(), bcs.get_name(tick.get_id()).as_str(), pos, world_table.clone()))?;
+        }
+
+        Ok(())
+    });
+
+    match result {
+        Ok(_) => (),
+        Err(error) => println!("COULD NOT RUN BLOCK TICKS: {}!", error),
+    }
+}
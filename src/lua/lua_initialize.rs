@@ -6,12 +6,18 @@ use crate::helper::helper_functions::get_path_string;
 
 use super::lua_functions::load_lua_file;
 
-pub fn initialize_lua() -> Lua {
+// enabled_mods limits which mods get loaded, None loads everything in the mods folder
+pub fn initialize_lua(enabled_mods: Option<&[String]>) -> Lua {
     let lua: Lua = Lua::new();
     
     // tells lua which operating system is being used
     lua.globals().set("operating_system", env::consts::OS).unwrap();
 
+    // tells lua which mods the world is played with
+    if let Some(mods) = enabled_mods {
+        lua.globals().set("enabled_mods", mods.to_vec()).unwrap();
+    }
+
     // tells lua where the root of the folder is
     lua.globals().set("current_working_directory", get_path_string()).unwrap();
 
@@ -1,13 +1,18 @@
-use std::path::Path;
+use std::{
+    collections::HashMap,
+    path::Path
+};
 
 use image::{
-    DynamicImage
+    DynamicImage,
+    RgbaImage
 };
 use mlua::{
     Lua,
     Table,
     prelude,
-    Integer, Error
+    Integer, Error,
+    Value
 };
 use texture_packer::{
     importer::ImageImporter,
@@ -20,13 +25,22 @@ use crate::{
     blocks::block_component_system::{
         BlockComponentSystem,
         DrawType,
-        BlockBox, AtlasTextureMap
+        RotationType,
+        BlockBox, AtlasTextureMap,
+        LiquidDefinition,
+        LiquidInteraction
     },
-    graphics::mesh_component_system::MeshComponentSystem,
     helper::helper_functions::with_path,
     lua::lua_texture_atlas_calculation::{
         calculate_atlas_location_normal
-    }, biomes::generation_component_system::{LayerDepth, NoiseParams, GenerationComponentSystem, BiomeOres}
+    }, biomes::{
+        generation_component_system::{LayerDepth, NoiseParams, TerrainNoiseParams, GenerationComponentSystem, BiomeOres, Climate, ClimateRange},
+        decoration::Decoration
+    },
+    world::{
+        dimension::DimensionDefinition,
+        world_descriptor::GeneratorType
+    }
 };
 
 
@@ -58,6 +72,14 @@ fn configure_texture_atlas(module_name: &str, texture_name: &str, number_of_text
     *number_of_textures += 1;
 }
 
+// lua turned every climate value a biome gave into a {min, max} table, missing means any value goes
+fn get_climate_range(climate_table: &Table, name: &str) -> Option<ClimateRange> {
+    climate_table.get::<_, Option<Table>>(name).unwrap().map(|range_table| ClimateRange::new(
+        range_table.get(1).unwrap(),
+        range_table.get(2).unwrap()
+    ))
+}
+
 fn create_texture(module_name: &str, texture_name: &str) -> DynamicImage {
     let string_path: String = with_path( &("/mods/".to_owned() + module_name + "/textures/" + texture_name) );
     let path: &Path = Path::new(&string_path);
@@ -65,7 +87,9 @@ fn create_texture(module_name: &str, texture_name: &str) -> DynamicImage {
 }
 
 
-pub fn intake_api_values(lua: &Lua, gcs: &mut GenerationComponentSystem, mcs: &mut MeshComponentSystem, bcs: &mut BlockComponentSystem) {
+// returns the packed texture atlas - the client uploads it, headless users can ignore it
+// every registered dimension is pushed into dimensions with its biomes
+pub fn intake_api_values(lua: &Lua, dimensions: &mut Vec<DimensionDefinition>, bcs: &mut BlockComponentSystem) -> RgbaImage {
 
     // this follows the same pattern as lua
     let crafter: Table = lua.globals().get("crafter").unwrap();
@@ -165,7 +189,7 @@ pub fn intake_api_values(lua: &Lua, gcs: &mut GenerationComponentSystem, mcs: &m
     let blocks: Table = crafter.get("blocks").unwrap();
 
     // intake all data from lua
-    for blocks in blocks.pairs::<String, Table>() {
+    for blocks in blocks.clone().pairs::<String, Table>() {
 
         let (_, lua_table) = blocks.unwrap();
 
@@ -218,12 +242,23 @@ pub fn intake_api_values(lua: &Lua, gcs: &mut GenerationComponentSystem, mcs: &m
                     "normal" => draw_type = DrawType::Normal,
                     "airlike" => draw_type = DrawType::None,
                     "block_box" => draw_type = DrawType::BlockBox,
+                    "liquid" => draw_type = DrawType::Liquid,
                     _ => draw_type = DrawType::Normal
                 }
             },
             Err(_) => todo!(),
         }
 
+        // light level was checked and floored in lua, it is always there
+        let block_light: u8 = lua_table.get::<_, Integer>("light").unwrap() as u8;
+
+        // rotation type was checked in lua, it is always there
+        let rotation_type: RotationType = match lua_table.get::<_, String>("rotation_type").unwrap().as_str() {
+            "horizontal" => RotationType::Horizontal,
+            "full" => RotationType::Full,
+            _ => RotationType::None
+        };
+
         /*
         precalculate mapping on texture atlas - but only if it's a block box
 
@@ -296,8 +331,8 @@ pub fn intake_api_values(lua: &Lua, gcs: &mut GenerationComponentSystem, mcs: &m
         match draw_type {
             // nothing needs to be done
             DrawType::None => (),
-            // a full block - nothing special is needed
-            DrawType::Normal => {
+            // a full block - nothing special is needed, liquids are only shorter
+            DrawType::Normal | DrawType::Liquid => {
                 // println!("---- debugging {} ------", block_name.clone());
                 // this will return an AtlasTextureMap per face
                 let mut index = 0;
@@ -326,21 +361,114 @@ pub fn intake_api_values(lua: &Lua, gcs: &mut GenerationComponentSystem, mcs: &m
         }
 
 
-        bcs.register_block(
+        let block_id: u32 = bcs.register_block(
             block_mod,
             block_name,
             draw_type,
             block_textures,
             block_box_option,
-            mapping
-        )
+            mapping,
+            block_light,
+            rotation_type
+        );
+
+        // lua made sure the groups are there and every rating is a whole number
+        let groups: HashMap<String, i32> = lua_table.get::<_, Table>("groups")
+            .unwrap()
+            .pairs::<String, Integer>()
+            .map(|group| {
+                let (name, rating) = group.unwrap();
+                (name, rating as i32)
+            })
+            .collect();
+
+        bcs.set_block_groups(block_id, groups);
+
+        // the callbacks stay in lua, rust only needs to know which blocks have them
+        bcs.set_block_ticks(
+            block_id,
+            matches!(lua_table.get::<_, Value>("on_random_tick"), Ok(Value::Function(_))),
+            matches!(lua_table.get::<_, Value>("on_scheduled_update"), Ok(Value::Function(_)))
+        );
     } 
 
-    // texture atlas will always be id 1
-    let value_test = mcs.new_texture_from_memory(atlas.as_rgba8().unwrap().to_owned());
 
-    println!("TEXTURE ATLAS IS VALUE: {}", value_test);
+    // liquids interact with blocks by name, so they're done once every block has an id
+    for blocks in blocks.pairs::<String, Table>() {
+
+        let (block_name, lua_table) = blocks.unwrap();
+
+        // lua made sure every liquid draw type has a liquid table and nothing else does
+        let liquid_table: Table = match lua_table.get::<_, Option<Table>>("liquid").unwrap() {
+            Some(liquid_table) => liquid_table,
+            None => continue,
+        };
+
+        let find_block = | name: &str | -> u32 {
+            match bcs.find_id_of(name) {
+                Some(id) => id,
+                None => panic!("LIQUID {} INTERACTS WITH NON-EXISTENT BLOCK: {}!", block_name, name),
+            }
+        };
+
+        let mut interactions: Vec<LiquidInteraction> = Vec::new();
+
+        // lua turned every interaction into a source and flowing result
+        let interactions_table: Table = liquid_table.get("interactions").unwrap();
+
+        for interaction in interactions_table.pairs::<String, Table>() {
+
+            let (other_liquid, results) = interaction.unwrap();
+
+            let other_liquid_id: u32 = find_block(&other_liquid);
+
+            let source_result: String = results.get("source").unwrap();
+            let flowing_result: String = results.get("flowing").unwrap();
+
+            interactions.push(LiquidInteraction::new(
+                other_liquid_id,
+                find_block(&source_result),
+                find_block(&flowing_result)
+            ));
+        }
+
+        let liquid: LiquidDefinition = LiquidDefinition::new(
+            liquid_table.get::<_, Integer>("viscosity").unwrap() as u32,
+            liquid_table.get::<_, Integer>("range").unwrap() as u8,
+            liquid_table.get("renewable").unwrap(),
+            interactions
+        );
+
+        bcs.set_liquid(bcs.get_id_of(block_name), liquid);
+    }
+
+
+    // begin iterating dimension data, biomes need somewhere to go
+
+    // iterating crafter.dimensions - lua keeps these in order
+    let lua_dimensions: Table = crafter.get("dimensions").unwrap();
+
+    for dimension_option in lua_dimensions.sequence_values::<Table>() {
+
+        let dimension_table: Table = dimension_option.unwrap();
+
+        let dimension_name: String = dimension_table.get("name").unwrap();
 
+        // lua checked this is a valid name
+        let generator: Option<GeneratorType> = dimension_table.get::<_, Option<String>>("generator")
+            .unwrap()
+            .and_then(|generator_name| GeneratorType::from_name(&generator_name));
+
+        let mut gcs: GenerationComponentSystem = GenerationComponentSystem::new();
+
+        gcs.set_base_height(dimension_table.get::<_, Integer>("base_height").unwrap() as i32);
+
+        gcs.set_biome_blend_radius(dimension_table.get::<_, Integer>("biome_blend_radius").unwrap() as i32);
+
+        println!("DIMENSION: {} IS ID: {}", dimension_name, dimensions.len());
+
+        dimensions.push(DimensionDefinition::new(dimension_name, generator, gcs));
+    }
 
     // begin iterating biome data
 
@@ -353,6 +481,15 @@ pub fn intake_api_values(lua: &Lua, gcs: &mut GenerationComponentSystem, mcs: &m
 
         let game_mod: String = biome_table.get("mod").unwrap();
 
+        let dimension_name: String = biome_table.get("dimension").unwrap();
+
+        // lua made sure the dimension exists
+        let gcs: &mut GenerationComponentSystem = dimensions
+            .iter_mut()
+            .find(|dimension| dimension.get_name() == &dimension_name)
+            .unwrap()
+            .get_gcs_mut();
+
         let top_layer: String = biome_table.get("top_layer").unwrap();
 
         let top_layer_depth_table: Table = biome_table.get("top_layer_depth").unwrap();
@@ -443,20 +580,28 @@ pub fn intake_api_values(lua: &Lua, gcs: &mut GenerationComponentSystem, mcs: &m
         }
 
         // getting biome noise parameters
-        let lua_biome_heat_params: Table = biome_table.get("biome_noise_params").unwrap();
+        let lua_biome_terrain_params: Table = biome_table.get("biome_noise_params").unwrap();
 
-        let biome_noise_params: NoiseParams = NoiseParams::new(
-            lua_biome_heat_params.get("heat_min").unwrap(),
-            lua_biome_heat_params.get("heat_max").unwrap(),
-            lua_biome_heat_params.get("scale").unwrap(),
-            lua_biome_heat_params.get("frequency").unwrap()
+        // where the biome goes is in the climate, these only shape the terrain
+        let biome_noise_params: TerrainNoiseParams = TerrainNoiseParams::new(
+            lua_biome_terrain_params.get("scale").unwrap(),
+            lua_biome_terrain_params.get("frequency").unwrap()
         );
 
+        // getting where the biome generates
+        let climate_table: Table = biome_table.get("climate").unwrap();
+
+        let climate: Climate = Climate::new(
+            get_climate_range(&climate_table, "heat"),
+            get_climate_range(&climate_table, "humidity"),
+            get_climate_range(&climate_table, "elevation")
+        );
 
 
         gcs.register_biome(
             biome_name,
             biome_noise_params,
+            climate,
             terrain_height_flux,
             game_mod,
             bcs.get_id_of(top_layer),
@@ -473,6 +618,78 @@ pub fn intake_api_values(lua: &Lua, gcs: &mut GenerationComponentSystem, mcs: &m
         );
     }
 
+    // begin iterating decoration data, after the biomes so their names mean something
+
+    // iterating crafter.decorations - lua keeps these in order, the order decides which decoration wins a block
+    let decorations: Table = crafter.get("decorations").unwrap();
+
+    for decoration_option in decorations.sequence_values::<Table>() {
+
+        let decoration_table: Table = decoration_option.unwrap();
+
+        let decoration_name: String = decoration_table.get("name").unwrap();
+
+        let game_mod: String = decoration_table.get("mod").unwrap();
+
+        let dimension_name: String = decoration_table.get("dimension").unwrap();
+
+        let biomes: Vec<String> = decoration_table.get::<_, Table>("biomes").unwrap()
+            .sequence_values::<String>()
+            .map(|biome| biome.unwrap())
+            .collect();
+
+        // lua made sure all of these blocks exist
+        let place_on: Vec<u32> = decoration_table.get::<_, Table>("place_on").unwrap()
+            .sequence_values::<String>()
+            .map(|block| bcs.get_id_of(block.unwrap()))
+            .collect();
+
+        let fill_ratio: f32 = decoration_table.get("fill_ratio").unwrap();
+
+        let mut blocks: Vec<((i32, i32, i32), u32, u8)> = Vec::new();
+
+        for block_option in decoration_table.get::<_, Table>("blocks").unwrap().sequence_values::<Table>() {
+
+            let block_table: Table = block_option.unwrap();
+
+            blocks.push((
+                (
+                    block_table.get("x").unwrap(),
+                    block_table.get("y").unwrap(),
+                    block_table.get("z").unwrap()
+                ),
+                bcs.get_id_of(block_table.get("name").unwrap()),
+                block_table.get("rotation").unwrap()
+            ));
+        }
+
+        // lua made sure the dimension exists
+        let gcs: &mut GenerationComponentSystem = dimensions
+            .iter_mut()
+            .find(|dimension| dimension.get_name() == &dimension_name)
+            .unwrap()
+            .get_gcs_mut();
+
+        gcs.register_decoration(Decoration::new(
+            decoration_name,
+            game_mod,
+            biomes,
+            place_on,
+            fill_ratio,
+            blocks
+        ));
+    }
+
 
     println!("-------------- done -----------------");
-}
\ No newline at end of file
+
+    atlas.to_rgba8()
+}
+
+// the mods lua actually ran, in load order
+pub fn get_loaded_mods(lua: &Lua) -> Vec<String> {
+    let crafter: Table = lua.globals().get("crafter").unwrap();
+    let loaded_mods: Table = crafter.get("loaded_mods").unwrap();
+
+    loaded_mods.sequence_values::<String>().map(|mod_name| mod_name.unwrap()).collect()
+}
@@ -0,0 +1,129 @@
+use std::collections::HashSet;
+
+use mlua::{
+    Lua,
+    Table,
+    Function
+};
+
+use crate::{
+    blocks::block_component_system::BlockComponentSystem,
+    world::{
+        world::World,
+        world_event::{
+            WorldEvent,
+            WorldObserver
+        }
+    }
+};
+
+/*
+sends world events on to the lua mods listening to them
+
+only event types a mod registered for with crafter.register_on_world_event cross
+over into lua, block ids are turned into block names on the way
+*/
+pub struct LuaWorldObserver<'lua> {
+    lua: &'lua Lua,
+    bcs: &'lua BlockComponentSystem,
+    // the dimension the events come from
+    dimension: &'lua str,
+    // event types somebody listens to
+    listened: HashSet<String>
+}
+
+impl<'lua> LuaWorldObserver<'lua> {
+
+    pub fn new(lua: &'lua Lua, bcs: &'lua BlockComponentSystem, dimension: &'lua str) -> Self {
+
+        let mut listened: HashSet<String> = HashSet::new();
+
+        let callbacks: mlua::Result<Table> = lua.globals()
+            .get::<_, Table>("crafter")
+            .and_then(|crafter| crafter.get::<_, Table>("world_event_callbacks"));
+
+        match callbacks {
+            Ok(callbacks) => {
+                for (event_type, listeners) in callbacks.pairs::<String, Table>().flatten() {
+                    if listeners.raw_len() > 0 {
+                        listened.insert(event_type);
+                    }
+                }
+            },
+            Err(error) => println!("COULD NOT READ WORLD EVENT CALLBACKS: {}!", error),
+        }
+
+        Self {
+            lua,
+            bcs,
+            dimension,
+            listened
+        }
+    }
+
+    // the event as the table lua gets
+    fn create_event_table(&self, event: &WorldEvent) -> mlua::Result<Table<'lua>> {
+
+        let table: Table = self.lua.create_table()?;
+
+        table.set("type", event.get_name())?;
+        table.set("dimension", self.dimension)?;
+
+        match *event {
+            WorldEvent::ChunkAdded { x, z } |
+            WorldEvent::ChunkGenerated { x, z } |
+            WorldEvent::ChunkRemoved { x, z } |
+            WorldEvent::ChunkEdited { x, z } |
+            WorldEvent::ChunkRelit { x, z } => {
+                table.set("x", x)?;
+                table.set("z", z)?;
+            },
+            WorldEvent::BlockSet { x, y, z, old_id, new_id } => {
+                table.set("x", x)?;
+                table.set("y", y)?;
+                table.set("z", z)?;
+                table.set("old_block", self.bcs.get_name(old_id).as_str())?;
+                table.set("new_block", self.bcs.get_name(new_id).as_str())?;
+            },
+            WorldEvent::BlockDropped { x, y, z, id } => {
+                table.set("x", x)?;
+                table.set("y", y)?;
+                table.set("z", z)?;
+                table.set("block", self.bcs.get_name(id).as_str())?;
+            },
+            WorldEvent::BlockRotated { x, y, z, old_rotation, new_rotation } => {
+                table.set("x", x)?;
+                table.set("y", y)?;
+                table.set("z", z)?;
+                table.set("old_rotation", old_rotation)?;
+                table.set("new_rotation", new_rotation)?;
+            },
+            WorldEvent::LightChanged { x, section, z } => {
+                table.set("x", x)?;
+                table.set("section", section)?;
+                table.set("z", z)?;
+            },
+        }
+
+        Ok(table)
+    }
+
+    fn send(&self, event: &WorldEvent) -> mlua::Result<()> {
+        let dispatch: Function = self.lua.globals().get("dispatch_world_event")?;
+        dispatch.call::<_, ()>(self.create_event_table(event)?)
+    }
+}
+
+impl WorldObserver for LuaWorldObserver<'_> {
+    fn on_world_event(&mut self, _world: &World, event: &WorldEvent) {
+
+        if !self.listened.contains(event.get_name()) {
+            return;
+        }
+
+        match self.send(event) {
+            Ok(_) => (),
+            Err(error) => println!("COULD NOT SEND WORLD EVENT {} TO LUA: {}!", event.get_name(), error),
+        }
+    }
+}
@@ -1,4 +1,6 @@
 pub mod lua_initialize;
 pub mod lua_functions;
 pub mod lua_intake_api;
-pub mod lua_texture_atlas_calculation;
\ No newline at end of file
+pub mod lua_texture_atlas_calculation;
+pub mod lua_world_events;
+pub mod lua_block_ticks;
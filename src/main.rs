@@ -1,27 +1,21 @@
 extern crate glfw;
 
-mod helper;
-mod graphics;
-mod controls;
-mod time;
-mod chunk_mesh_procedure;
-mod world;
-mod blocks;
-mod lua;
-mod biomes;
-
 use glfw::*;
 
-use graphics::window_controls::toggle_full_screen;
 use mlua::Lua;
-use opensimplex_noise_rs::OpenSimplexNoise;
 
 use std::{
-    sync::mpsc::Receiver
+    sync::{
+        Arc,
+        mpsc::Receiver
+    },
+    path::Path,
+    env
 };
 
-use crate::{
+use crafter::{
     graphics::{
+        self,
         shader_program::{
             ShaderProgram
         },
@@ -29,23 +23,35 @@ use crate::{
             *
         },
         render::Renderer,
-        window_controls::WindowVariables
+        window_controls::{
+            WindowVariables,
+            toggle_full_screen
+        }
     },
 
     time::time_object::{
             Time
         },
-    chunk_mesh_procedure::{
-        chunk_mesh_creation,
-        chunk_mesh_generator_queue::{
-            ChunkMeshGeneratorQueue,
-            MeshUpdate
-        }
-    },
+    chunk_mesh_procedure::chunk_mesh_service::FinishedMesh,
     world::{
         world::{
             *,
-        },        
+        },
+        world_save::WorldSave,
+        world_descriptor::{
+            WorldDescriptor,
+            GeneratorType
+        },
+        dimension::{
+            Dimension,
+            DimensionDefinition,
+            Dimensions
+        },
+        chunk_manager::ChunkManager,
+        tick_scheduler::{
+            BlockTick,
+            TICKS_PER_SECOND
+        }
     }, 
     controls::{
         keyboard::Keyboard, 
@@ -53,16 +59,34 @@ use crate::{
     }, blocks::block_component_system::{BlockComponentSystem},
     lua::{
         lua_initialize::initialize_lua,
-        lua_intake_api::intake_api_values
+        lua_world_events::LuaWorldObserver,
+        lua_block_ticks::run_block_ticks,
+        lua_intake_api::{
+            intake_api_values,
+            get_loaded_mods
+        }
     },
-        helper::helper_functions::get_path_string, biomes::{biome_generator::gen_biome, generation_component_system::GenerationComponentSystem},
+        helper::helper_functions::{get_path_string, with_path},
 
 };
 
-pub const SEED: u64 = 123213123;
-
 fn main() {
 
+    // crafter [world name] [seed or random] [biomes or flat] - the seed and generator only matter to a new world
+    let mut arguments = env::args().skip(1);
+
+    let world_name: String = arguments.next().unwrap_or_else(|| String::from("world"));
+
+    let seed_option: Option<u64> = match arguments.next() {
+        Some(seed) if seed != "random" => Some(seed.parse::<u64>().expect("THE SEED MUST BE A WHOLE NUMBER!")),
+        _ => None,
+    };
+
+    let generator: GeneratorType = match arguments.next() {
+        Some(generator_name) => GeneratorType::from_name(&generator_name).expect("THE GENERATOR MUST BE biomes OR flat!"),
+        None => GeneratorType::Biomes,
+    };
+
     // glfw initialization and configuration
 
     // initalize glfw
@@ -89,11 +113,19 @@ fn main() {
     let mut keyboard: Keyboard = Keyboard::new();
     let mut mouse: Mouse = Mouse::new(&window);
 
-    // noise structure
-    let noise = OpenSimplexNoise::new(Some(SEED as i64));
-
     const RENDER_DISTANCE: i32 = 20;
 
+    // finished chunks put into the world per frame, each one gets lit on the main thread
+    const MAX_GENERATED_CHUNKS_PER_FRAME: usize = 4;
+
+    // finished meshes uploaded to the GPU per frame
+    const MAX_MESHES_PER_FRAME: usize = 8;
+
+    // a slow frame catches up on at most this many game ticks, the rest are dropped
+    const MAX_CATCH_UP_TICKS: u32 = 5;
+
+    let tick_length: f64 = 1.0 / TICKS_PER_SECOND as f64;
+
     // construct the renderer
     let mut renderer: Renderer = Renderer::new();
     renderer.set_render_distance(RENDER_DISTANCE as f32 * 16.0);
@@ -111,117 +143,170 @@ fn main() {
 
     let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
 
-    let mut gcs: GenerationComponentSystem = GenerationComponentSystem::new();
+    let mut dimension_definitions: Vec<DimensionDefinition> = Vec::new();
 
     let mut window_variables: WindowVariables = WindowVariables::new();
 
-    let mut world: World = World::initialize();
+    // keeps RENDER_DISTANCE chunks loaded around the camera
+    let mut chunk_manager: ChunkManager = ChunkManager::new(RENDER_DISTANCE);
 
-    let mut debug_x = -RENDER_DISTANCE;
-    let mut debug_z = -RENDER_DISTANCE;
+    // set by the travel key, the camera moves on to the next dimension
+    let mut travel: bool = false;
 
-    let mut continue_debug = true;
-
-    let mut chunk_mesh_generator_queue: ChunkMeshGeneratorQueue = ChunkMeshGeneratorQueue::new();
-    
-    let mut poll = true;
+    // time that has passed but not been ticked yet
+    let mut tick_time: f64 = 0.0;
     
     // let debug_texture: u32 = mcs.new_texture("/mods/default/textures/dirt.png");
     // bcs.register_block("dirt", vec![String::from("test.png")], None, DrawType::Normal);
 
-    let lua: Lua = initialize_lua();
+    let world_directory: String = with_path(&format!("/worlds/{}", world_name));
+
+    // an existing world decides which mods get loaded
+    let saved_descriptor: Option<WorldDescriptor> = WorldSave::read_descriptor(Path::new(&world_directory))
+        .expect("FAILED TO READ WORLD DESCRIPTOR!");
+
+    let enabled_mods: Option<&[String]> = saved_descriptor
+        .as_ref()
+        .map(|descriptor| descriptor.get_mods().as_slice())
+        .filter(|mods| !mods.is_empty());
+
+    let lua: Lua = initialize_lua(enabled_mods);
+
+    let atlas = intake_api_values(&lua, &mut dimension_definitions, &mut bcs);
+
+    // every block is registered, from here on the mesh workers share it
+    let bcs: Arc<BlockComponentSystem> = Arc::new(bcs);
+
+    // texture atlas will always be id 1
+    let atlas_texture_id: u32 = mcs.new_texture_from_memory(atlas);
+
+    println!("TEXTURE ATLAS IS VALUE: {}", atlas_texture_id);
+
+    // only used if the world is new, an existing world keeps its own descriptor
+    let mut new_descriptor: WorldDescriptor = WorldDescriptor::new(world_name, seed_option, generator);
+    new_descriptor.set_mods(get_loaded_mods(&lua));
+
+    // the world directory can only be opened once the blocks and dimensions are registered
+    let mut dimensions: Dimensions = Dimensions::open(
+        Path::new(&world_directory),
+        new_descriptor,
+        dimension_definitions,
+        &bcs
+    ).expect("FAILED TO OPEN WORLD DIRECTORY!");
 
-    intake_api_values(&lua, &mut gcs, &mut mcs, &mut bcs);
+    let descriptor: &WorldDescriptor = dimensions.get_descriptor();
+
+    println!("OPENED WORLD: {} SEED: {} GENERATOR: {}", descriptor.get_name(), descriptor.get_seed(), descriptor.get_generator().get_name());
 
 
     // main program loop
     while !window.should_close() {
 
-        // here is testing for the logic of the chunk mesh generator queue
-        if poll {
-
-            let mesh_update_option: Option<MeshUpdate> = chunk_mesh_generator_queue.pop_front();
-
-            // does this update exist?
-            match mesh_update_option {
-                Some(mesh_update) => {
-                    // add neighbors to queue if told to do so
-                    if mesh_update.update_neighbors() {
-                        chunk_mesh_generator_queue.batch_neighbor_update(mesh_update.get_x(), mesh_update.get_z());
-                    }
-
-                    let mesh: Option<u32> = chunk_mesh_creation::create_chunk_mesh(&bcs, &mut mcs, &world, mesh_update.get_x(), mesh_update.get_z(), 1);//debug_texture);
-                    match mesh {
-                        Some(unwrapped_mesh) => {
-                            world.set_chunk_mesh(&mut mcs, mesh_update.get_x(), mesh_update.get_z(), unwrapped_mesh);
-                            world.sort_map(renderer.get_camera().get_pos());                            
-                        },
-                        None => (),
-                    }
-                },
-                None => {
-                    if !continue_debug {
-                        poll = false;
-                        println!("DONE GENERATING MESHES!");
-                    }
-                },
-            } 
-            // println!("RUNNING")     
-        }
-
+        // the camera moved into another chunk or dimension, load what's around it and let go of what's too far away
+        let unloaded_meshes: Vec<u32> = chunk_manager.update(
+            &mut dimensions,
+            renderer.get_camera().get_dimension(),
+            renderer.get_camera().get_pos_x(),
+            renderer.get_camera().get_pos_z()
+        ).expect("FAILED TO LOAD OR UNLOAD CHUNKS!");
 
-        // this is chunk generation debug
-        // this needs to be turned into an async queue
-        if continue_debug {
-            
-            // println!(" CREATING {} {}", debug_x, debug_y);
-            world.add_chunk(debug_x, debug_z);
+        for mesh_id in unloaded_meshes {
+            mcs.delete_mesh(mesh_id, false);
+        }
 
-            gen_biome(
-                &gcs,
-                world.get_chunk_blocks_mut(debug_x, debug_z).unwrap(),
-                debug_x,
-                debug_z,
-                &noise
-            );
+        // everything below works on the dimension the camera is in
+        let dimension: &mut Dimension = dimensions.get_mut(renderer.get_camera().get_dimension()).unwrap();
 
-            // world.add(generated_chunk);
+        // the chunks the generation pool finished go in, the mesh is queued by the chunk's event
+        dimension.receive_generated_chunks(&bcs, MAX_GENERATED_CHUNKS_PER_FRAME).expect("FAILED TO LOAD A SAVED CHUNK!");
 
-            chunk_mesh_generator_queue.push_back(debug_x, debug_z, true);
+        // queued meshes are built on the generation pool from snapshots, the finished ones are uploaded here
+        dimension.request_meshes(&bcs);
 
+        let finished_meshes: Vec<FinishedMesh> = dimension.receive_meshes(MAX_MESHES_PER_FRAME);
 
-            // this part just ticks up the generation value
-            debug_x += 1;
+        if !finished_meshes.is_empty() {
+            let world: &mut World = dimension.get_world_mut();
 
-            if debug_x > RENDER_DISTANCE {
-                debug_x = -RENDER_DISTANCE;
+            for finished_mesh in finished_meshes {
+                let (mesh_x, mesh_z) = finished_mesh.get_pos();
 
-                debug_z += 1;
+                for (section, mesh_data) in finished_mesh.unpack() {
+                    // upload the CPU mesh data to the GPU
+                    let mesh_id_option: Option<u32> = mesh_data.map(|unwrapped_mesh_data| {
+                        let (float_data, indices_data) = unwrapped_mesh_data.unpack();
+                        mcs.new_mesh(float_data, indices_data, atlas_texture_id)
+                    });
 
-                if debug_z > RENDER_DISTANCE {
-                    continue_debug = false;
-                    println!("DONE GENERATING CHUNKS!");
+                    world.set_section_mesh(&mut mcs, mesh_x, section, mesh_z, mesh_id_option);
                 }
             }
+
+            world.sort_map(renderer.get_camera().get_pos());
+        }
+
+        // what changed in the worlds this frame goes to the mesh queues and the lua mods
+        for dimension in dimensions.iter_mut() {
+            let dimension_name: String = dimension.get_name().clone();
+
+            let mut lua_world_observer: LuaWorldObserver = LuaWorldObserver::new(&lua, &bcs, &dimension_name);
+
+            dimension.dispatch_events(&bcs, &mut [&mut lua_world_observer]);
         }
 
         let delta: f64 = time_object.calculate_delta(&glfw);
 
+        // game ticks run at a fixed rate no matter the frame rate
+        tick_time += delta;
+
+        let mut ticks_run: u32 = 0;
+
+        while tick_time >= tick_length && ticks_run < MAX_CATCH_UP_TICKS {
+            tick_time -= tick_length;
+            ticks_run += 1;
+
+            for dimension in dimensions.iter_mut() {
+                let block_ticks: Vec<BlockTick> = dimension.advance_tick(&bcs);
+
+                let (world, tick_scheduler) = dimension.get_world_and_tick_scheduler_mut();
+
+                run_block_ticks(&lua, &bcs, world, tick_scheduler, &block_ticks);
+            }
+        }
+
+        if ticks_run == MAX_CATCH_UP_TICKS {
+            tick_time = 0.0;
+        }
+
         glfw.poll_events();
 
         mouse.reset();
 
         // this is where all events are processed
-        process_events(&mut glfw, &mut window, &events, &mut mouse, &mut keyboard, &mut window_variables);
+        process_events(&mut glfw, &mut window, &events, &mut mouse, &mut keyboard, &mut window_variables, &mut travel);
+
+
+        let mut update_chunk_ordering: bool = renderer.get_camera_mut().on_tick(&mouse, &keyboard, delta as f32);
+
+        if travel {
+            travel = false;
+
+            let next_dimension: String = dimensions.get_next_name(renderer.get_camera().get_dimension()).clone();
 
+            println!("TRAVELING TO DIMENSION: {}", next_dimension);
 
-        let update_chunk_ordering: bool = renderer.get_camera_mut().on_tick(&mouse, &keyboard, delta as f32);
+            // the chunks around the camera get asked for next frame
+            renderer.get_camera_mut().set_dimension(&next_dimension);
+
+            update_chunk_ordering = true;
+        }
 
         if update_chunk_ordering {
-            world.sort_map(renderer.get_camera().get_pos());
+            let camera_pos = *renderer.get_camera().get_pos();
+            dimensions.get_mut(renderer.get_camera().get_dimension()).unwrap().get_world_mut().sort_map(&camera_pos);
         }
 
-        renderer.render(&mut mcs, &window, &mut world);
+        renderer.render(&mut mcs, &window, &dimensions);
 
 
         let returned_value = time_object.count_fps(&glfw);
@@ -238,7 +323,12 @@ fn main() {
      
     }
 
-    world.clean_up(&mut mcs);
+    let saved_chunks: usize = dimensions.save_unsaved(&bcs).expect("FAILED TO SAVE WORLD!");
+    println!("SAVED {} CHUNKS!", saved_chunks);
+
+    for dimension in dimensions.iter_mut() {
+        dimension.get_world_mut().clean_up(&mut mcs);
+    }
     renderer.clean_up();
 
     mcs.final_clean_up();
@@ -257,7 +347,9 @@ fn process_events(
     mouse: &mut Mouse,
     keyboard: &mut Keyboard,
 
-    window_variables: &mut WindowVariables
+    window_variables: &mut WindowVariables,
+
+    travel: &mut bool
 ) {
     // iterate events
     for (_, event) in glfw::flush_messages(events) {
@@ -279,6 +371,9 @@ fn process_events(
             glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => window.set_should_close(true),
             glfw::WindowEvent::Key(Key::F11, _, Action::Press, _) => toggle_full_screen(glfw, window, window_variables),
 
+            // move on to the next dimension
+            glfw::WindowEvent::Key(Key::F4, _, Action::Press, _) => *travel = true,
+
             _ => ()
         }
     }
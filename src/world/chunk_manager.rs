@@ -0,0 +1,280 @@
+/*
+the chunk manager - keeps the chunks around the camera loaded
+
+every time the camera moves into another chunk the dimension it's in gets asked
+for every chunk within the load radius (the generation pool works through them
+closest first) and gives up the chunks past the unload radius - the unload radius
+is a little further out than the load radius, so walking back and forth over a
+chunk border doesn't load and unload the same row of chunks every time
+
+a dimension the camera leaves keeps nothing loaded
+
+unloaded chunks are saved first if they were modified, their meshes are handed
+back so whoever owns the meshes can free them
+*/
+
+use std::io;
+
+use super::{
+    world::CHUNK_WIDTH,
+    dimension::{
+        Dimension,
+        Dimensions
+    }
+};
+
+// how many chunks past the load radius a chunk has to be before it's unloaded
+const UNLOAD_MARGIN: i32 = 2;
+
+fn is_within(center_x: i32, center_z: i32, x: i32, z: i32, radius: i32) -> bool {
+    let (offset_x, offset_z) = ((x - center_x) as i64, (z - center_z) as i64);
+    offset_x * offset_x + offset_z * offset_z <= radius as i64 * radius as i64
+}
+
+pub struct ChunkManager {
+    // in chunks
+    load_radius: i32,
+    unload_radius: i32,
+    // (dimension, chunk x, chunk z) the chunks were last loaded around
+    center: Option<(String, i32, i32)>
+}
+
+impl ChunkManager {
+    pub fn new(load_radius: i32) -> Self {
+        Self {
+            load_radius,
+            unload_radius: load_radius + UNLOAD_MARGIN,
+            center: None
+        }
+    }
+
+    pub fn get_load_radius(&self) -> i32 {
+        self.load_radius
+    }
+
+    pub fn get_unload_radius(&self) -> i32 {
+        self.unload_radius
+    }
+
+    /*
+    loads and unloads chunks around the camera, only does anything when the camera moved into another chunk or dimension
+
+    returns the meshes of the chunks that were unloaded, the caller frees them
+    */
+    pub fn update(&mut self, dimensions: &mut Dimensions, dimension_name: &str, camera_x: f32, camera_z: f32) -> io::Result<Vec<u32>> {
+
+        let center_x: i32 = (camera_x / CHUNK_WIDTH as f32).floor() as i32;
+        let center_z: i32 = (camera_z / CHUNK_WIDTH as f32).floor() as i32;
+
+        match &self.center {
+            Some((name, x, z)) if name == dimension_name && *x == center_x && *z == center_z => return Ok(Vec::new()),
+            _ => (),
+        }
+
+        let mut mesh_ids: Vec<u32> = Vec::new();
+
+        // the camera went to another dimension, the one it left empties out
+        match &self.center {
+            Some((name, ..)) if name != dimension_name => {
+                if let Some(old_dimension) = dimensions.get_mut(name) {
+                    old_dimension.cancel_generation();
+
+                    let chunks: Vec<(i32, i32)> = old_dimension.get_world().get_chunk_positions();
+
+                    mesh_ids.extend(old_dimension.unload_chunks(&chunks)?);
+                }
+            },
+            _ => (),
+        }
+
+        let dimension: &mut Dimension = match dimensions.get_mut(dimension_name) {
+            Some(dimension) => dimension,
+            None => return Ok(mesh_ids),
+        };
+
+        let distant_chunks: Vec<(i32, i32)> = dimension
+            .get_world()
+            .get_chunk_positions()
+            .into_iter()
+            .filter(|(x, z)| !is_within(center_x, center_z, *x, *z, self.unload_radius))
+            .collect();
+
+        mesh_ids.extend(dimension.unload_chunks(&distant_chunks)?);
+
+        // everything waiting outside the load radius is cancelled
+        dimension.set_generation_center(center_x, center_z, self.load_radius);
+
+        for x in -self.load_radius..=self.load_radius {
+            for z in -self.load_radius..=self.load_radius {
+                if is_within(0, 0, x, z, self.load_radius) {
+                    // saved chunks are read back on the pool, everything else is generated there
+                    dimension.request_chunk(center_x + x, center_z + z)?;
+                }
+            }
+        }
+
+        self.center = Some((dimension_name.to_string(), center_x, center_z));
+
+        Ok(mesh_ids)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        path::PathBuf,
+        thread,
+        time::Duration
+    };
+
+    use crate::{
+        biomes::generation_component_system::{
+            Climate,
+            GenerationComponentSystem,
+            LayerDepth,
+            NoiseParams,
+            TerrainNoiseParams
+        },
+        blocks::block_component_system::{
+            BlockComponentSystem,
+            DrawType,
+            RotationType
+        }
+    };
+
+    use super::{
+        super::{
+            dimension::{
+                DimensionDefinition,
+                OVERWORLD
+            },
+            world_descriptor::{
+                GeneratorType,
+                WorldDescriptor
+            }
+        },
+        *
+    };
+
+    // a flat overworld and nether of stone (id 1) in a fresh world directory
+    fn test_dimensions(name: &str) -> (Dimensions, BlockComponentSystem) {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+        bcs.register_block(String::from("test"), String::from("stone"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+
+        let definitions: Vec<DimensionDefinition> = [OVERWORLD, "nether"].iter().map(|dimension_name| {
+            let mut gcs: GenerationComponentSystem = GenerationComponentSystem::new();
+            gcs.set_base_height(20);
+            gcs.register_biome(
+                String::from("plains"), TerrainNoiseParams::new(10.0, 0.01), Climate::new(None, None, None), 0, String::from("test"),
+                1, LayerDepth::new(1, 1), 1, LayerDepth::new(1, 1), 1, 1, None, false, NoiseParams::new(0.0, 0.0, 0.0, 0.0), false, false
+            );
+            DimensionDefinition::new(dimension_name.to_string(), None, gcs)
+        }).collect();
+
+        let directory: PathBuf = std::env::temp_dir().join(format!("crafter_chunk_manager_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        let dimensions: Dimensions = Dimensions::open(
+            &directory,
+            WorldDescriptor::new(String::from("test"), Some(1), GeneratorType::Flat),
+            definitions,
+            &bcs
+        ).unwrap();
+
+        (dimensions, bcs)
+    }
+
+    // camera position in the middle of a chunk
+    fn chunk_center(chunk: i32) -> f32 {
+        (chunk * CHUNK_WIDTH + CHUNK_WIDTH / 2) as f32
+    }
+
+    // waits for everything the dimension asked for to come in
+    fn receive_all(dimensions: &mut Dimensions, bcs: &BlockComponentSystem, name: &str) {
+        let dimension: &mut Dimension = dimensions.get_mut(name).unwrap();
+
+        for _ in 0..2000 {
+            dimension.receive_generated_chunks(bcs, usize::MAX).unwrap();
+
+            if dimension.get_number_of_generating() == 0 {
+                return;
+            }
+
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        panic!("CHUNKS NEVER CAME IN!");
+    }
+
+    fn loaded(dimensions: &Dimensions, name: &str, x: i32, z: i32) -> bool {
+        dimensions.get(name).unwrap().get_world().chunk_exists(x, z)
+    }
+
+    #[test]
+    fn is_within_is_a_circle_of_chunks() {
+        assert!(is_within(0, 0, 2, 0, 2));
+        assert!(is_within(0, 0, 1, 1, 2));
+        assert!(!is_within(0, 0, 2, 1, 2));
+        assert!(is_within(-5, 3, -7, 3, 2));
+        assert!(!is_within(-5, 3, -8, 3, 2));
+        assert!(is_within(4, 4, 4, 4, 0));
+        assert!(!is_within(4, 4, 5, 4, 0));
+    }
+
+    #[test]
+    fn chunks_are_only_unloaded_past_the_margin_and_come_back_from_disk() {
+        let (mut dimensions, bcs) = test_dimensions("margin");
+        let mut chunk_manager: ChunkManager = ChunkManager::new(2);
+
+        assert_eq!(chunk_manager.get_unload_radius(), 2 + UNLOAD_MARGIN);
+
+        chunk_manager.update(&mut dimensions, OVERWORLD, chunk_center(0), chunk_center(0)).unwrap();
+        receive_all(&mut dimensions, &bcs, OVERWORLD);
+
+        assert_eq!(dimensions.get(OVERWORLD).unwrap().get_world().get_number_of_chunks(), 13);
+
+        // a changed block in a chunk that is about to be unloaded
+        let stone: u32 = bcs.get_id_of(String::from("stone"));
+        dimensions.get_mut(OVERWORLD).unwrap().get_world_mut().set_block(-2 * CHUNK_WIDTH, 60, 0, stone);
+
+        // three chunks over, -1 is outside the load radius but inside the margin
+        chunk_manager.update(&mut dimensions, OVERWORLD, chunk_center(3), chunk_center(0)).unwrap();
+        receive_all(&mut dimensions, &bcs, OVERWORLD);
+
+        assert!(loaded(&dimensions, OVERWORLD, -1, 0));
+        assert!(!loaded(&dimensions, OVERWORLD, -2, 0));
+        assert!(loaded(&dimensions, OVERWORLD, 5, 0));
+
+        // the same chunk again does nothing
+        assert!(chunk_manager.update(&mut dimensions, OVERWORLD, chunk_center(3) + 1.0, chunk_center(0)).unwrap().is_empty());
+
+        chunk_manager.update(&mut dimensions, OVERWORLD, chunk_center(0), chunk_center(0)).unwrap();
+        receive_all(&mut dimensions, &bcs, OVERWORLD);
+
+        // read back on the pool with the change in it
+        assert!(loaded(&dimensions, OVERWORLD, -2, 0));
+        assert_eq!(dimensions.get(OVERWORLD).unwrap().get_world().get_block(-2 * CHUNK_WIDTH, 60, 0), Some(stone));
+        assert!(loaded(&dimensions, OVERWORLD, 4, 0));
+        assert!(!loaded(&dimensions, OVERWORLD, 5, 0));
+    }
+
+    #[test]
+    fn switching_dimensions_empties_the_one_left_behind() {
+        let (mut dimensions, bcs) = test_dimensions("switch");
+        let mut chunk_manager: ChunkManager = ChunkManager::new(1);
+
+        chunk_manager.update(&mut dimensions, OVERWORLD, chunk_center(0), chunk_center(0)).unwrap();
+        receive_all(&mut dimensions, &bcs, OVERWORLD);
+
+        assert_eq!(dimensions.get(OVERWORLD).unwrap().get_world().get_number_of_chunks(), 5);
+
+        // same position, other dimension
+        chunk_manager.update(&mut dimensions, "nether", chunk_center(0), chunk_center(0)).unwrap();
+        receive_all(&mut dimensions, &bcs, "nether");
+
+        assert_eq!(dimensions.get(OVERWORLD).unwrap().get_world().get_number_of_chunks(), 0);
+        assert_eq!(dimensions.get(OVERWORLD).unwrap().get_number_of_generating(), 0);
+        assert_eq!(dimensions.get("nether").unwrap().get_world().get_number_of_chunks(), 5);
+    }
+}
@@ -0,0 +1,128 @@
+/*
+a chunk column split into 16x16x16 sections
+
+each section is its own palette, so a section of pure air or pure stone
+costs almost nothing and can be recognized without walking its blocks
+
+indexing still uses the chunk layout from pos_to_index, the section and the
+index inside it are worked out here
+*/
+
+use super::{
+    palette_storage::PaletteStorage,
+    world::{
+        CHUNK_WIDTH,
+        CHUNK_HEIGHT,
+        CHUNK_SIZE,
+        index_to_pos
+    }
+};
+
+// sections are cubes
+pub const SECTION_HEIGHT: i32 = 16;
+
+pub const SECTION_COUNT: usize = (CHUNK_HEIGHT / SECTION_HEIGHT) as usize;
+
+// a partial section at the top of the world would be lost
+const _: () = assert!(CHUNK_HEIGHT % SECTION_HEIGHT == 0, "CHUNK_HEIGHT MUST BE A MULTIPLE OF SECTION_HEIGHT!");
+
+pub const SECTION_SIZE: usize = (CHUNK_WIDTH * CHUNK_WIDTH * SECTION_HEIGHT) as usize;
+
+// chunk index -> (section, index inside the section)
+fn split_index(index: usize) -> (usize, usize) {
+    let (x, y, z) = index_to_pos(index);
+
+    let section_height: usize = SECTION_HEIGHT as usize;
+
+    (
+        y / section_height,
+        (x * 256) + (z * section_height) + (y % section_height)
+    )
+}
+
+// index inside a section -> local (x, y, z) inside that section
+pub fn section_index_to_pos(index: usize) -> (usize, usize, usize) {
+    let section_height: usize = SECTION_HEIGHT as usize;
+
+    (index / 256, index % section_height, (index % 256) / section_height)
+}
+
+#[derive(Clone)]
+pub struct ChunkStorage<T> {
+    sections: Vec<PaletteStorage<T>>
+}
+
+impl<T: Copy + PartialEq> ChunkStorage<T> {
+
+    // a chunk where every block is value
+    pub fn new(value: T) -> Self {
+        Self {
+            sections: (0..SECTION_COUNT).map(|_| PaletteStorage::new(SECTION_SIZE, value)).collect()
+        }
+    }
+
+    // packs a flat chunk array (pos_to_index layout) into sections
+    pub fn from_slice(values: &[T]) -> Self {
+
+        let mut section_values: Vec<Vec<T>> = (0..SECTION_COUNT).map(|_| vec![values[0]; SECTION_SIZE]).collect();
+
+        for (index, value) in values.iter().enumerate() {
+            let (section, section_index) = split_index(index);
+            section_values[section][section_index] = *value;
+        }
+
+        Self {
+            sections: section_values.iter().map(|section| PaletteStorage::from_slice(section)).collect()
+        }
+    }
+
+    pub fn get(&self, index: usize) -> T {
+        let (section, section_index) = split_index(index);
+        self.sections[section].get(section_index)
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        let (section, section_index) = split_index(index);
+        self.sections[section].set(section_index, value);
+    }
+
+    pub fn fill(&mut self, value: T) {
+        self.sections.iter_mut().for_each(|section| section.fill(value));
+    }
+
+    // Some(value) if the whole chunk is a single value
+    pub fn get_single(&self) -> Option<T> {
+        let first: T = self.sections[0].get_single()?;
+
+        if self.sections.iter().all(|section| section.get_single() == Some(first)) {
+            Some(first)
+        } else {
+            None
+        }
+    }
+
+    pub fn get_section(&self, section: usize) -> &PaletteStorage<T> {
+        &self.sections[section]
+    }
+
+    pub fn get_section_count(&self) -> usize {
+        self.sections.len()
+    }
+
+    pub fn len(&self) -> usize {
+        CHUNK_SIZE
+    }
+
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    // unpacks into a flat chunk array (pos_to_index layout)
+    pub fn to_vec(&self) -> Vec<T> {
+        (0..CHUNK_SIZE).map(|index| self.get(index)).collect()
+    }
+
+    pub fn get_memory_usage(&self) -> usize {
+        self.sections.iter().map(|section| section.get_memory_usage()).sum()
+    }
+}
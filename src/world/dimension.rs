@@ -0,0 +1,719 @@
+/*
+dimensions - separate worlds inside one save
+
+every dimension has its own chunks, its own biome set and generator settings
+(registered from lua with crafter.register_dimension) and its own region files
+
+the overworld always exists and keeps the world's own directory, so saves from
+before dimensions open the same as they did - the others live in
+worlds/<name>/dimensions/<dimension>/
+
+block ids are shared, every dimension draws from the same registered blocks
+*/
+
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    io::{
+        self,
+        Error,
+        ErrorKind
+    },
+    path::{
+        Path,
+        PathBuf
+    }
+};
+
+use rayon::ThreadPool;
+
+use crate::{
+    biomes::{
+        decoration::{
+            DecorationQueue,
+            DecorationWrite
+        },
+        generation_component_system::GenerationComponentSystem,
+        generation_service::{
+            ChunkGenerationService,
+            FinishedChunk,
+            GeneratedChunk,
+            new_generation_pool
+        }
+    },
+    blocks::block_component_system::BlockComponentSystem,
+    chunk_mesh_procedure::{
+        chunk_mesh_generator_queue::{
+            ChunkMeshGeneratorQueue,
+            MeshUpdate
+        },
+        chunk_mesh_service::{
+            ChunkMeshService,
+            FinishedMesh
+        }
+    }
+};
+
+use super::{
+    world::{
+        World,
+        ChunkData
+    },
+    world_save::WorldSave,
+    world_event::WorldObserver,
+    tick_scheduler::{
+        TickScheduler,
+        BlockTick
+    },
+    world_descriptor::{
+        WorldDescriptor,
+        GeneratorType
+    },
+    lighting::light_chunk,
+    liquid::{
+        LiquidObserver,
+        flow_liquids
+    },
+    falling_block::{
+        FallingBlocks,
+        FallingBlockObserver
+    }
+};
+
+// the dimension every world starts in
+pub const OVERWORLD: &str = "overworld";
+
+// a dimension the way lua registered it, before it has any chunks
+pub struct DimensionDefinition {
+    name: String,
+    // None uses the generator the world was created with
+    generator: Option<GeneratorType>,
+    // the biomes of this dimension and its base height
+    gcs: GenerationComponentSystem
+}
+
+impl DimensionDefinition {
+    pub fn new(name: String, generator: Option<GeneratorType>, gcs: GenerationComponentSystem) -> Self {
+        Self {
+            name,
+            generator,
+            gcs
+        }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_generator(&self) -> Option<GeneratorType> {
+        self.generator
+    }
+
+    pub fn get_gcs(&self) -> &GenerationComponentSystem {
+        &self.gcs
+    }
+
+    pub fn get_gcs_mut(&mut self) -> &mut GenerationComponentSystem {
+        &mut self.gcs
+    }
+}
+
+// the overworld uses the world seed as it is, everything else mixes its name in so no two dimensions match
+fn dimension_seed(world_seed: u64, name: &str) -> u64 {
+    if name == OVERWORLD {
+        return world_seed;
+    }
+
+    // fnv-1a, stable between runs unlike the std hasher
+    let name_hash: u64 = name.bytes().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+
+    world_seed ^ name_hash
+}
+
+// the name becomes a directory, so anything that could climb out of the world (.. or /) is turned away
+fn dimension_directory(world_directory: &Path, name: &str) -> io::Result<PathBuf> {
+    if name.is_empty() || !name.bytes().all(|byte| byte.is_ascii_lowercase() || byte.is_ascii_digit() || byte == b'_') {
+        return Err(Error::new(ErrorKind::InvalidInput, format!("DIMENSION NAME {:?} MAY ONLY USE a-z, 0-9 AND _!", name)));
+    }
+
+    if name == OVERWORLD {
+        Ok(world_directory.to_path_buf())
+    } else {
+        Ok(world_directory.join("dimensions").join(name))
+    }
+}
+
+pub struct Dimension {
+    name: String,
+    generator: GeneratorType,
+    // shared with the generation workers
+    gcs: Arc<GenerationComponentSystem>,
+
+    // generates the dimension's chunks off the main thread, with the dimension's own seed
+    generation_service: ChunkGenerationService,
+
+    world: World,
+    world_save: WorldSave,
+
+    // meshes belong to the dimension's chunks, so every dimension queues its own
+    chunk_mesh_generator_queue: ChunkMeshGeneratorQueue,
+
+    // builds the queued meshes on the generation pool
+    chunk_mesh_service: ChunkMeshService,
+
+    // every dimension runs its own clock
+    tick_scheduler: TickScheduler,
+
+    falling_blocks: FallingBlocks,
+
+    // decoration writes waiting for chunks that aren't in the world yet
+    decoration_queue: DecorationQueue
+}
+
+impl Dimension {
+
+    fn open(definition: DimensionDefinition, world_save: WorldSave, descriptor: &WorldDescriptor, bcs: &BlockComponentSystem, generation_pool: Arc<ThreadPool>) -> io::Result<Self> {
+        let seed: u64 = dimension_seed(descriptor.get_seed(), &definition.name);
+        let generator: GeneratorType = definition.generator.unwrap_or(descriptor.get_generator());
+        let gcs: Arc<GenerationComponentSystem> = Arc::new(definition.gcs);
+        Ok(Self {
+            generator,
+            generation_service: ChunkGenerationService::new(generation_pool.clone(), gcs.clone(), generator, seed),
+            chunk_mesh_service: ChunkMeshService::new(generation_pool),
+            decoration_queue: DecorationQueue::load(world_save.get_directory(), bcs)?,
+            tick_scheduler: TickScheduler::new(seed),
+            falling_blocks: FallingBlocks::new(),
+            name: definition.name,
+            gcs,
+            world: World::initialize(),
+            world_save,
+            chunk_mesh_generator_queue: ChunkMeshGeneratorQueue::new()
+        })
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_generator(&self) -> GeneratorType {
+        self.generator
+    }
+
+    pub fn get_gcs(&self) -> &GenerationComponentSystem {
+        &self.gcs
+    }
+
+    pub fn get_world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn get_world_mut(&mut self) -> &mut World {
+        &mut self.world
+    }
+
+    // hands the world's events to the dimension's mesh queue, liquids and falling blocks, then to the other observers
+    pub fn dispatch_events(&mut self, bcs: &BlockComponentSystem, observers: &mut [&mut dyn WorldObserver]) {
+        let mut all_observers: Vec<&mut dyn WorldObserver> = Vec::with_capacity(observers.len() + 3);
+
+        let mut liquid_observer: LiquidObserver = LiquidObserver::new(bcs, &mut self.tick_scheduler);
+        let mut falling_block_observer: FallingBlockObserver = FallingBlockObserver::new(bcs, &mut self.falling_blocks);
+
+        all_observers.push(&mut self.chunk_mesh_generator_queue);
+        all_observers.push(&mut liquid_observer);
+        all_observers.push(&mut falling_block_observer);
+
+        for observer in observers.iter_mut() {
+            all_observers.push(&mut **observer);
+        }
+
+        self.world.dispatch_events(&mut all_observers);
+    }
+
+    // the world and its mesh queue together, block edits need both
+    pub fn get_world_and_queue_mut(&mut self) -> (&mut World, &mut ChunkMeshGeneratorQueue) {
+        (&mut self.world, &mut self.chunk_mesh_generator_queue)
+    }
+
+    pub fn get_tick_scheduler(&self) -> &TickScheduler {
+        &self.tick_scheduler
+    }
+
+    pub fn get_tick_scheduler_mut(&mut self) -> &mut TickScheduler {
+        &mut self.tick_scheduler
+    }
+
+    // the world and its tick scheduler together, running block ticks needs both
+    pub fn get_world_and_tick_scheduler_mut(&mut self) -> (&mut World, &mut TickScheduler) {
+        (&mut self.world, &mut self.tick_scheduler)
+    }
+
+    // the blocks falling in this dimension right now
+    pub fn get_falling_blocks(&self) -> &FallingBlocks {
+        &self.falling_blocks
+    }
+
+    // decoration blocks waiting for their chunk to show up
+    pub fn get_decoration_queue(&self) -> &DecorationQueue {
+        &self.decoration_queue
+    }
+
+    // runs the dimension's clock one game tick, lets liquids flow and blocks fall - returns the block ticks that are due
+    pub fn advance_tick(&mut self, bcs: &BlockComponentSystem) -> Vec<BlockTick> {
+        let ticks: Vec<BlockTick> = self.tick_scheduler.advance(&self.world, bcs);
+
+        flow_liquids(&mut self.world, bcs, &ticks);
+
+        self.falling_blocks.update(&mut self.world, bcs);
+
+        ticks
+    }
+
+    /*
+    brings a chunk into the dimension right away - saved chunks come back from disk, everything else is generated on this thread
+
+    the chunk is lit, its mesh is queued when the events are dispatched - returns false if it was already loaded
+    */
+    pub fn load_or_generate_chunk(&mut self, bcs: &BlockComponentSystem, x: i32, z: i32) -> io::Result<bool> {
+
+        if self.world.chunk_exists(x, z) {
+            return Ok(false);
+        }
+
+        if !self.load_chunk(bcs, x, z)? {
+            let generated_chunk: GeneratedChunk = self.generation_service.generate_now(x, z);
+
+            self.insert_generated_chunk(bcs, generated_chunk);
+        }
+
+        Ok(true)
+    }
+
+    /*
+    asks for a chunk without waiting on it - saved chunks are decompressed on the generation pool, everything
+    else is generated there, both show up in receive_generated_chunks
+
+    returns false if the chunk was already loaded or asked for
+    */
+    pub fn request_chunk(&mut self, x: i32, z: i32) -> io::Result<bool> {
+
+        if self.world.chunk_exists(x, z) || self.generation_service.is_requested(x, z) {
+            return Ok(false);
+        }
+
+        match self.world_save.get_saved_chunk(x, z)? {
+            Some(compressed) => self.generation_service.request_saved(x, z, compressed),
+            None => self.generation_service.request(x, z),
+        }
+
+        Ok(true)
+    }
+
+    // the generation service works outwards from x,z, anything waiting further than range chunks away is dropped
+    pub fn set_generation_center(&mut self, x: i32, z: i32, range: i32) {
+        self.generation_service.set_center(x, z, range);
+    }
+
+    // drops every chunk that is waiting on the generation service, the ones being generated are thrown away when they're done
+    pub fn cancel_generation(&mut self) {
+        self.generation_service.cancel_all();
+    }
+
+    // how many requested chunks are still being generated
+    pub fn get_number_of_generating(&self) -> usize {
+        self.generation_service.get_number_of_requested()
+    }
+
+    // puts up to max_chunks chunks the generation service finished into the world - returns how many went in
+    pub fn receive_generated_chunks(&mut self, bcs: &BlockComponentSystem, max_chunks: usize) -> io::Result<usize> {
+        let mut received: usize = 0;
+
+        while received < max_chunks {
+            let finished_chunk: FinishedChunk = match self.generation_service.try_receive() {
+                Some(finished_chunk) => finished_chunk,
+                None => break,
+            };
+
+            let (x, z) = finished_chunk.get_pos();
+
+            // it was loaded some other way while it was being generated
+            if self.world.chunk_exists(x, z) {
+                continue;
+            }
+
+            match finished_chunk {
+                FinishedChunk::Generated(generated_chunk) => self.insert_generated_chunk(bcs, generated_chunk),
+                FinishedChunk::Loaded(x, z, chunk) => self.insert_loaded_chunk(bcs, x, z, chunk?),
+            }
+
+            received += 1;
+        }
+
+        Ok(received)
+    }
+
+    /*
+    hands queued mesh updates to the generation pool, each with a snapshot of the world as it is now
+
+    only a few are built at once so the updates that come in while they're being built still
+    go out in queue order - returns how many were handed over
+    */
+    pub fn request_meshes(&mut self, bcs: &Arc<BlockComponentSystem>) -> usize {
+        let mut requested: usize = 0;
+
+        while self.chunk_mesh_service.get_number_in_flight() < self.chunk_mesh_service.get_max_in_flight() {
+            let mesh_update: MeshUpdate = match self.chunk_mesh_generator_queue.pop_front() {
+                Some(mesh_update) => mesh_update,
+                None => break,
+            };
+
+            // add neighbors to queue if told to do so
+            if mesh_update.update_neighbors() {
+                self.chunk_mesh_generator_queue.batch_neighbor_update(mesh_update.get_x(), mesh_update.get_z());
+            }
+
+            self.chunk_mesh_service.request(bcs, &self.world, &mesh_update);
+
+            requested += 1;
+        }
+
+        requested
+    }
+
+    // up to max_meshes meshes the generation pool finished, only the sections newer than the ones the chunks have
+    pub fn receive_meshes(&mut self, max_meshes: usize) -> Vec<FinishedMesh> {
+        let mut finished_meshes: Vec<FinishedMesh> = Vec::new();
+
+        while finished_meshes.len() < max_meshes {
+            match self.chunk_mesh_service.try_receive(&self.world) {
+                Some(finished_mesh) => finished_meshes.push(finished_mesh),
+                None => break,
+            }
+        }
+
+        finished_meshes
+    }
+
+    // how many meshes are being built
+    pub fn get_number_of_meshing(&self) -> usize {
+        self.chunk_mesh_service.get_number_in_flight()
+    }
+
+    /*
+    takes chunks out of the dimension, the modified ones are saved first
+
+    returns the meshes of the chunks that were taken out, the caller frees them
+    */
+    pub fn unload_chunks(&mut self, chunks: &[(i32, i32)]) -> io::Result<Vec<u32>> {
+
+        self.world_save.save_chunks(&mut self.world, chunks)?;
+
+        let mut mesh_ids: Vec<u32> = Vec::new();
+
+        for (x, z) in chunks {
+            if let Some(chunk_mesh_ids) = self.world.remove_chunk(*x, *z) {
+                mesh_ids.extend(chunk_mesh_ids);
+            }
+            self.chunk_mesh_service.forget(*x, *z);
+        }
+
+        // everything in the cached regions is on disk, the ones nothing loaded is in can go
+        self.world_save.release_regions(&self.world);
+
+        Ok(mesh_ids)
+    }
+
+    // loads and lights a saved chunk - returns false if it was never saved
+    fn load_chunk(&mut self, bcs: &BlockComponentSystem, x: i32, z: i32) -> io::Result<bool> {
+
+        if !self.world_save.load_chunk(&mut self.world, x, z)? {
+            return Ok(false);
+        }
+
+        self.decoration_queue.decorate_loaded_chunk(&mut self.world, bcs, x, z);
+
+        self.finish_chunk(bcs, x, z);
+
+        Ok(true)
+    }
+
+    // a saved chunk the generation pool read back
+    fn insert_loaded_chunk(&mut self, bcs: &BlockComponentSystem, x: i32, z: i32, chunk: ChunkData) {
+
+        self.world.insert_chunk(x, z, chunk);
+
+        self.decoration_queue.decorate_loaded_chunk(&mut self.world, bcs, x, z);
+
+        self.finish_chunk(bcs, x, z);
+    }
+
+    fn insert_generated_chunk(&mut self, bcs: &BlockComponentSystem, generated_chunk: GeneratedChunk) {
+
+        let (x, z) = generated_chunk.get_pos();
+
+        let (mut chunk, writes): (ChunkData, Vec<DecorationWrite>) = generated_chunk.unpack();
+
+        self.decoration_queue.decorate_generated_chunk(&mut self.world, bcs, x, z, &mut chunk, writes);
+
+        self.world.insert_generated_chunk(x, z, chunk);
+
+        self.finish_chunk(bcs, x, z);
+    }
+
+    fn finish_chunk(&mut self, bcs: &BlockComponentSystem, x: i32, z: i32) {
+
+        // unloaded chunks were generated too, they're on disk
+        let (world, world_save) = (&self.world, &mut self.world_save);
+
+        self.decoration_queue.forget_finished(x, z, |x, z| world.chunk_exists(x, z) || world_save.has_chunk(x, z).unwrap_or(false));
+
+        // light has to be in place before the mesh is built, the mesher draws with it
+        light_chunk(&mut self.world, bcs, x, z);
+    }
+
+    // writes every changed chunk and the decorations still waiting to disk - returns how many chunks were saved
+    pub fn save_unsaved(&mut self, bcs: &BlockComponentSystem) -> io::Result<usize> {
+        self.decoration_queue.save(self.world_save.get_directory(), bcs)?;
+
+        self.world_save.save_unsaved(&mut self.world)
+    }
+}
+
+// every dimension of an open world
+pub struct Dimensions {
+    descriptor: WorldDescriptor,
+    dimensions: Vec<Dimension>,
+    // name -> index into dimensions
+    index: HashMap<String, usize>
+}
+
+impl Dimensions {
+
+    /*
+    opens every dimension of the world in world_directory, new_descriptor is only used if the world is new
+
+    the overworld has to be one of the definitions, lua always registers it
+    */
+    pub fn open(world_directory: &Path, new_descriptor: WorldDescriptor, definitions: Vec<DimensionDefinition>, bcs: &BlockComponentSystem) -> io::Result<Self> {
+
+        if !definitions.iter().any(|definition| definition.name == OVERWORLD) {
+            return Err(Error::new(ErrorKind::InvalidInput, "THERE IS NO OVERWORLD DIMENSION!"));
+        }
+
+        // the overworld holds the world's real descriptor, the rest follow it
+        let overworld_save: WorldSave = WorldSave::open(world_directory, new_descriptor, bcs)?;
+
+        let descriptor: WorldDescriptor = overworld_save.get_metadata().get_descriptor().clone();
+
+        let mut overworld_save_option: Option<WorldSave> = Some(overworld_save);
+
+        let generation_pool: Arc<ThreadPool> = new_generation_pool();
+
+        let mut dimensions: Vec<Dimension> = Vec::with_capacity(definitions.len());
+        let mut index: HashMap<String, usize> = HashMap::new();
+
+        for definition in definitions {
+
+            if definition.gcs.get_number_of_biomes() == 0 {
+                return Err(Error::new(ErrorKind::InvalidInput, format!("DIMENSION {} HAS NO BIOMES!", definition.name)));
+            }
+
+            let world_save: WorldSave = if definition.name == OVERWORLD {
+                overworld_save_option.take().unwrap()
+            } else {
+                WorldSave::open(&dimension_directory(world_directory, &definition.name)?, descriptor.clone(), bcs)?
+            };
+
+            index.insert(definition.name.clone(), dimensions.len());
+            dimensions.push(Dimension::open(definition, world_save, &descriptor, bcs, generation_pool.clone())?);
+        }
+
+        Ok(Self {
+            descriptor,
+            dimensions,
+            index
+        })
+    }
+
+    pub fn get_descriptor(&self) -> &WorldDescriptor {
+        &self.descriptor
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Dimension> {
+        self.index.get(name).map(|index| &self.dimensions[*index])
+    }
+
+    pub fn get_mut(&mut self, name: &str) -> Option<&mut Dimension> {
+        match self.index.get(name) {
+            Some(index) => Some(&mut self.dimensions[*index]),
+            None => None,
+        }
+    }
+
+    // the dimension registered after this one, wrapping around - how travel cycles through them
+    pub fn get_next_name(&self, name: &str) -> &String {
+        let next: usize = match self.index.get(name) {
+            Some(index) => (index + 1) % self.dimensions.len(),
+            None => 0,
+        };
+        &self.dimensions[next].name
+    }
+
+    /*
+    moves the falling block at index in the from dimension into the to dimension, at the same position and speed -
+    if its chunk isn't loaded there it waits in the air until it is
+
+    returns false if either dimension doesn't exist, they're the same one, or there's no block at index
+    */
+    pub fn transfer_falling_block(&mut self, from: &str, to: &str, index: usize) -> bool {
+        let (from_index, to_index) = match (self.index.get(from), self.index.get(to)) {
+            (Some(from_index), Some(to_index)) if from_index != to_index => (*from_index, *to_index),
+            _ => return false,
+        };
+
+        match self.dimensions[from_index].falling_blocks.take(index) {
+            Some(block) => {
+                self.dimensions[to_index].falling_blocks.insert(block);
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Dimension> {
+        self.dimensions.iter()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Dimension> {
+        self.dimensions.iter_mut()
+    }
+
+    // saves every dimension - returns how many chunks were written
+    pub fn save_unsaved(&mut self, bcs: &BlockComponentSystem) -> io::Result<usize> {
+        let mut saved_chunks: usize = 0;
+        for dimension in self.dimensions.iter_mut() {
+            saved_chunks += dimension.save_unsaved(bcs)?;
+        }
+        Ok(saved_chunks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs,
+        thread,
+        time::Duration
+    };
+
+    use crate::{
+        biomes::generation_component_system::{
+            Climate,
+            LayerDepth,
+            NoiseParams,
+            TerrainNoiseParams
+        },
+        blocks::block_component_system::{
+            AtlasTextureMap,
+            DrawType,
+            RotationType
+        }
+    };
+
+    use super::*;
+
+    // a flat overworld of stone (id 1) in a fresh world directory, stone has a texture so it can be meshed
+    fn test_dimensions(name: &str) -> (Dimensions, Arc<BlockComponentSystem>, PathBuf) {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+        let mapping: Vec<AtlasTextureMap> = (0..6).map(|_| AtlasTextureMap::new(0.0, 0.0, 1.0, 1.0, 0, 0)).collect();
+        bcs.register_block(String::from("test"), String::from("stone"), DrawType::Normal, vec![], None, mapping, 0, RotationType::None);
+
+        let mut gcs: GenerationComponentSystem = GenerationComponentSystem::new();
+        gcs.set_base_height(20);
+        gcs.register_biome(
+            String::from("plains"), TerrainNoiseParams::new(10.0, 0.01), Climate::new(None, None, None), 0, String::from("test"),
+            1, LayerDepth::new(1, 1), 1, LayerDepth::new(1, 1), 1, 1, None, false, NoiseParams::new(0.0, 0.0, 0.0, 0.0), false, false
+        );
+
+        let directory: PathBuf = std::env::temp_dir().join(format!("crafter_dimension_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+
+        let dimensions: Dimensions = Dimensions::open(
+            &directory,
+            WorldDescriptor::new(String::from("test"), Some(1), GeneratorType::Flat),
+            vec![DimensionDefinition::new(OVERWORLD.to_string(), None, gcs)],
+            &bcs
+        ).unwrap();
+
+        (dimensions, Arc::new(bcs), directory)
+    }
+
+    // hands out and receives meshes until the queue is empty and nothing is being built, (x, z, section) of every mesh that came back
+    fn mesh_everything(dimension: &mut Dimension, bcs: &Arc<BlockComponentSystem>) -> Vec<(i32, i32, usize)> {
+        let mut meshed: Vec<(i32, i32, usize)> = Vec::new();
+
+        for _ in 0..1000 {
+            for finished_mesh in dimension.receive_meshes(usize::MAX) {
+                let (x, z) = finished_mesh.get_pos();
+
+                for (section, mesh_data) in finished_mesh.unpack() {
+                    if mesh_data.is_some() {
+                        meshed.push((x, z, section));
+                    }
+                }
+            }
+
+            // nothing left in the queue once there is room and nothing goes out
+            if dimension.request_meshes(bcs) == 0 && dimension.get_number_of_meshing() == 0 {
+                return meshed;
+            }
+
+            thread::sleep(Duration::from_millis(5));
+        }
+
+        panic!("MESHING NEVER FINISHED!");
+    }
+
+    #[test]
+    fn only_plain_dimension_names_become_directories() {
+        let world_directory: &Path = Path::new("worlds/test");
+
+        assert_eq!(dimension_directory(world_directory, OVERWORLD).unwrap(), PathBuf::from("worlds/test"));
+        assert_eq!(dimension_directory(world_directory, "sky_2").unwrap(), PathBuf::from("worlds/test/dimensions/sky_2"));
+
+        for name in ["", "..", "../../etc", "sky/lands", "/tmp", "Sky", "sky lands", "sky.2", "sky\\..\\.."] {
+            assert_eq!(dimension_directory(world_directory, name).unwrap_err().kind(), ErrorKind::InvalidInput, "{:?}", name);
+        }
+    }
+
+    #[test]
+    fn queued_meshes_come_back_from_the_pool() {
+        let (mut dimensions, bcs, directory) = test_dimensions("meshing");
+        let dimension: &mut Dimension = dimensions.get_mut(OVERWORLD).unwrap();
+
+        for x in 0..3 {
+            dimension.load_or_generate_chunk(&bcs, x, 0).unwrap();
+        }
+        dimension.dispatch_events(&bcs, &mut []);
+
+        let mut meshed: Vec<(i32, i32, usize)> = mesh_everything(dimension, &bcs);
+        meshed.sort();
+        meshed.dedup();
+
+        // the ground at y 20 is all in the second section
+        assert_eq!(meshed, vec![(0, 0, 1), (1, 0, 1), (2, 0, 1)]);
+
+        // a chunk unloaded while its mesh is being built never gets it
+        dimension.get_world_mut().set_block(1, 20, 1, 0);
+        dimension.get_world_mut().set_block(17, 20, 1, 0);
+        dimension.dispatch_events(&bcs, &mut []);
+
+        dimension.request_meshes(&bcs);
+        dimension.unload_chunks(&[(0, 0)]).unwrap();
+
+        assert_eq!(mesh_everything(dimension, &bcs), vec![(1, 0, 1)]);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,430 @@
+/*
+falling blocks - blocks in the falling group (sand) don't float
+
+when the block under one becomes air or liquid it is taken out of the world and
+falls as a falling block, every game tick it speeds up and drops until it hits
+something - on a solid block it lands and goes back into the world as a block,
+on anything else (a block box, a slab) it drops, and a BlockDropped event lets
+the mods decide what's left of it
+
+the world never starts a fall itself, the FallingBlockObserver watches block
+changes and asks for one, the fall starts on the next tick - so taking out the
+bottom of a pillar of sand brings the whole pillar down, one block after the other
+
+falling blocks only live in memory, like scheduled updates
+*/
+
+use crate::blocks::block_component_system::BlockComponentSystem;
+
+use super::{
+    world::{
+        World,
+        MIN_Y
+    },
+    lighting::set_block_lit,
+    world_event::{
+        WorldEvent,
+        WorldObserver
+    }
+};
+
+// the group that makes a block fall
+pub const FALLING_GROUP: &str = "falling";
+
+// blocks per tick a falling block speeds up by each tick
+const GRAVITY: f32 = 0.04;
+
+// blocks per tick, a falling block never goes faster
+const MAX_FALL_SPEED: f32 = 2.0;
+
+pub fn is_falling_block(bcs: &BlockComponentSystem, id: u32) -> bool {
+    bcs.get_group(id, FALLING_GROUP) > 0
+}
+
+// a falling block can fall through air and liquids
+fn is_passable(bcs: &BlockComponentSystem, id: u32) -> bool {
+    id == 0 || bcs.is_liquid(id)
+}
+
+pub struct FallingBlock {
+    id: u32,
+    rotation: u8,
+    x: i32,
+    // the bottom of the block
+    y: f32,
+    z: i32,
+    // blocks per tick, down
+    velocity: f32
+}
+
+impl FallingBlock {
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_rotation(&self) -> u8 {
+        self.rotation
+    }
+
+    // the bottom corner of the block, the same space blocks are in
+    pub fn get_pos(&self) -> (f32, f32, f32) {
+        (self.x as f32, self.y, self.z as f32)
+    }
+
+    pub fn get_velocity(&self) -> f32 {
+        self.velocity
+    }
+}
+
+// what a falling block did this tick
+enum Fall {
+    Falling,
+    Landed,
+    Dropped,
+    // it went out the bottom of the world
+    Lost
+}
+
+pub struct FallingBlocks {
+    blocks: Vec<FallingBlock>,
+    // blocks the observer thinks might have to start falling
+    pending: Vec<(i32, i32, i32)>
+}
+
+impl FallingBlocks {
+    pub fn new() -> Self {
+        Self {
+            blocks: Vec::new(),
+            pending: Vec::new()
+        }
+    }
+
+    // the blocks that are falling right now
+    pub fn iter(&self) -> impl Iterator<Item = &FallingBlock> {
+        self.blocks.iter()
+    }
+
+    pub fn get_number_of_falling(&self) -> usize {
+        self.blocks.len()
+    }
+
+    // takes a falling block out, it keeps falling wherever it's put back in - None if there's no block at index
+    pub fn take(&mut self, index: usize) -> Option<FallingBlock> {
+        if index < self.blocks.len() {
+            Some(self.blocks.swap_remove(index))
+        } else {
+            None
+        }
+    }
+
+    // a block that was falling somewhere else goes on falling here, from the same position and speed
+    pub fn insert(&mut self, block: FallingBlock) {
+        self.blocks.push(block);
+    }
+
+    // asks for the block at x,y,z to be checked on the next tick, nothing happens if it has ground under it
+    pub fn check(&mut self, x: i32, y: i32, z: i32) {
+        self.pending.push((x, y, z));
+    }
+
+    // one game tick - starts the falls that were asked for, then moves everything that's falling
+    pub fn update(&mut self, world: &mut World, bcs: &BlockComponentSystem) {
+
+        for (x, y, z) in std::mem::take(&mut self.pending) {
+            self.start_fall(world, bcs, x, y, z);
+        }
+
+        self.blocks.retain_mut(|block| {
+            matches!(step(world, bcs, block), Fall::Falling)
+        });
+    }
+
+    fn start_fall(&mut self, world: &mut World, bcs: &BlockComponentSystem, x: i32, y: i32, z: i32) {
+
+        let id: u32 = match world.get_block(x, y, z) {
+            Some(id) if is_falling_block(bcs, id) => id,
+            _ => return,
+        };
+
+        // an unloaded chunk underneath holds it up until it loads
+        match world.get_block(x, y - 1, z) {
+            Some(below) if is_passable(bcs, below) => (),
+            _ => return,
+        }
+
+        let rotation: u8 = world.get_block_rotation(x, y, z).unwrap();
+
+        set_block_lit(world, bcs, x, y, z, 0);
+
+        self.blocks.push(FallingBlock {
+            id,
+            rotation,
+            x,
+            y: y as f32,
+            z,
+            velocity: 0.0
+        });
+    }
+}
+
+impl Default for FallingBlocks {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// moves a falling block one tick down, it lands on top of the first block in the way
+fn step(world: &mut World, bcs: &BlockComponentSystem, block: &mut FallingBlock) -> Fall {
+
+    let velocity: f32 = (block.velocity + GRAVITY).min(MAX_FALL_SPEED);
+
+    let target: f32 = block.y - velocity;
+
+    // every cell the bottom of the block goes into this tick, top to bottom
+    for cell in ((target.floor() as i32)..(block.y.ceil() as i32)).rev() {
+
+        if cell < MIN_Y {
+            return Fall::Lost;
+        }
+
+        match world.get_block(block.x, cell, block.z) {
+            Some(id) if is_passable(bcs, id) => (),
+            Some(id) => return land(world, bcs, block, cell + 1, id),
+            // wait at the edge of an unloaded chunk
+            None => {
+                block.y = (cell + 1) as f32;
+                block.velocity = 0.0;
+                return Fall::Falling;
+            },
+        }
+    }
+
+    block.y = target;
+    block.velocity = velocity;
+
+    Fall::Falling
+}
+
+// puts the block back into the world at y, on top of the block below, or drops it if that block can't hold it
+fn land(world: &mut World, bcs: &BlockComponentSystem, block: &FallingBlock, y: i32, below: u32) -> Fall {
+
+    // only full blocks can hold a block up
+    if !bcs.is_opaque(below) {
+        world.emit(WorldEvent::BlockDropped { x: block.x, y, z: block.z, id: block.id });
+        return Fall::Dropped;
+    }
+
+    set_block_lit(world, bcs, block.x, y, block.z, block.id);
+    world.set_block_rotation(block.x, y, block.z, block.rotation);
+
+    Fall::Landed
+}
+
+/*
+asks for a fall everywhere a block change could start one - a falling block
+that was just put down, or the block on top of one that was just taken away
+*/
+pub struct FallingBlockObserver<'a> {
+    bcs: &'a BlockComponentSystem,
+    falling_blocks: &'a mut FallingBlocks
+}
+
+impl<'a> FallingBlockObserver<'a> {
+    pub fn new(bcs: &'a BlockComponentSystem, falling_blocks: &'a mut FallingBlocks) -> Self {
+        Self {
+            bcs,
+            falling_blocks
+        }
+    }
+}
+
+impl WorldObserver for FallingBlockObserver<'_> {
+    fn on_world_event(&mut self, world: &World, event: &WorldEvent) {
+        if let WorldEvent::BlockSet { x, y, z, new_id, .. } = *event {
+            if is_falling_block(self.bcs, new_id) {
+                self.falling_blocks.check(x, y, z);
+            }
+
+            if is_passable(self.bcs, new_id) {
+                match world.get_block(x, y + 1, z) {
+                    Some(above) if is_falling_block(self.bcs, above) => self.falling_blocks.check(x, y + 1, z),
+                    _ => (),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::blocks::block_component_system::{
+        DrawType,
+        RotationType
+    };
+
+    use super::*;
+
+    // air, stone, sand (falling) and a slab
+    fn test_bcs() -> (BlockComponentSystem, u32, u32, u32) {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+
+        let stone: u32 = bcs.register_block(String::from("test"), String::from("stone"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+        let sand: u32 = bcs.register_block(String::from("test"), String::from("sand"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+        let slab: u32 = bcs.register_block(String::from("test"), String::from("slab"), DrawType::BlockBox, vec![], None, vec![], 0, RotationType::None);
+
+        bcs.set_block_groups(sand, HashMap::from([(String::from(FALLING_GROUP), 1)]));
+
+        (bcs, stone, sand, slab)
+    }
+
+    // a floor of stone at y 10 in chunk 0,0
+    fn test_world(stone: u32) -> World {
+        let mut world: World = World::initialize();
+        world.add_chunk(0, 0);
+
+        for x in 0..16 {
+            for z in 0..16 {
+                world.set_block(x, 10, z, stone);
+            }
+        }
+
+        world.take_events();
+
+        world
+    }
+
+    // dispatches the world's events to the falling blocks and runs long enough for anything to land
+    fn run(world: &mut World, bcs: &BlockComponentSystem, falling_blocks: &mut FallingBlocks) -> Vec<WorldEvent> {
+        let mut events: Vec<WorldEvent> = Vec::new();
+
+        for _ in 0..200 {
+            for event in world.take_events() {
+                FallingBlockObserver::new(bcs, falling_blocks).on_world_event(world, &event);
+                events.push(event);
+            }
+
+            falling_blocks.update(world, bcs);
+        }
+
+        events
+    }
+
+    #[test]
+    fn placed_sand_falls_to_the_ground() {
+        let (bcs, stone, sand, _) = test_bcs();
+        let mut world: World = test_world(stone);
+        let mut falling_blocks: FallingBlocks = FallingBlocks::new();
+
+        world.set_block(5, 40, 5, sand);
+        world.set_block_rotation(5, 40, 5, 3);
+
+        run(&mut world, &bcs, &mut falling_blocks);
+
+        assert_eq!(world.get_block(5, 40, 5), Some(0));
+        assert_eq!(world.get_block(5, 11, 5), Some(sand));
+        assert_eq!(world.get_block_rotation(5, 11, 5), Some(3));
+        assert_eq!(falling_blocks.get_number_of_falling(), 0);
+    }
+
+    #[test]
+    fn sand_with_ground_under_it_stays() {
+        let (bcs, stone, sand, _) = test_bcs();
+        let mut world: World = test_world(stone);
+        let mut falling_blocks: FallingBlocks = FallingBlocks::new();
+
+        world.set_block(5, 11, 5, sand);
+
+        run(&mut world, &bcs, &mut falling_blocks);
+
+        assert_eq!(world.get_block(5, 11, 5), Some(sand));
+    }
+
+    #[test]
+    fn removing_the_support_brings_the_pillar_down() {
+        let (bcs, stone, sand, _) = test_bcs();
+        let mut world: World = test_world(stone);
+        let mut falling_blocks: FallingBlocks = FallingBlocks::new();
+
+        world.set_block(5, 20, 5, stone);
+        for y in 21..24 {
+            world.set_block(5, y, 5, sand);
+        }
+        world.take_events();
+
+        world.set_block(5, 20, 5, 0);
+
+        run(&mut world, &bcs, &mut falling_blocks);
+
+        for y in 11..14 {
+            assert_eq!(world.get_block(5, y, 5), Some(sand));
+        }
+        for y in 14..24 {
+            assert_eq!(world.get_block(5, y, 5), Some(0));
+        }
+    }
+
+    #[test]
+    fn landing_on_a_slab_drops_the_block() {
+        let (bcs, stone, sand, slab) = test_bcs();
+        let mut world: World = test_world(stone);
+        let mut falling_blocks: FallingBlocks = FallingBlocks::new();
+
+        world.set_block(5, 11, 5, slab);
+        world.set_block(5, 30, 5, sand);
+
+        let events: Vec<WorldEvent> = run(&mut world, &bcs, &mut falling_blocks);
+
+        assert_eq!(world.get_block(5, 11, 5), Some(slab));
+        assert_eq!(world.get_block(5, 12, 5), Some(0));
+        assert!(events.contains(&WorldEvent::BlockDropped { x: 5, y: 12, z: 5, id: sand }));
+    }
+
+    #[test]
+    fn falling_speeds_up_but_never_skips_a_block() {
+        let (bcs, stone, sand, _) = test_bcs();
+        let mut world: World = test_world(stone);
+        let mut falling_blocks: FallingBlocks = FallingBlocks::new();
+
+        // a one block ledge far below, it has to be hit at full speed
+        world.set_block(5, 120, 5, sand);
+        world.set_block(5, 50, 5, stone);
+
+        run(&mut world, &bcs, &mut falling_blocks);
+
+        assert_eq!(world.get_block(5, 51, 5), Some(sand));
+    }
+
+    #[test]
+    fn a_block_taken_out_mid_fall_lands_in_the_world_it_is_put_into() {
+        let (bcs, stone, sand, _) = test_bcs();
+        let mut world: World = test_world(stone);
+        let mut other_world: World = test_world(stone);
+        let mut falling_blocks: FallingBlocks = FallingBlocks::new();
+        let mut other_falling_blocks: FallingBlocks = FallingBlocks::new();
+
+        // a ledge in the first world that the block never gets to
+        world.set_block(5, 30, 5, stone);
+        world.set_block(5, 60, 5, sand);
+
+        for event in world.take_events() {
+            FallingBlockObserver::new(&bcs, &mut falling_blocks).on_world_event(&world, &event);
+        }
+        falling_blocks.update(&mut world, &bcs);
+
+        assert!(falling_blocks.take(1).is_none());
+
+        let block: FallingBlock = falling_blocks.take(0).unwrap();
+        assert_eq!(block.get_id(), sand);
+
+        other_falling_blocks.insert(block);
+
+        run(&mut world, &bcs, &mut falling_blocks);
+        run(&mut other_world, &bcs, &mut other_falling_blocks);
+
+        assert_eq!(world.get_block(5, 31, 5), Some(0));
+        assert_eq!(other_world.get_block(5, 11, 5), Some(sand));
+        assert_eq!(other_falling_blocks.get_number_of_falling(), 0);
+    }
+}
@@ -0,0 +1,593 @@
+/*
+block and sky light
+
+every block holds a single light byte - sky light in the high 4 bits, block light in the low 4 bits
+
+sky light pours straight down from the top of the world without getting weaker,
+every other step (sideways, upwards, and block light in any direction) takes 1 away
+
+light floods across chunk borders through the global World accessors, an unloaded
+chunk stops the flood - when it loads in, lighting it pulls the light back over the border
+*/
+
+use std::collections::{
+    HashSet,
+    VecDeque
+};
+
+use crate::blocks::block_component_system::BlockComponentSystem;
+
+use super::{
+    chunk_storage::ChunkStorage,
+    world_event::WorldEvent,
+    world::{
+        World,
+        CHUNK_WIDTH,
+        CHUNK_HEIGHT,
+        CHUNK_SIZE,
+        MIN_Y,
+        MAX_Y,
+        pos_to_index,
+        index_to_pos
+    }
+};
+
+pub const MAX_LIGHT: u8 = 15;
+
+// local x/z of the last block in a chunk
+const CHUNK_EDGE: usize = (CHUNK_WIDTH - 1) as usize;
+
+// (x, y, z) steps to the 6 blocks touching a block
+const DIRECTIONS: [(i32, i32, i32); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1)
+];
+
+// position along a chunk side -> local (x, z) of the border block in the chunk and of the one across from it
+type BorderPair = fn(usize) -> ((usize, usize), (usize, usize));
+
+#[derive(Clone, Copy, PartialEq)]
+enum Channel {
+    Sky,
+    Block
+}
+
+pub fn get_sky_light(light: u8) -> u8 {
+    light >> 4
+}
+
+pub fn get_block_light(light: u8) -> u8 {
+    light & 0x0F
+}
+
+pub fn pack_light(sky_light: u8, block_light: u8) -> u8 {
+    (sky_light << 4) | (block_light & 0x0F)
+}
+
+// the brightest of the two channels, this is what the mesher draws with
+pub fn get_light_level(light: u8) -> u8 {
+    get_sky_light(light).max(get_block_light(light))
+}
+
+fn read_channel(world: &World, channel: Channel, x: i32, y: i32, z: i32) -> Option<u8> {
+    let light: u8 = world.get_block_light(x, y, z)?;
+
+    match channel {
+        Channel::Sky => Some(get_sky_light(light)),
+        Channel::Block => Some(get_block_light(light)),
+    }
+}
+
+// writes one channel and remembers which sections have to be remeshed because of it
+fn write_channel(world: &mut World, channel: Channel, x: i32, y: i32, z: i32, level: u8, changed: &mut HashSet<(i32, usize, i32)>) {
+
+    let light: u8 = match world.get_block_light(x, y, z) {
+        Some(light) => light,
+        None => return,
+    };
+
+    let new_light: u8 = match channel {
+        Channel::Sky => pack_light(level, get_block_light(light)),
+        Channel::Block => pack_light(get_sky_light(light), level),
+    };
+
+    if world.set_block_light_unqueued(x, y, z, new_light) {
+        World::for_each_remesh_section(x, y, z, |chunk_x, section, chunk_z| {
+            changed.insert((chunk_x, section, chunk_z));
+        });
+    }
+}
+
+// how bright a block next to a block of level gets, dy is the step from the lit block to it
+fn spread(channel: Channel, level: u8, dy: i32) -> u8 {
+    if channel == Channel::Sky && dy == -1 && level == MAX_LIGHT {
+        MAX_LIGHT
+    } else {
+        level.saturating_sub(1)
+    }
+}
+
+// floods light outwards from every block in the queue
+fn propagate(world: &mut World, bcs: &BlockComponentSystem, channel: Channel, queue: &mut VecDeque<(i32, i32, i32)>, changed: &mut HashSet<(i32, usize, i32)>) {
+
+    while let Some((x, y, z)) = queue.pop_front() {
+
+        let level: u8 = read_channel(world, channel, x, y, z).unwrap_or(0);
+
+        if level == 0 {
+            continue;
+        }
+
+        for (dx, dy, dz) in DIRECTIONS {
+
+            let (neighbor_x, neighbor_y, neighbor_z) = (x + dx, y + dy, z + dz);
+
+            // unloaded or outside the world
+            let neighbor_id: u32 = match world.get_block(neighbor_x, neighbor_y, neighbor_z) {
+                Some(id) => id,
+                None => continue,
+            };
+
+            if bcs.is_opaque(neighbor_id) {
+                continue;
+            }
+
+            let target: u8 = spread(channel, level, dy);
+
+            if read_channel(world, channel, neighbor_x, neighbor_y, neighbor_z).unwrap_or(MAX_LIGHT) < target {
+                write_channel(world, channel, neighbor_x, neighbor_y, neighbor_z, target, changed);
+                queue.push_back((neighbor_x, neighbor_y, neighbor_z));
+            }
+        }
+    }
+}
+
+/*
+darkens everything that was lit by the blocks in the removal queue
+
+anything brighter found along the way is handed to the propagation queue so it can fill the hole back in
+*/
+fn remove(
+    world: &mut World,
+    bcs: &BlockComponentSystem,
+    channel: Channel,
+    removal: &mut VecDeque<((i32, i32, i32), u8)>,
+    queue: &mut VecDeque<(i32, i32, i32)>,
+    changed: &mut HashSet<(i32, usize, i32)>
+) {
+
+    while let Some(((x, y, z), level)) = removal.pop_front() {
+
+        for (dx, dy, dz) in DIRECTIONS {
+
+            let (neighbor_x, neighbor_y, neighbor_z) = (x + dx, y + dy, z + dz);
+
+            let neighbor_level: u8 = match read_channel(world, channel, neighbor_x, neighbor_y, neighbor_z) {
+                Some(neighbor_level) => neighbor_level,
+                None => continue,
+            };
+
+            if neighbor_level == 0 {
+                continue;
+            }
+
+            // was this neighbor lit by the block that went dark?
+            let fed_by_removed: bool =
+                neighbor_level < level ||
+                (channel == Channel::Sky && dy == -1 && level == MAX_LIGHT && neighbor_level == MAX_LIGHT);
+
+            if fed_by_removed {
+                write_channel(world, channel, neighbor_x, neighbor_y, neighbor_z, 0, changed);
+                removal.push_back(((neighbor_x, neighbor_y, neighbor_z), neighbor_level));
+
+                // light sources keep shining no matter what
+                if channel == Channel::Block {
+                    let source: u8 = bcs.get_light(world.get_block(neighbor_x, neighbor_y, neighbor_z).unwrap());
+                    if source > 0 {
+                        write_channel(world, channel, neighbor_x, neighbor_y, neighbor_z, source, changed);
+                        queue.push_back((neighbor_x, neighbor_y, neighbor_z));
+                    }
+                }
+            } else {
+                queue.push_back((neighbor_x, neighbor_y, neighbor_z));
+            }
+        }
+    }
+}
+
+// floods one channel through a single chunk's flat arrays, the cheap part of lighting a chunk
+fn propagate_local(opaque: &[bool], light: &mut [u8], channel: Channel, queue: &mut VecDeque<usize>) {
+
+    let read = | light: &[u8], index: usize | match channel {
+        Channel::Sky => get_sky_light(light[index]),
+        Channel::Block => get_block_light(light[index]),
+    };
+
+    while let Some(index) = queue.pop_front() {
+
+        let level: u8 = read(light, index);
+
+        if level == 0 {
+            continue;
+        }
+
+        let (x, y, z) = index_to_pos(index);
+        let (x, y, z) = (x as i32, y as i32, z as i32);
+
+        for (dx, dy, dz) in DIRECTIONS {
+
+            let (neighbor_x, neighbor_y, neighbor_z) = (x + dx, y + dy, z + dz);
+
+            // the world level flood deals with everything over the border
+            if !(0..CHUNK_WIDTH).contains(&neighbor_x) ||
+               !(0..CHUNK_HEIGHT).contains(&neighbor_y) ||
+               !(0..CHUNK_WIDTH).contains(&neighbor_z) {
+                continue;
+            }
+
+            let neighbor_index: usize = pos_to_index(neighbor_x as usize, neighbor_y as usize, neighbor_z as usize);
+
+            if opaque[neighbor_index] {
+                continue;
+            }
+
+            let target: u8 = spread(channel, level, dy);
+
+            if read(light, neighbor_index) < target {
+                light[neighbor_index] = match channel {
+                    Channel::Sky => pack_light(target, get_block_light(light[neighbor_index])),
+                    Channel::Block => pack_light(get_sky_light(light[neighbor_index]), target),
+                };
+                queue.push_back(neighbor_index);
+            }
+        }
+    }
+}
+
+/*
+lights a freshly generated or loaded chunk
+
+the chunk is flooded on its own first, then light is traded with the loaded
+neighbors over the border - both ways
+
+a ChunkRelit is emitted for this chunk and a LightChanged for every section of
+another chunk the light over the border changed
+
+returns false if the chunk is not loaded
+*/
+pub fn light_chunk(world: &mut World, bcs: &BlockComponentSystem, chunk_x: i32, chunk_z: i32) -> bool {
+
+    let blocks: Vec<u32> = match world.get_chunk_blocks(chunk_x, chunk_z) {
+        Some(blocks) => blocks.to_vec(),
+        None => return false,
+    };
+
+    let opaque: Vec<bool> = blocks.iter().map(|id| bcs.is_opaque(*id)).collect();
+
+    let mut light: Vec<u8> = vec![0; CHUNK_SIZE];
+
+    let mut sky_queue: VecDeque<usize> = VecDeque::new();
+    let mut block_queue: VecDeque<usize> = VecDeque::new();
+
+    for x in 0..CHUNK_WIDTH as usize {
+        for z in 0..CHUNK_WIDTH as usize {
+
+            // sunlight goes straight down until it hits something
+            let mut sky_light: u8 = MAX_LIGHT;
+
+            for y in (0..CHUNK_HEIGHT as usize).rev() {
+
+                let index: usize = pos_to_index(x, y, z);
+
+                if opaque[index] {
+                    sky_light = 0;
+                }
+
+                let block_light: u8 = bcs.get_light(blocks[index]);
+
+                light[index] = pack_light(sky_light, block_light);
+
+                if sky_light > 0 {
+                    sky_queue.push_back(index);
+                }
+                if block_light > 0 {
+                    block_queue.push_back(index);
+                }
+            }
+        }
+    }
+
+    propagate_local(&opaque, &mut light, Channel::Sky, &mut sky_queue);
+    propagate_local(&opaque, &mut light, Channel::Block, &mut block_queue);
+
+    // a chunk loaded from disk is usually already lit, don't flag it for saving again
+    if world.get_chunk_light(chunk_x, chunk_z).unwrap().to_vec() != light {
+        *world.get_chunk_light_mut(chunk_x, chunk_z).unwrap() = ChunkStorage::from_slice(&light);
+    }
+
+    // now trade light over the border with the loaded neighbors
+    let base_x: i32 = chunk_x * CHUNK_WIDTH;
+    let base_z: i32 = chunk_z * CHUNK_WIDTH;
+
+    let mut sky_border_queue: VecDeque<(i32, i32, i32)> = VecDeque::new();
+    let mut block_border_queue: VecDeque<(i32, i32, i32)> = VecDeque::new();
+
+    // neighbor chunk offset and how the blocks pair up across that side
+    let sides: [((i32, i32), BorderPair); 4] = [
+        ((-1, 0), |along| ((0, along), (CHUNK_EDGE, along))),
+        ((1, 0), |along| ((CHUNK_EDGE, along), (0, along))),
+        ((0, -1), |along| ((along, 0), (along, CHUNK_EDGE))),
+        ((0, 1), |along| ((along, CHUNK_EDGE), (along, 0)))
+    ];
+
+    for ((offset_x, offset_z), border) in sides {
+
+        let (neighbor_blocks, neighbor_light) = match (
+            world.get_chunk_blocks(chunk_x + offset_x, chunk_z + offset_z),
+            world.get_chunk_light(chunk_x + offset_x, chunk_z + offset_z)
+        ) {
+            (Some(neighbor_blocks), Some(neighbor_light)) => (neighbor_blocks, neighbor_light),
+            _ => continue,
+        };
+
+        for along in 0..=CHUNK_EDGE {
+            for y in 0..CHUNK_HEIGHT as usize {
+
+                let ((inside_x, inside_z), (outside_x, outside_z)) = border(along);
+
+                let inside_index: usize = pos_to_index(inside_x, y, inside_z);
+                let outside_index: usize = pos_to_index(outside_x, y, outside_z);
+
+                let global_y: i32 = y as i32 + MIN_Y;
+
+                let inside_pos: (i32, i32, i32) = (base_x + inside_x as i32, global_y, base_z + inside_z as i32);
+                let outside_pos: (i32, i32, i32) = (
+                    base_x + (offset_x * CHUNK_WIDTH) + outside_x as i32,
+                    global_y,
+                    base_z + (offset_z * CHUNK_WIDTH) + outside_z as i32
+                );
+
+                let outside_opaque: bool = bcs.is_opaque(neighbor_blocks.get(outside_index));
+
+                let outside_light: u8 = neighbor_light.get(outside_index);
+
+                // light only crosses where one side is brighter by more than a step
+                for (channel, queue) in [(Channel::Sky, &mut sky_border_queue), (Channel::Block, &mut block_border_queue)] {
+
+                    let (inside_level, outside_level) = match channel {
+                        Channel::Sky => (get_sky_light(light[inside_index]), get_sky_light(outside_light)),
+                        Channel::Block => (get_block_light(light[inside_index]), get_block_light(outside_light)),
+                    };
+
+                    if inside_level > outside_level + 1 && !outside_opaque {
+                        queue.push_back(inside_pos);
+                    } else if outside_level > inside_level + 1 && !opaque[inside_index] {
+                        queue.push_back(outside_pos);
+                    }
+                }
+            }
+        }
+    }
+
+    let mut changed: HashSet<(i32, usize, i32)> = HashSet::new();
+
+    propagate(world, bcs, Channel::Sky, &mut sky_border_queue, &mut changed);
+    propagate(world, bcs, Channel::Block, &mut block_border_queue, &mut changed);
+
+    world.emit(WorldEvent::ChunkRelit { x: chunk_x, z: chunk_z });
+
+    // light that crossed the border can flood further than the neighbors the chunk event remeshes
+    for (changed_x, section, changed_z) in changed {
+        if (changed_x, changed_z) != (chunk_x, chunk_z) {
+            world.emit(WorldEvent::LightChanged { x: changed_x, section, z: changed_z });
+        }
+    }
+
+    true
+}
+
+/*
+relights a group of chunks from scratch, for bulk edits that went around update_light
+
+the light of every chunk in the group is thrown away first so nothing stale can
+flow back in, light still comes in over the border from chunks outside the group
+*/
+pub fn relight_chunks(world: &mut World, bcs: &BlockComponentSystem, chunks: &[(i32, i32)]) {
+
+    for (chunk_x, chunk_z) in chunks {
+        if let Some(light) = world.get_chunk_light_mut(*chunk_x, *chunk_z) {
+            light.fill(0);
+        }
+    }
+
+    for (chunk_x, chunk_z) in chunks {
+        light_chunk(world, bcs, *chunk_x, *chunk_z);
+    }
+}
+
+/*
+relights around a block that just changed, placed or removed
+
+the old light is taken away then flooded back in from whatever is still lit,
+every section that changed brightness gets one LightChanged
+*/
+pub fn update_light(world: &mut World, bcs: &BlockComponentSystem, x: i32, y: i32, z: i32) {
+
+    let id: u32 = match world.get_block(x, y, z) {
+        Some(id) => id,
+        None => return,
+    };
+
+    let opaque: bool = bcs.is_opaque(id);
+
+    let mut changed: HashSet<(i32, usize, i32)> = HashSet::new();
+
+    for channel in [Channel::Sky, Channel::Block] {
+
+        let mut removal: VecDeque<((i32, i32, i32), u8)> = VecDeque::new();
+        let mut queue: VecDeque<(i32, i32, i32)> = VecDeque::new();
+
+        let old_level: u8 = read_channel(world, channel, x, y, z).unwrap();
+
+        write_channel(world, channel, x, y, z, 0, &mut changed);
+        removal.push_back(((x, y, z), old_level));
+
+        remove(world, bcs, channel, &mut removal, &mut queue, &mut changed);
+
+        // whatever this block gives off itself
+        let source: u8 = match channel {
+            Channel::Sky => if !opaque && y == MAX_Y { MAX_LIGHT } else { 0 },
+            Channel::Block => bcs.get_light(id),
+        };
+
+        if source > 0 {
+            write_channel(world, channel, x, y, z, source, &mut changed);
+            queue.push_back((x, y, z));
+        }
+
+        // an opening lets the surrounding light back in
+        if !opaque {
+            for (dx, dy, dz) in DIRECTIONS {
+                queue.push_back((x + dx, y + dy, z + dz));
+            }
+        }
+
+        propagate(world, bcs, channel, &mut queue, &mut changed);
+    }
+
+    for (chunk_x, section, chunk_z) in changed {
+        world.emit(WorldEvent::LightChanged { x: chunk_x, section, z: chunk_z });
+    }
+}
+
+// sets a block and relights around it - returns false if the chunk is not loaded
+pub fn set_block_lit(world: &mut World, bcs: &BlockComponentSystem, x: i32, y: i32, z: i32, id: u32) -> bool {
+
+    if !world.set_block(x, y, z, id) {
+        return false;
+    }
+
+    update_light(world, bcs, x, y, z);
+
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::block_component_system::{
+        DrawType,
+        RotationType
+    };
+
+    use crate::world::chunk_storage::SECTION_HEIGHT;
+
+    use super::*;
+
+    // air, stone and a torch that gives off 14
+    fn test_bcs() -> (BlockComponentSystem, u32, u32) {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+
+        let stone: u32 = bcs.register_block(String::from("test"), String::from("stone"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+        let torch: u32 = bcs.register_block(String::from("test"), String::from("torch"), DrawType::BlockBox, vec![], None, vec![], 14, RotationType::None);
+
+        (bcs, stone, torch)
+    }
+
+    // lit chunks of open air next to each other along x
+    fn test_world(bcs: &BlockComponentSystem, chunks: i32) -> World {
+        let mut world: World = World::initialize();
+
+        for chunk_x in 0..chunks {
+            world.add_chunk(chunk_x, 0);
+            light_chunk(&mut world, bcs, chunk_x, 0);
+        }
+
+        world.take_events();
+
+        world
+    }
+
+    fn block_light_at(world: &World, x: i32, y: i32, z: i32) -> u8 {
+        get_block_light(world.get_block_light(x, y, z).unwrap())
+    }
+
+    fn sky_light_at(world: &World, x: i32, y: i32, z: i32) -> u8 {
+        get_sky_light(world.get_block_light(x, y, z).unwrap())
+    }
+
+    #[test]
+    fn a_light_source_lights_up_and_goes_dark_when_removed() {
+        let (bcs, _, torch) = test_bcs();
+        let mut world: World = test_world(&bcs, 1);
+
+        set_block_lit(&mut world, &bcs, 8, 20, 8, torch);
+
+        assert_eq!(block_light_at(&world, 8, 20, 8), 14);
+        assert_eq!(block_light_at(&world, 11, 20, 8), 11);
+        assert_eq!(block_light_at(&world, 8, 22, 9), 11);
+
+        set_block_lit(&mut world, &bcs, 8, 20, 8, 0);
+
+        for (x, y, z) in [(8, 20, 8), (11, 20, 8), (8, 22, 9), (1, 20, 8)] {
+            assert_eq!(block_light_at(&world, x, y, z), 0);
+        }
+    }
+
+    #[test]
+    fn block_light_crosses_chunk_borders_both_ways() {
+        let (bcs, _, torch) = test_bcs();
+        let mut world: World = test_world(&bcs, 2);
+
+        set_block_lit(&mut world, &bcs, 15, 20, 8, torch);
+
+        assert_eq!(block_light_at(&world, 17, 20, 8), 12);
+
+        let events: Vec<WorldEvent> = world.take_events();
+        assert!(events.contains(&WorldEvent::LightChanged { x: 1, section: ((20 - MIN_Y) / SECTION_HEIGHT) as usize, z: 0 }));
+
+        set_block_lit(&mut world, &bcs, 15, 20, 8, 0);
+
+        assert_eq!(block_light_at(&world, 17, 20, 8), 0);
+    }
+
+    #[test]
+    fn lighting_a_chunk_pushes_its_light_into_the_neighbors() {
+        let (bcs, _, torch) = test_bcs();
+        let mut world: World = test_world(&bcs, 1);
+
+        world.add_chunk(1, 0);
+        world.set_block(16, 20, 8, torch);
+        light_chunk(&mut world, &bcs, 1, 0);
+
+        assert_eq!(block_light_at(&world, 14, 20, 8), 12);
+
+        let events: Vec<WorldEvent> = world.take_events();
+        assert!(events.contains(&WorldEvent::LightChanged { x: 0, section: ((20 - MIN_Y) / SECTION_HEIGHT) as usize, z: 0 }));
+    }
+
+    #[test]
+    fn a_new_roof_shades_the_sky_light_under_it() {
+        let (bcs, stone, _) = test_bcs();
+        let mut world: World = test_world(&bcs, 1);
+
+        assert_eq!(sky_light_at(&world, 5, 20, 5), MAX_LIGHT);
+
+        for x in 1..10 {
+            for z in 1..10 {
+                set_block_lit(&mut world, &bcs, x, 30, z, stone);
+            }
+        }
+
+        // the closest open column is 5 steps away
+        assert_eq!(sky_light_at(&world, 5, 20, 5), MAX_LIGHT - 5);
+        assert_eq!(sky_light_at(&world, 5, 31, 5), MAX_LIGHT);
+
+        set_block_lit(&mut world, &bcs, 5, 30, 5, 0);
+
+        assert_eq!(sky_light_at(&world, 5, 20, 5), MAX_LIGHT);
+    }
+}
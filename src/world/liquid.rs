@@ -0,0 +1,474 @@
+/*
+flowing liquids
+
+a liquid is one block id, how much of it is in a block lives in the block's rotation byte:
+
+0 - a source, it never runs dry on its own
+1 through range - flowing, the number is how many blocks it is from the source that feeds it
+FALLING - fed from the liquid right above it, it spreads out like a source once it lands
+
+the flow is all scheduled updates - every time a block changes, the liquids in and right
+next to it get an update viscosity ticks later (the LiquidObserver does this), the update
+settles the block's own level, lets it react with other liquids touching it, and spreads
+it down or out, which changes more blocks and keeps the flow going until nothing changes
+
+liquids only flow into air, what happens when two liquids meet is up to the mods that
+defined them (the interactions of the liquid definition)
+*/
+
+use crate::blocks::block_component_system::{
+    BlockComponentSystem,
+    LiquidDefinition
+};
+
+use super::{
+    world::{
+        World,
+        CHUNK_WIDTH,
+        MIN_Y
+    },
+    lighting::set_block_lit,
+    tick_scheduler::{
+        TickScheduler,
+        BlockTick,
+        TickKind
+    },
+    world_event::{
+        WorldEvent,
+        WorldObserver
+    },
+    chunk_storage::{
+        section_index_to_pos,
+        SECTION_HEIGHT,
+        SECTION_SIZE
+    }
+};
+
+pub const SOURCE: u8 = 0;
+
+// liquid fed from above, the level bits are left empty
+pub const FALLING: u8 = 0x80;
+
+const LEVEL_MASK: u8 = 0x0F;
+
+// x+, x-, z+, z-
+const HORIZONTAL: [(i32, i32); 4] = [(1, 0), (-1, 0), (0, 1), (0, -1)];
+
+// x+, x-, y+, y-, z+, z-
+const NEIGHBORS: [(i32, i32, i32); 6] = [(1, 0, 0), (-1, 0, 0), (0, 1, 0), (0, -1, 0), (0, 0, 1), (0, 0, -1)];
+
+pub fn is_source(level: u8) -> bool {
+    level == SOURCE
+}
+
+pub fn is_falling(level: u8) -> bool {
+    level & FALLING != 0
+}
+
+// the level liquid spreads out from - falling liquid spreads like a source
+fn get_spread_level(level: u8) -> u8 {
+    if is_falling(level) {
+        SOURCE
+    } else {
+        level & LEVEL_MASK
+    }
+}
+
+/*
+how high the surface of the liquid in a block is, 0.0 to 1.0
+
+sources sit a little below the top of the block, flowing liquid thins out the further it gets
+*/
+pub fn get_liquid_height(level: u8, range: u8) -> f32 {
+    const FULL: f32 = 14.0 / 16.0;
+    const THINNEST: f32 = 2.0 / 16.0;
+
+    if is_source(level) || is_falling(level) {
+        return FULL;
+    }
+
+    let range: f32 = range as f32;
+    let level: f32 = (level & LEVEL_MASK) as f32;
+
+    (FULL * (range + 1.0 - level) / (range + 1.0)).max(THINNEST)
+}
+
+// everything the flow puts down goes in with its rotation byte, set_block leaves the old one behind
+fn place_block(world: &mut World, bcs: &BlockComponentSystem, x: i32, y: i32, z: i32, id: u32, rotation: u8) {
+    if set_block_lit(world, bcs, x, y, z, id) {
+        world.set_block_rotation(x, y, z, rotation);
+    }
+}
+
+/*
+the level the liquid at x,y,z should have going by what's around it, None if it should dry up
+
+only used on flowing liquid, sources never change on their own
+*/
+fn settle_level(world: &World, bcs: &BlockComponentSystem, liquid: &LiquidDefinition, id: u32, x: i32, y: i32, z: i32) -> Option<u8> {
+
+    if world.get_block(x, y + 1, z) == Some(id) {
+        return Some(FALLING);
+    }
+
+    let mut lowest: Option<u8> = None;
+    let mut sources: u8 = 0;
+
+    for (offset_x, offset_z) in HORIZONTAL {
+
+        let (neighbor_x, neighbor_z) = (x + offset_x, z + offset_z);
+
+        if world.get_block(neighbor_x, y, neighbor_z) != Some(id) {
+            continue;
+        }
+
+        let neighbor_level: u8 = world.get_block_rotation(neighbor_x, y, neighbor_z).unwrap();
+
+        if is_source(neighbor_level) {
+            sources += 1;
+        }
+
+        let spread_level: u8 = get_spread_level(neighbor_level);
+
+        lowest = Some(lowest.map_or(spread_level, |lowest| lowest.min(spread_level)));
+    }
+
+    // a new source needs something to stand on, solid ground or more of itself
+    if liquid.is_renewable() && sources >= 2 {
+        match world.get_block(x, y - 1, z) {
+            Some(below) if below == id && is_source(world.get_block_rotation(x, y - 1, z).unwrap()) => return Some(SOURCE),
+            Some(below) if below != 0 && !bcs.is_liquid(below) => return Some(SOURCE),
+            _ => (),
+        }
+    }
+
+    match lowest {
+        Some(lowest) if lowest < liquid.get_range() => Some(lowest + 1),
+        _ => None,
+    }
+}
+
+// turns the other liquids touching x,y,z into whatever this liquid makes of them
+fn interact(world: &mut World, bcs: &BlockComponentSystem, liquid: &LiquidDefinition, x: i32, y: i32, z: i32) {
+
+    for (offset_x, offset_y, offset_z) in NEIGHBORS {
+
+        let (neighbor_x, neighbor_y, neighbor_z) = (x + offset_x, y + offset_y, z + offset_z);
+
+        let neighbor_id: u32 = match world.get_block(neighbor_x, neighbor_y, neighbor_z) {
+            Some(neighbor_id) => neighbor_id,
+            None => continue,
+        };
+
+        if let Some(interaction) = liquid.get_interaction(neighbor_id) {
+            let source: bool = is_source(world.get_block_rotation(neighbor_x, neighbor_y, neighbor_z).unwrap());
+
+            // results are plain blocks, they go in unrotated
+            place_block(world, bcs, neighbor_x, neighbor_y, neighbor_z, interaction.get_result(source), 0);
+        }
+    }
+}
+
+// one step of the flow of the liquid at x,y,z - does nothing if there is no liquid there anymore
+pub fn flow_liquid(world: &mut World, bcs: &BlockComponentSystem, x: i32, y: i32, z: i32) {
+
+    let id: u32 = match world.get_block(x, y, z) {
+        Some(id) => id,
+        None => return,
+    };
+
+    let liquid: &LiquidDefinition = match bcs.get_liquid(id) {
+        Some(liquid) => liquid,
+        None => return,
+    };
+
+    let mut level: u8 = world.get_block_rotation(x, y, z).unwrap();
+
+    if !is_source(level) {
+        match settle_level(world, bcs, liquid, id, x, y, z) {
+            Some(new_level) => {
+                world.set_block_rotation(x, y, z, new_level);
+                level = new_level;
+            },
+            None => {
+                set_block_lit(world, bcs, x, y, z, 0);
+                return;
+            },
+        }
+    }
+
+    interact(world, bcs, liquid, x, y, z);
+
+    // down first, flowing liquid that can fall doesn't spread out
+    let falls: bool = match world.get_block(x, y - 1, z) {
+        Some(0) => {
+            place_block(world, bcs, x, y - 1, z, id, FALLING);
+            true
+        },
+        Some(below) if below == id => {
+            let below_level: u8 = world.get_block_rotation(x, y - 1, z).unwrap();
+            if !is_source(below_level) && !is_falling(below_level) {
+                world.set_block_rotation(x, y - 1, z, FALLING);
+            }
+            !is_source(below_level)
+        },
+        _ => false,
+    };
+
+    if falls && !is_source(level) {
+        return;
+    }
+
+    let next_level: u8 = get_spread_level(level) + 1;
+
+    if next_level > liquid.get_range() {
+        return;
+    }
+
+    for (offset_x, offset_z) in HORIZONTAL {
+
+        let (neighbor_x, neighbor_z) = (x + offset_x, z + offset_z);
+
+        match world.get_block(neighbor_x, y, neighbor_z) {
+            Some(0) => place_block(world, bcs, neighbor_x, y, neighbor_z, id, next_level),
+            Some(neighbor_id) if neighbor_id == id => {
+                let neighbor_level: u8 = world.get_block_rotation(neighbor_x, y, neighbor_z).unwrap();
+                if !is_source(neighbor_level) && !is_falling(neighbor_level) && neighbor_level > next_level {
+                    world.set_block_rotation(neighbor_x, y, neighbor_z, next_level);
+                }
+            },
+            _ => (),
+        }
+    }
+}
+
+// runs the flow of every liquid that has a scheduled update in ticks, the rest are left alone
+pub fn flow_liquids(world: &mut World, bcs: &BlockComponentSystem, ticks: &[BlockTick]) {
+    for tick in ticks {
+        if tick.get_kind() == TickKind::Scheduled && bcs.is_liquid(tick.get_id()) {
+            let (x, y, z) = tick.get_pos();
+            flow_liquid(world, bcs, x, y, z);
+        }
+    }
+}
+
+/*
+keeps liquids flowing - schedules an update for every liquid a change could set moving
+
+block changes wake the liquid in and right next to the block, region edits wake
+every liquid in the sections of the chunk that have any
+*/
+pub struct LiquidObserver<'a> {
+    bcs: &'a BlockComponentSystem,
+    tick_scheduler: &'a mut TickScheduler
+}
+
+impl<'a> LiquidObserver<'a> {
+    pub fn new(bcs: &'a BlockComponentSystem, tick_scheduler: &'a mut TickScheduler) -> Self {
+        Self {
+            bcs,
+            tick_scheduler
+        }
+    }
+
+    fn wake(&mut self, world: &World, x: i32, y: i32, z: i32) {
+        if let Some(liquid) = world.get_block(x, y, z).and_then(|id| self.bcs.get_liquid(id)) {
+            self.tick_scheduler.schedule(x, y, z, liquid.get_viscosity() as u64);
+        }
+    }
+
+    fn wake_around(&mut self, world: &World, x: i32, y: i32, z: i32) {
+        self.wake(world, x, y, z);
+
+        for (offset_x, offset_y, offset_z) in NEIGHBORS {
+            self.wake(world, x + offset_x, y + offset_y, z + offset_z);
+        }
+    }
+
+    fn wake_chunk(&mut self, world: &World, chunk_x: i32, chunk_z: i32) {
+
+        let blocks = match world.get_chunk_blocks(chunk_x, chunk_z) {
+            Some(blocks) => blocks,
+            None => return,
+        };
+
+        for section in 0..blocks.get_section_count() {
+
+            let section_blocks = blocks.get_section(section);
+
+            if !section_blocks.get_used_values().any(|id| self.bcs.is_liquid(id)) {
+                continue;
+            }
+
+            for section_index in 0..SECTION_SIZE {
+
+                let id: u32 = section_blocks.get(section_index);
+
+                if let Some(liquid) = self.bcs.get_liquid(id) {
+                    let (local_x, local_y, local_z) = section_index_to_pos(section_index);
+
+                    self.tick_scheduler.schedule(
+                        chunk_x * CHUNK_WIDTH + local_x as i32,
+                        MIN_Y + section as i32 * SECTION_HEIGHT + local_y as i32,
+                        chunk_z * CHUNK_WIDTH + local_z as i32,
+                        liquid.get_viscosity() as u64
+                    );
+                }
+            }
+        }
+    }
+}
+
+impl WorldObserver for LiquidObserver<'_> {
+    fn on_world_event(&mut self, world: &World, event: &WorldEvent) {
+        match *event {
+            WorldEvent::BlockSet { x, y, z, .. } |
+            WorldEvent::BlockRotated { x, y, z, .. } => self.wake_around(world, x, y, z),
+            WorldEvent::ChunkEdited { x, z } => self.wake_chunk(world, x, z),
+            _ => (),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::block_component_system::{
+        DrawType,
+        RotationType
+    };
+
+    use super::*;
+
+    // air, stone, water (range 3) and a water that never makes new sources
+    fn test_bcs() -> (BlockComponentSystem, u32, u32, u32) {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+
+        let stone: u32 = bcs.register_block(String::from("test"), String::from("stone"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+        let water: u32 = bcs.register_block(String::from("test"), String::from("water"), DrawType::Liquid, vec![], None, vec![], 0, RotationType::None);
+        let dry_water: u32 = bcs.register_block(String::from("test"), String::from("dry_water"), DrawType::Liquid, vec![], None, vec![], 0, RotationType::None);
+
+        bcs.set_liquid(water, LiquidDefinition::new(1, 3, true, vec![]));
+        bcs.set_liquid(dry_water, LiquidDefinition::new(1, 3, false, vec![]));
+
+        (bcs, stone, water, dry_water)
+    }
+
+    // a floor of stone at y 10 in chunk 0,0
+    fn test_world(stone: u32) -> World {
+        let mut world: World = World::initialize();
+        world.add_chunk(0, 0);
+
+        for x in 0..CHUNK_WIDTH {
+            for z in 0..CHUNK_WIDTH {
+                world.set_block(x, 10, z, stone);
+            }
+        }
+
+        world.take_events();
+
+        world
+    }
+
+    // lets the liquids wake up and flow for a while
+    fn run(world: &mut World, bcs: &BlockComponentSystem, tick_scheduler: &mut TickScheduler) {
+        for _ in 0..100 {
+            for event in world.take_events() {
+                LiquidObserver::new(bcs, tick_scheduler).on_world_event(world, &event);
+            }
+
+            let ticks: Vec<BlockTick> = tick_scheduler.advance(world, bcs);
+
+            flow_liquids(world, bcs, &ticks);
+        }
+    }
+
+    fn level(world: &World, x: i32, y: i32, z: i32) -> Option<u8> {
+        world.get_block_rotation(x, y, z)
+    }
+
+    #[test]
+    fn a_source_spreads_out_to_its_range_and_drains_when_removed() {
+        let (bcs, stone, water, _) = test_bcs();
+        let mut world: World = test_world(stone);
+        let mut tick_scheduler: TickScheduler = TickScheduler::new(0);
+
+        world.set_block(8, 11, 8, water);
+
+        run(&mut world, &bcs, &mut tick_scheduler);
+
+        for (x, expected) in [(8, 0), (9, 1), (10, 2), (11, 3)] {
+            assert_eq!(world.get_block(x, 11, 8), Some(water));
+            assert_eq!(level(&world, x, 11, 8), Some(expected));
+        }
+        assert_eq!(world.get_block(12, 11, 8), Some(0));
+        assert_eq!(level(&world, 9, 11, 9), Some(2));
+        assert_eq!(world.get_block(8, 12, 8), Some(0));
+
+        world.set_block(8, 11, 8, 0);
+
+        run(&mut world, &bcs, &mut tick_scheduler);
+
+        for x in 4..13 {
+            for z in 4..13 {
+                assert_eq!(world.get_block(x, 11, z), Some(0));
+            }
+        }
+        assert_eq!(tick_scheduler.get_number_of_scheduled(), 0);
+    }
+
+    #[test]
+    fn liquid_falls_before_it_spreads() {
+        let (bcs, stone, water, _) = test_bcs();
+        let mut world: World = test_world(stone);
+        let mut tick_scheduler: TickScheduler = TickScheduler::new(0);
+
+        // a source on a pillar, the water runs off its sides and down to the floor
+        world.set_block(8, 11, 8, stone);
+        world.set_block(8, 12, 8, water);
+
+        run(&mut world, &bcs, &mut tick_scheduler);
+
+        assert_eq!(level(&world, 9, 12, 8), Some(1));
+        assert_eq!(world.get_block(9, 11, 8), Some(water));
+        assert_eq!(level(&world, 9, 11, 8), Some(FALLING));
+        // landed falling water spreads out like a source
+        assert_eq!(level(&world, 10, 11, 8), Some(1));
+        // flowing water that can fall doesn't spread
+        assert_eq!(world.get_block(10, 12, 8), Some(0));
+    }
+
+    #[test]
+    fn flowing_liquid_between_two_sources_becomes_a_source() {
+        let (bcs, stone, water, dry_water) = test_bcs();
+
+        for (id, renewable) in [(water, true), (dry_water, false)] {
+            let mut world: World = test_world(stone);
+            let mut tick_scheduler: TickScheduler = TickScheduler::new(0);
+
+            // a trench three blocks long, with a source at both ends
+            for x in 0..CHUNK_WIDTH {
+                for z in 0..CHUNK_WIDTH {
+                    if z != 8 || !(7..=9).contains(&x) {
+                        world.set_block(x, 11, z, stone);
+                    }
+                }
+            }
+            world.set_block(7, 11, 8, id);
+            world.set_block(9, 11, 8, id);
+
+            run(&mut world, &bcs, &mut tick_scheduler);
+
+            assert_eq!(world.get_block(8, 11, 8), Some(id));
+            assert_eq!(level(&world, 8, 11, 8), Some(if renewable { SOURCE } else { 1 }));
+
+            // a new source stays when the ones that made it are gone
+            world.set_block(7, 11, 8, 0);
+            world.set_block(9, 11, 8, 0);
+
+            run(&mut world, &bcs, &mut tick_scheduler);
+
+            let middle: u32 = if renewable { id } else { 0 };
+            assert_eq!(world.get_block(8, 11, 8), Some(middle));
+        }
+    }
+}
@@ -1 +1,14 @@
 pub mod world;
+pub mod world_event;
+pub mod palette_storage;
+pub mod chunk_storage;
+pub mod world_save;
+pub mod world_descriptor;
+pub mod dimension;
+pub mod lighting;
+pub mod raycast;
+pub mod tick_scheduler;
+pub mod region_edit;
+pub mod schematic;pub mod liquid;
+pub mod falling_block;
+pub mod chunk_manager;
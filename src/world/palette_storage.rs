@@ -0,0 +1,310 @@
+/*
+palette compressed chunk storage
+
+instead of holding a full value per block, a chunk holds a small palette of
+the values it actually contains and a bit packed index into that palette per block
+
+a chunk that is a single value (all air, all stone) holds no per block data at all
+
+every palette entry keeps a count of how many blocks use it, an entry that drops to
+zero is reused by the next new value so edits don't grow the palette forever
+
+the index width grows 0 -> 1 -> 2 -> 4 -> 8 -> 16 -> 32 bits as new values appear,
+widths are powers of two so an index never straddles two words
+*/
+
+#[derive(Clone)]
+pub struct PaletteStorage<T> {
+    // how many blocks this holds
+    size: usize,
+
+    palette: Vec<T>,
+
+    // how many blocks point at each palette entry
+    counts: Vec<usize>,
+
+    // bits per packed palette index - 0 means every block is palette[0]
+    bits: u32,
+
+    data: Vec<u64>
+}
+
+impl<T: Copy + PartialEq> PaletteStorage<T> {
+
+    // a storage of size blocks that are all the same value
+    pub fn new(size: usize, value: T) -> Self {
+        Self {
+            size,
+            palette: vec![value],
+            counts: vec![size],
+            bits: 0,
+            data: Vec::new()
+        }
+    }
+
+    // packs an existing flat array, the palette only holds what's actually used
+    pub fn from_slice(values: &[T]) -> Self {
+
+        let mut storage: PaletteStorage<T> = PaletteStorage::new(values.len(), values[0]);
+
+        storage.counts[0] = 0;
+
+        let mut palette_indices: Vec<usize> = Vec::with_capacity(values.len());
+
+        for value in values.iter() {
+            let palette_index: usize = match storage.palette.iter().position(|test| test == value) {
+                Some(palette_index) => palette_index,
+                None => {
+                    storage.palette.push(*value);
+                    storage.counts.push(0);
+                    storage.palette.len() - 1
+                }
+            };
+            storage.counts[palette_index] += 1;
+            palette_indices.push(palette_index);
+        }
+
+        let bits: u32 = Self::bits_for(storage.palette.len());
+
+        storage.repack(bits, |index| palette_indices[index]);
+
+        storage
+    }
+
+    // the smallest power of two width that can index palette_length entries
+    fn bits_for(palette_length: usize) -> u32 {
+        let mut bits: u32 = 0;
+        while (1_usize << bits) < palette_length {
+            bits = if bits == 0 { 1 } else { bits * 2 };
+        }
+        bits
+    }
+
+    fn entries_per_word(&self) -> usize {
+        (64 / self.bits) as usize
+    }
+
+    fn mask(&self) -> u64 {
+        if self.bits == 64 {
+            u64::MAX
+        } else {
+            (1_u64 << self.bits) - 1
+        }
+    }
+
+    fn read_index(&self, index: usize) -> usize {
+        if self.bits == 0 {
+            return 0;
+        }
+        let per_word: usize = self.entries_per_word();
+        let shift: u32 = ((index % per_word) as u32) * self.bits;
+        ((self.data[index / per_word] >> shift) & self.mask()) as usize
+    }
+
+    fn write_index(&mut self, index: usize, palette_index: usize) {
+        if self.bits == 0 {
+            return;
+        }
+        let per_word: usize = self.entries_per_word();
+        let shift: u32 = ((index % per_word) as u32) * self.bits;
+        let mask: u64 = self.mask();
+        let word: &mut u64 = &mut self.data[index / per_word];
+        *word = (*word & !(mask << shift)) | ((palette_index as u64 & mask) << shift);
+    }
+
+    // changes the index width, old_index supplies the palette index each block had
+    fn repack<F: Fn(usize) -> usize>(&mut self, bits: u32, old_index: F) {
+
+        let old_indices: Vec<usize> = (0..self.size).map(old_index).collect();
+
+        self.bits = bits;
+
+        if bits == 0 {
+            self.data = Vec::new();
+            return;
+        }
+
+        let per_word: usize = self.entries_per_word();
+
+        self.data = vec![0; self.size.div_ceil(per_word)];
+
+        for (index, palette_index) in old_indices.into_iter().enumerate() {
+            self.write_index(index, palette_index);
+        }
+    }
+
+    pub fn get(&self, index: usize) -> T {
+        self.palette[self.read_index(index)]
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+
+        let old_palette_index: usize = self.read_index(index);
+
+        if self.palette[old_palette_index] == value {
+            return;
+        }
+
+        self.counts[old_palette_index] -= 1;
+
+        let palette_index: usize = match self.palette.iter().position(|test| *test == value) {
+            Some(palette_index) => palette_index,
+            None => {
+                // reuse an entry nothing points at anymore
+                match self.counts.iter().position(|count| *count == 0) {
+                    Some(free_index) => {
+                        self.palette[free_index] = value;
+                        free_index
+                    },
+                    None => {
+                        self.palette.push(value);
+                        self.counts.push(0);
+
+                        // out of room in the current width, grow it
+                        if self.palette.len() > (1_usize << self.bits) {
+                            let bits: u32 = Self::bits_for(self.palette.len());
+                            let old: PaletteStorage<T> = PaletteStorage {
+                                size: self.size,
+                                palette: Vec::new(),
+                                counts: Vec::new(),
+                                bits: self.bits,
+                                data: std::mem::take(&mut self.data)
+                            };
+                            self.repack(bits, |index| old.read_index(index));
+                        }
+
+                        self.palette.len() - 1
+                    }
+                }
+            }
+        };
+
+        self.counts[palette_index] += 1;
+
+        self.write_index(index, palette_index);
+    }
+
+    // sets every block to value, this drops back to the single value fast path
+    pub fn fill(&mut self, value: T) {
+        self.palette.clear();
+        self.palette.push(value);
+        self.counts.clear();
+        self.counts.push(self.size);
+        self.bits = 0;
+        self.data = Vec::new();
+    }
+
+    // the fast path - Some(value) if every block is the same value
+    pub fn get_single(&self) -> Option<T> {
+        let mut used = self.get_used_values();
+
+        match (used.next(), used.next()) {
+            (Some(value), None) => Some(value),
+            _ => None,
+        }
+    }
+
+    // every value at least one block holds
+    pub fn get_used_values(&self) -> impl Iterator<Item = T> + '_ {
+        self.palette
+            .iter()
+            .zip(self.counts.iter())
+            .filter(|(_, count)| **count > 0)
+            .map(|(value, _)| *value)
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    // unpacks into a flat array
+    pub fn to_vec(&self) -> Vec<T> {
+        (0..self.size).map(|index| self.get(index)).collect()
+    }
+
+    // rough heap usage in bytes, useful for checking how well chunks compress
+    pub fn get_memory_usage(&self) -> usize {
+        (self.palette.len() * (std::mem::size_of::<T>() + std::mem::size_of::<usize>())) + (self.data.len() * 8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SIZE: usize = 4096;
+
+    fn assert_matches(storage: &PaletteStorage<u32>, expected: &[u32]) {
+        assert_eq!(storage.to_vec(), expected);
+    }
+
+    #[test]
+    fn values_survive_every_width_growth() {
+        let mut storage: PaletteStorage<u32> = PaletteStorage::new(SIZE, 0);
+        let mut expected: Vec<u32> = vec![0; SIZE];
+
+        // palette length -> the width it needs
+        let widths: [(usize, u32); 4] = [(2, 1), (3, 2), (5, 4), (17, 8)];
+
+        for value in 1..17_u32 {
+            // spread each value over blocks in different words, some written twice
+            for index in (value as usize..SIZE).step_by(61 + value as usize) {
+                storage.set(index, value);
+                expected[index] = value;
+            }
+
+            for (palette_length, bits) in widths.iter() {
+                if value as usize + 1 == *palette_length {
+                    assert_eq!(storage.bits, *bits);
+                }
+            }
+
+            assert_matches(&storage, &expected);
+        }
+
+        assert_eq!(PaletteStorage::from_slice(&expected).to_vec(), expected);
+    }
+
+    #[test]
+    fn entries_nothing_uses_are_reused() {
+        let mut storage: PaletteStorage<u32> = PaletteStorage::new(SIZE, 0);
+
+        storage.set(10, 5);
+        storage.set(20, 6);
+        storage.set(10, 0);
+
+        assert_eq!(storage.get_used_values().collect::<Vec<u32>>(), vec![0, 6]);
+
+        // 7 takes the entry 5 left behind instead of growing the palette
+        storage.set(30, 7);
+
+        assert_eq!(storage.palette.len(), 3);
+        assert_eq!(storage.bits, 2);
+        assert_eq!((storage.get(10), storage.get(20), storage.get(30)), (0, 6, 7));
+    }
+
+    #[test]
+    fn fill_drops_back_to_a_single_value() {
+        let mut storage: PaletteStorage<u32> = PaletteStorage::new(SIZE, 0);
+
+        for index in 0..SIZE {
+            storage.set(index, (index % 20) as u32);
+        }
+
+        storage.fill(3);
+
+        assert_eq!(storage.bits, 0);
+        assert!(storage.data.is_empty());
+        assert_eq!(storage.get_single(), Some(3));
+        assert_matches(&storage, &vec![3; SIZE]);
+
+        storage.set(100, 4);
+
+        assert_eq!(storage.get_single(), None);
+        assert_eq!((storage.get(99), storage.get(100)), (3, 4));
+    }
+}
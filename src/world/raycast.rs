@@ -0,0 +1,260 @@
+/*
+voxel raycasting
+
+walks the ray block by block (a DDA, Amanatides and Woo) so no block along the line
+can be skipped no matter how thin the sliver of it the ray crosses
+
+blocks are the unit cubes from x to x + 1, the same space the camera moves in,
+chunk borders mean nothing here since the walk goes through World's global accessors
+*/
+
+use glam::{Vec3, IVec3};
+
+use super::world::World;
+
+pub struct RaycastHit {
+    // the block that was hit
+    position: IVec3,
+    // which face of it the ray went in through, zero if the ray started inside it
+    normal: IVec3,
+    id: u32,
+    // distance along the ray to the face it went in through
+    distance: f32
+}
+
+impl RaycastHit {
+    pub fn get_position(&self) -> IVec3 {
+        self.position
+    }
+
+    pub fn get_normal(&self) -> IVec3 {
+        self.normal
+    }
+
+    // the cell in front of the face that was hit - where a placed block goes
+    pub fn get_adjacent(&self) -> IVec3 {
+        self.position + self.normal
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn get_distance(&self) -> f32 {
+        self.distance
+    }
+}
+
+// the first non-air block along the ray within max_distance, unloaded chunks are looked through
+pub fn raycast(world: &World, origin: Vec3, direction: Vec3, max_distance: f32) -> Option<RaycastHit> {
+
+    if direction.length_squared() == 0.0 {
+        return None;
+    }
+
+    let direction: Vec3 = direction.normalize();
+
+    let mut cell: IVec3 = origin.floor().as_ivec3();
+
+    // the ray can start inside a block
+    if let Some(id) = solid_block(world, cell) {
+        return Some(RaycastHit {
+            position: cell,
+            normal: IVec3::ZERO,
+            id,
+            distance: 0.0
+        });
+    }
+
+    let step: IVec3 = IVec3::new(
+        axis_step(direction.x),
+        axis_step(direction.y),
+        axis_step(direction.z)
+    );
+
+    // how far along the ray it takes to cross a whole block on each axis
+    let t_delta: Vec3 = Vec3::new(
+        axis_delta(direction.x),
+        axis_delta(direction.y),
+        axis_delta(direction.z)
+    );
+
+    // how far along the ray the next block border on each axis is
+    let mut t_max: Vec3 = Vec3::new(
+        axis_first_border(origin.x, cell.x, direction.x),
+        axis_first_border(origin.y, cell.y, direction.y),
+        axis_first_border(origin.z, cell.z, direction.z)
+    );
+
+    loop {
+        // step over whichever border is closest
+        let (distance, normal): (f32, IVec3) = if t_max.x < t_max.y && t_max.x < t_max.z {
+            cell.x += step.x;
+            let distance: f32 = t_max.x;
+            t_max.x += t_delta.x;
+            (distance, IVec3::new(-step.x, 0, 0))
+        } else if t_max.y < t_max.z {
+            cell.y += step.y;
+            let distance: f32 = t_max.y;
+            t_max.y += t_delta.y;
+            (distance, IVec3::new(0, -step.y, 0))
+        } else {
+            cell.z += step.z;
+            let distance: f32 = t_max.z;
+            t_max.z += t_delta.z;
+            (distance, IVec3::new(0, 0, -step.z))
+        };
+
+        if distance > max_distance {
+            return None;
+        }
+
+        if let Some(id) = solid_block(world, cell) {
+            return Some(RaycastHit {
+                position: cell,
+                normal,
+                id,
+                distance
+            });
+        }
+    }
+}
+
+// the block id if there is something other than air there
+fn solid_block(world: &World, cell: IVec3) -> Option<u32> {
+    match world.get_block(cell.x, cell.y, cell.z) {
+        Some(id) if id != 0 => Some(id),
+        _ => None,
+    }
+}
+
+fn axis_step(direction: f32) -> i32 {
+    if direction > 0.0 {
+        1
+    } else if direction < 0.0 {
+        -1
+    } else {
+        0
+    }
+}
+
+fn axis_delta(direction: f32) -> f32 {
+    if direction == 0.0 {
+        f32::INFINITY
+    } else {
+        (1.0 / direction).abs()
+    }
+}
+
+fn axis_first_border(origin: f32, cell: i32, direction: f32) -> f32 {
+    if direction > 0.0 {
+        ((cell + 1) as f32 - origin) / direction
+    } else if direction < 0.0 {
+        (origin - cell as f32) / -direction
+    } else {
+        f32::INFINITY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::{Vec3, IVec3};
+
+    use crate::world::world::World;
+
+    use super::raycast;
+
+    // an empty 3x3 area of chunks around the origin
+    fn empty_world() -> World {
+        let mut world: World = World::initialize();
+        for x in -1..=1 {
+            for z in -1..=1 {
+                world.add_chunk(x, z);
+            }
+        }
+        world
+    }
+
+    fn place(world: &mut World, x: i32, y: i32, z: i32) {
+        assert!(world.set_block(x, y, z, 1));
+    }
+
+    #[test]
+    fn hits_the_ground_below() {
+        let mut world: World = empty_world();
+        place(&mut world, 0, 5, 0);
+
+        let hit = raycast(&world, Vec3::new(0.5, 10.5, 0.5), Vec3::new(0.0, -1.0, 0.0), 20.0).unwrap();
+
+        assert_eq!(hit.get_position(), IVec3::new(0, 5, 0));
+        assert_eq!(hit.get_normal(), IVec3::new(0, 1, 0));
+        assert_eq!(hit.get_adjacent(), IVec3::new(0, 6, 0));
+        assert_eq!(hit.get_id(), 1);
+        assert!((hit.get_distance() - 4.5).abs() < 0.0001);
+    }
+
+    #[test]
+    fn crosses_chunk_borders() {
+        let mut world: World = empty_world();
+        place(&mut world, 17, 5, 0);
+
+        let hit = raycast(&world, Vec3::new(15.5, 5.5, 0.5), Vec3::new(1.0, 0.0, 0.0), 20.0).unwrap();
+
+        assert_eq!(hit.get_position(), IVec3::new(17, 5, 0));
+        assert_eq!(hit.get_normal(), IVec3::new(-1, 0, 0));
+        assert_eq!(hit.get_adjacent(), IVec3::new(16, 5, 0));
+    }
+
+    #[test]
+    fn works_in_negative_coordinates() {
+        let mut world: World = empty_world();
+        place(&mut world, -1, 5, -4);
+
+        let hit = raycast(&world, Vec3::new(-0.5, 5.5, -0.5), Vec3::new(0.0, 0.0, -1.0), 20.0).unwrap();
+
+        assert_eq!(hit.get_position(), IVec3::new(-1, 5, -4));
+        assert_eq!(hit.get_normal(), IVec3::new(0, 0, 1));
+        assert_eq!(hit.get_adjacent(), IVec3::new(-1, 5, -3));
+    }
+
+    #[test]
+    fn diagonal_ray_hits_the_right_face() {
+        let mut world: World = empty_world();
+        for y in 0..20 {
+            place(&mut world, 4, y, 0);
+        }
+
+        // reaches x = 4 at y = 5.5 + (3.5 * 0.5) = 7.25
+        let hit = raycast(&world, Vec3::new(0.5, 5.5, 0.5), Vec3::new(1.0, 0.5, 0.0), 20.0).unwrap();
+
+        assert_eq!(hit.get_position(), IVec3::new(4, 7, 0));
+        assert_eq!(hit.get_normal(), IVec3::new(-1, 0, 0));
+    }
+
+    #[test]
+    fn stops_at_max_distance() {
+        let mut world: World = empty_world();
+        place(&mut world, 0, 5, 0);
+
+        assert!(raycast(&world, Vec3::new(0.5, 10.5, 0.5), Vec3::new(0.0, -1.0, 0.0), 4.0).is_none());
+    }
+
+    #[test]
+    fn starting_inside_a_block() {
+        let mut world: World = empty_world();
+        place(&mut world, 2, 3, 2);
+
+        let hit = raycast(&world, Vec3::new(2.5, 3.5, 2.5), Vec3::new(1.0, 0.0, 0.0), 10.0).unwrap();
+
+        assert_eq!(hit.get_position(), IVec3::new(2, 3, 2));
+        assert_eq!(hit.get_normal(), IVec3::ZERO);
+        assert_eq!(hit.get_distance(), 0.0);
+    }
+
+    #[test]
+    fn no_direction_hits_nothing() {
+        let world: World = empty_world();
+
+        assert!(raycast(&world, Vec3::new(0.5, 10.5, 0.5), Vec3::ZERO, 10.0).is_none());
+    }
+}
@@ -0,0 +1,629 @@
+/*
+bulk edits over a box of world coordinates
+
+everything runs a chunk at a time straight on the chunk storage instead of going
+through set_block, so a big fill doesn't relight or send an event per block
+
+once the blocks are in, the edited chunks get their heightmaps rebuilt and a ChunkEdited,
+the edited chunks and all 8 of their neighbors are relit (light can't reach further than that,
+a light in the corner of a chunk spills into the diagonal one too)
+and each of them gets a ChunkRelit once
+
+chunks that aren't loaded are skipped, copying from one reads as air
+*/
+
+use glam::IVec3;
+
+use crate::{
+    blocks::{
+        block_component_system::{
+            BlockComponentSystem,
+            RotationType
+        },
+        block_rotation::{
+            rotate,
+            transform_rotation
+        }
+    }
+};
+
+use super::{
+    chunk_storage::ChunkStorage,
+    lighting::relight_chunks,
+    world_event::WorldEvent,
+    world::{
+        World,
+        CHUNK_WIDTH,
+        MIN_Y,
+        MAX_Y,
+        pos_to_index
+    }
+};
+
+// an axis aligned box of blocks, both corners are inside it
+#[derive(Clone, Copy)]
+pub struct Selection {
+    min: IVec3,
+    max: IVec3
+}
+
+impl Selection {
+    // any two opposite corners, cut down to the part inside the world's height - None if none of it is
+    pub fn new(corner_1: IVec3, corner_2: IVec3) -> Option<Self> {
+        let mut min: IVec3 = corner_1.min(corner_2);
+        let mut max: IVec3 = corner_1.max(corner_2);
+
+        if max.y < MIN_Y || min.y > MAX_Y {
+            return None;
+        }
+
+        min.y = min.y.max(MIN_Y);
+        max.y = max.y.min(MAX_Y);
+
+        Some(Self {
+            min,
+            max
+        })
+    }
+
+    pub fn get_min(&self) -> IVec3 {
+        self.min
+    }
+
+    pub fn get_max(&self) -> IVec3 {
+        self.max
+    }
+
+    // how many blocks across on each axis
+    pub fn get_size(&self) -> IVec3 {
+        self.max - self.min + IVec3::ONE
+    }
+}
+
+// how a clipboard is turned before it is pasted - mirroring happens first, then the turns
+#[derive(Clone, Copy, Default)]
+pub struct PasteTransform {
+    // quarter turns around +y, z+ swings to x+ like a block facing does
+    turns: u8,
+    mirror_x: bool,
+    mirror_z: bool
+}
+
+impl PasteTransform {
+    pub fn new(turns: u8, mirror_x: bool, mirror_z: bool) -> Self {
+        Self {
+            turns: turns % 4,
+            mirror_x,
+            mirror_z
+        }
+    }
+
+    // carries a direction through the mirror and the turns
+    fn apply(&self, direction: [i32; 3]) -> [i32; 3] {
+        let [mut x, y, mut z] = direction;
+        if self.mirror_x {
+            x = -x;
+        }
+        if self.mirror_z {
+            z = -z;
+        }
+        rotate(self.turns, [x, y, z])
+    }
+}
+
+// a copied box of blocks, laid out x then z then y
+pub struct Clipboard {
+    size: IVec3,
+    block: Vec<u32>,
+    rotation: Vec<u8>
+}
+
+impl Clipboard {
+
+    // block and rotation are laid out x then z then y and must both hold size.x * size.y * size.z values
+    pub fn new(size: IVec3, block: Vec<u32>, rotation: Vec<u8>) -> Self {
+        assert_eq!(block.len(), (size.x * size.y * size.z) as usize, "CLIPBOARD BLOCK DATA DOES NOT MATCH ITS SIZE!");
+        assert_eq!(rotation.len(), block.len(), "CLIPBOARD ROTATION DATA DOES NOT MATCH ITS SIZE!");
+
+        Self {
+            size,
+            block,
+            rotation
+        }
+    }
+
+    fn index(size: IVec3, pos: IVec3) -> usize {
+        (((pos.y * size.z) + pos.z) * size.x + pos.x) as usize
+    }
+
+    pub fn get_blocks(&self) -> &Vec<u32> {
+        &self.block
+    }
+
+    pub fn get_rotations(&self) -> &Vec<u8> {
+        &self.rotation
+    }
+
+    pub fn get_size(&self) -> IVec3 {
+        self.size
+    }
+
+    // (id, rotation) at a position inside the clipboard
+    pub fn get(&self, pos: IVec3) -> (u32, u8) {
+        let index: usize = Self::index(self.size, pos);
+        (self.block[index], self.rotation[index])
+    }
+
+    // a copy of this clipboard turned and mirrored, directional blocks turn with it
+    pub fn transformed(&self, bcs: &BlockComponentSystem, transform: &PasteTransform) -> Clipboard {
+
+        let size: IVec3 = if transform.turns % 2 == 1 {
+            IVec3::new(self.size.z, self.size.y, self.size.x)
+        } else {
+            self.size
+        };
+
+        let volume: usize = (size.x * size.y * size.z) as usize;
+
+        let mut result: Clipboard = Clipboard {
+            size,
+            block: vec![0; volume],
+            rotation: vec![0; volume]
+        };
+
+        // the far corner of the box, positions are turned around the box center
+        let far: IVec3 = self.size - IVec3::ONE;
+
+        for y in 0..self.size.y {
+            for z in 0..self.size.z {
+                for x in 0..self.size.x {
+
+                    let (id, rotation) = self.get(IVec3::new(x, y, z));
+
+                    // doubled so the box center lands on whole numbers
+                    let centered: [i32; 3] = [(2 * x) - far.x, (2 * y) - far.y, (2 * z) - far.z];
+
+                    let turned: [i32; 3] = transform.apply(centered);
+
+                    let new_far: IVec3 = size - IVec3::ONE;
+
+                    let new_pos: IVec3 = IVec3::new(
+                        (turned[0] + new_far.x) / 2,
+                        (turned[1] + new_far.y) / 2,
+                        (turned[2] + new_far.z) / 2
+                    );
+
+                    let new_rotation: u8 = match bcs.get_rotation_type(id) {
+                        RotationType::None => rotation,
+                        _ => transform_rotation(rotation, |direction| transform.apply(direction)),
+                    };
+
+                    let index: usize = Self::index(size, new_pos);
+                    result.block[index] = id;
+                    result.rotation[index] = new_rotation;
+                }
+            }
+        }
+
+        result
+    }
+}
+
+/*
+runs edit_block over every loaded block in the selection, chunk by chunk
+
+edit_block gets the global position and the current (id, rotation), and hands back
+what should be there instead or None to leave it - returns how many blocks changed
+*/
+fn edit<F: FnMut(IVec3, u32, u8) -> Option<(u32, u8)>>(
+    world: &mut World,
+    bcs: &BlockComponentSystem,
+    selection: &Selection,
+    mut edit_block: F
+) -> usize {
+
+    let min: IVec3 = selection.get_min();
+    let max: IVec3 = selection.get_max();
+
+    let mut changed_blocks: usize = 0;
+
+    let mut edited_chunks: Vec<(i32, i32)> = Vec::new();
+
+    for chunk_x in min.x.div_euclid(CHUNK_WIDTH)..=max.x.div_euclid(CHUNK_WIDTH) {
+        for chunk_z in min.z.div_euclid(CHUNK_WIDTH)..=max.z.div_euclid(CHUNK_WIDTH) {
+
+            let (block, rotation) = match world.get_chunk_blocks_and_rotation_mut(chunk_x, chunk_z) {
+                Some(chunk_data) => chunk_data,
+                None => continue,
+            };
+
+            let base_x: i32 = chunk_x * CHUNK_WIDTH;
+            let base_z: i32 = chunk_z * CHUNK_WIDTH;
+
+            let mut chunk_changed: bool = false;
+
+            // the part of the selection inside this chunk
+            for x in min.x.max(base_x)..=max.x.min(base_x + CHUNK_WIDTH - 1) {
+                for z in min.z.max(base_z)..=max.z.min(base_z + CHUNK_WIDTH - 1) {
+                    for y in min.y..=max.y {
+
+                        let index: usize = pos_to_index((x - base_x) as usize, (y - MIN_Y) as usize, (z - base_z) as usize);
+
+                        let old_id: u32 = block.get(index);
+                        let old_rotation: u8 = rotation.get(index);
+
+                        if let Some((new_id, new_rotation)) = edit_block(IVec3::new(x, y, z), old_id, old_rotation) {
+                            if new_id != old_id || new_rotation != old_rotation {
+                                block.set(index, new_id);
+                                rotation.set(index, new_rotation);
+                                changed_blocks += 1;
+                                chunk_changed = true;
+                            }
+                        }
+                    }
+                }
+            }
+
+            if chunk_changed {
+                world.set_chunk_modified(chunk_x, chunk_z);
+                world.recalculate_heightmap(chunk_x, chunk_z);
+                world.emit(WorldEvent::ChunkEdited { x: chunk_x, z: chunk_z });
+                edited_chunks.push((chunk_x, chunk_z));
+            }
+        }
+    }
+
+    // the edited chunks and every loaded chunk touching them, corners included, each one only once
+    let mut touched_chunks: Vec<(i32, i32)> = Vec::new();
+
+    for (chunk_x, chunk_z) in edited_chunks.iter() {
+        for (offset_x, offset_z) in [(0, 0), (1, 0), (-1, 0), (0, 1), (0, -1), (1, 1), (1, -1), (-1, 1), (-1, -1)] {
+            let touched: (i32, i32) = (chunk_x + offset_x, chunk_z + offset_z);
+            if world.chunk_exists(touched.0, touched.1) && !touched_chunks.contains(&touched) {
+                touched_chunks.push(touched);
+            }
+        }
+    }
+
+    relight_chunks(world, bcs, &touched_chunks);
+
+    changed_blocks
+}
+
+// sets every block in the selection to id - returns how many blocks changed
+pub fn fill(world: &mut World, bcs: &BlockComponentSystem, selection: &Selection, id: u32) -> usize {
+    edit(world, bcs, selection, |_, _, _| Some((id, 0)))
+}
+
+// swaps every from block in the selection for to, facings are kept - returns how many blocks changed
+pub fn replace(world: &mut World, bcs: &BlockComponentSystem, selection: &Selection, from: u32, to: u32) -> usize {
+    edit(world, bcs, selection, |_, id, rotation| {
+        if id == from {
+            Some((to, rotation))
+        } else {
+            None
+        }
+    })
+}
+
+// turns the selection into air - returns how many blocks changed
+pub fn clear(world: &mut World, bcs: &BlockComponentSystem, selection: &Selection) -> usize {
+    fill(world, bcs, selection, 0)
+}
+
+// copies the selection into a clipboard, chunk by chunk
+pub fn copy(world: &World, selection: &Selection) -> Clipboard {
+
+    let min: IVec3 = selection.get_min();
+    let max: IVec3 = selection.get_max();
+    let size: IVec3 = selection.get_size();
+
+    let volume: usize = (size.x * size.y * size.z) as usize;
+
+    let mut clipboard: Clipboard = Clipboard {
+        size,
+        block: vec![0; volume],
+        rotation: vec![0; volume]
+    };
+
+    for chunk_x in min.x.div_euclid(CHUNK_WIDTH)..=max.x.div_euclid(CHUNK_WIDTH) {
+        for chunk_z in min.z.div_euclid(CHUNK_WIDTH)..=max.z.div_euclid(CHUNK_WIDTH) {
+
+            let (block, rotation): (&ChunkStorage<u32>, &ChunkStorage<u8>) = match (
+                world.get_chunk_blocks(chunk_x, chunk_z),
+                world.get_chunk_rotation(chunk_x, chunk_z)
+            ) {
+                (Some(block), Some(rotation)) => (block, rotation),
+                _ => continue,
+            };
+
+            let base_x: i32 = chunk_x * CHUNK_WIDTH;
+            let base_z: i32 = chunk_z * CHUNK_WIDTH;
+
+            for x in min.x.max(base_x)..=max.x.min(base_x + CHUNK_WIDTH - 1) {
+                for z in min.z.max(base_z)..=max.z.min(base_z + CHUNK_WIDTH - 1) {
+                    for y in min.y..=max.y {
+
+                        let index: usize = pos_to_index((x - base_x) as usize, (y - MIN_Y) as usize, (z - base_z) as usize);
+
+                        let clipboard_index: usize = Clipboard::index(size, IVec3::new(x, y, z) - min);
+
+                        clipboard.block[clipboard_index] = block.get(index);
+                        clipboard.rotation[clipboard_index] = rotation.get(index);
+                    }
+                }
+            }
+        }
+    }
+
+    clipboard
+}
+
+/*
+pastes the clipboard with its lowest corner at origin, turned and mirrored by transform
+
+air in the clipboard is pasted too, the box ends up exactly like the copy - returns how many blocks changed
+*/
+pub fn paste(
+    world: &mut World,
+    bcs: &BlockComponentSystem,
+    clipboard: &Clipboard,
+    origin: IVec3,
+    transform: &PasteTransform
+) -> usize {
+
+    let transformed: Clipboard = clipboard.transformed(bcs, transform);
+
+    // the part of the clipboard that ends up above or below the world is cut off
+    let selection: Selection = match Selection::new(origin, origin + transformed.get_size() - IVec3::ONE) {
+        Some(selection) => selection,
+        None => return 0,
+    };
+
+    edit(world, bcs, &selection, |pos, _, _| Some(transformed.get(pos - origin)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        blocks::{
+            block_component_system::DrawType,
+            block_rotation::FACE_DIRECTIONS
+        },
+        world::lighting::{
+            light_chunk,
+            get_block_light
+        }
+    };
+
+    use super::*;
+
+    // air, stone and dirt
+    fn test_bcs() -> (BlockComponentSystem, u32, u32) {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+
+        let stone: u32 = bcs.register_block(String::from("test"), String::from("stone"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+        let dirt: u32 = bcs.register_block(String::from("test"), String::from("dirt"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+
+        (bcs, stone, dirt)
+    }
+
+    // chunks 0 to 2 along x
+    fn test_world() -> World {
+        let mut world: World = World::initialize();
+
+        for chunk_x in 0..3 {
+            world.add_chunk(chunk_x, 0);
+        }
+
+        world
+    }
+
+    fn selection(corner_1: (i32, i32, i32), corner_2: (i32, i32, i32)) -> Selection {
+        Selection::new(IVec3::from(corner_1), IVec3::from(corner_2)).unwrap()
+    }
+
+    // every block of the world in the box
+    fn blocks_in(world: &World, min: (i32, i32, i32), max: (i32, i32, i32)) -> Vec<u32> {
+        let mut blocks: Vec<u32> = Vec::new();
+        for x in min.0..=max.0 {
+            for y in min.1..=max.1 {
+                for z in min.2..=max.2 {
+                    blocks.push(world.get_block(x, y, z).unwrap());
+                }
+            }
+        }
+        blocks
+    }
+
+    #[test]
+    fn selections_are_cut_down_to_the_world_height() {
+        assert!(Selection::new(IVec3::new(0, MAX_Y + 1, 0), IVec3::new(4, MAX_Y + 20, 4)).is_none());
+        assert!(Selection::new(IVec3::new(0, MIN_Y - 20, 0), IVec3::new(4, MIN_Y - 1, 4)).is_none());
+
+        let partly_above: Selection = selection((0, MAX_Y - 2, 0), (4, MAX_Y + 20, 4));
+
+        assert_eq!(partly_above.get_min().y, MAX_Y - 2);
+        assert_eq!(partly_above.get_max().y, MAX_Y);
+    }
+
+    #[test]
+    fn fill_replace_and_clear_work_across_a_chunk_border() {
+        let (bcs, stone, dirt) = test_bcs();
+        let mut world: World = test_world();
+
+        let border: Selection = selection((14, 10, 2), (17, 12, 3));
+
+        assert_eq!(fill(&mut world, &bcs, &border, stone), 24);
+        assert_eq!(blocks_in(&world, (14, 10, 2), (17, 12, 3)), vec![stone; 24]);
+        assert_eq!(blocks_in(&world, (13, 10, 2), (13, 12, 3)), vec![0; 6]);
+        assert_eq!(blocks_in(&world, (18, 10, 2), (18, 12, 3)), vec![0; 6]);
+
+        // only the stone turns to dirt, the air around it stays
+        assert_eq!(replace(&mut world, &bcs, &selection((10, 10, 2), (16, 10, 3)), stone, dirt), 6);
+        assert_eq!(blocks_in(&world, (14, 10, 2), (16, 10, 3)), vec![dirt; 6]);
+        assert_eq!(blocks_in(&world, (17, 10, 2), (17, 10, 3)), vec![stone; 2]);
+        assert_eq!(blocks_in(&world, (10, 10, 2), (13, 10, 3)), vec![0; 8]);
+
+        assert_eq!(clear(&mut world, &bcs, &border), 24);
+        assert_eq!(blocks_in(&world, (14, 10, 2), (17, 12, 3)), vec![0; 24]);
+    }
+
+    #[test]
+    fn copy_and_paste_work_across_a_chunk_border() {
+        let (bcs, stone, dirt) = test_bcs();
+        let mut world: World = test_world();
+
+        world.set_block(14, 10, 2, stone);
+        world.set_block(17, 11, 3, dirt);
+
+        let clipboard: Clipboard = copy(&world, &selection((14, 10, 2), (17, 11, 3)));
+
+        assert_eq!(clipboard.get_size(), IVec3::new(4, 2, 2));
+
+        // lands over the border between chunks 1 and 2
+        paste(&mut world, &bcs, &clipboard, IVec3::new(30, 20, 5), &PasteTransform::default());
+
+        assert_eq!(blocks_in(&world, (30, 20, 5), (33, 21, 6)), blocks_in(&world, (14, 10, 2), (17, 11, 3)));
+        assert_eq!(world.get_block(30, 20, 5), Some(stone));
+        assert_eq!(world.get_block(33, 21, 6), Some(dirt));
+    }
+
+    #[test]
+    fn pastes_past_the_world_height_only_change_blocks_inside_it() {
+        let (bcs, stone, _) = test_bcs();
+        let mut world: World = test_world();
+
+        let clipboard: Clipboard = Clipboard::new(IVec3::new(2, 3, 2), vec![stone; 12], vec![0; 12]);
+
+        // the top layer would be above the world
+        assert_eq!(paste(&mut world, &bcs, &clipboard, IVec3::new(15, MAX_Y - 1, 0), &PasteTransform::default()), 8);
+        assert_eq!(blocks_in(&world, (15, MAX_Y - 1, 0), (16, MAX_Y, 1)), vec![stone; 8]);
+
+        // entirely above or below the world does nothing at all
+        assert_eq!(paste(&mut world, &bcs, &clipboard, IVec3::new(0, MAX_Y + 1, 0), &PasteTransform::default()), 0);
+        assert_eq!(paste(&mut world, &bcs, &clipboard, IVec3::new(0, MIN_Y - 3, 0), &PasteTransform::default()), 0);
+        assert_eq!(blocks_in(&world, (0, MIN_Y, 0), (1, MIN_Y + 2, 1)), vec![0; 12]);
+        assert_eq!(blocks_in(&world, (0, MAX_Y - 2, 0), (1, MAX_Y, 1)), vec![0; 12]);
+    }
+
+    #[test]
+    fn clearing_a_corner_light_darkens_the_diagonal_chunk() {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+        let torch: u32 = bcs.register_block(String::from("test"), String::from("torch"), DrawType::BlockBox, vec![], None, vec![], 14, RotationType::None);
+
+        let mut world: World = World::initialize();
+        for (chunk_x, chunk_z) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            world.add_chunk(chunk_x, chunk_z);
+        }
+        for (chunk_x, chunk_z) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+            light_chunk(&mut world, &bcs, chunk_x, chunk_z);
+        }
+
+        let corner: Selection = selection((15, 20, 15), (15, 20, 15));
+
+        fill(&mut world, &bcs, &corner, torch);
+        assert_eq!(get_block_light(world.get_block_light(16, 20, 16).unwrap()), 12);
+
+        // only chunk 0,0 is edited, 1,1 only touches it at the corner
+        clear(&mut world, &bcs, &corner);
+
+        for (x, y, z) in [(15, 20, 15), (16, 20, 15), (15, 20, 16), (16, 20, 16), (17, 21, 17)] {
+            assert_eq!(get_block_light(world.get_block_light(x, y, z).unwrap()), 0, "{} {} {}", x, y, z);
+        }
+    }
+
+    #[test]
+    fn paste_transforms_wrap_their_turns_and_mirror_before_turning() {
+        assert_eq!(PasteTransform::new(5, false, false).apply([0, 0, 1]), [1, 0, 0]);
+        assert_eq!(PasteTransform::new(4, false, false).apply([1, 2, 3]), [1, 2, 3]);
+
+        for turns in 0..4 {
+            let transform: PasteTransform = PasteTransform::new(turns, false, false);
+            assert_eq!(transform.apply([0, 1, 0]), [0, 1, 0]);
+            assert_eq!(transform.apply([0, 0, 1]), FACE_DIRECTIONS[[2, 4, 3, 5][turns as usize]]);
+        }
+
+        assert_eq!(PasteTransform::new(0, true, false).apply([1, 0, 1]), [-1, 0, 1]);
+        assert_eq!(PasteTransform::new(0, false, true).apply([1, 0, 1]), [1, 0, -1]);
+
+        // x+ mirrors to x- and then turns to z+, turning first would have given z-
+        assert_eq!(PasteTransform::new(1, true, false).apply([1, 0, 0]), [0, 0, 1]);
+    }
+
+    #[test]
+    fn rotations_follow_the_transform() {
+        let turn: PasteTransform = PasteTransform::new(1, false, false);
+
+        // a furnace facing x+ turns to face z-
+        assert_eq!(transform_rotation(1, |direction| turn.apply(direction)), 2);
+
+        // a log lying along x turns to lie along z, its front swings from z+ to x+
+        let log: u8 = transform_rotation(16, |direction| turn.apply(direction));
+        assert_eq!(rotate(log, FACE_DIRECTIONS[0]), [0, 0, -1]);
+        assert_eq!(rotate(log, FACE_DIRECTIONS[2]), [1, 0, 0]);
+
+        // mirroring across the block's own front leaves it alone
+        let mirror: PasteTransform = PasteTransform::new(0, true, false);
+        assert_eq!(transform_rotation(0, |direction| mirror.apply(direction)), 0);
+        assert_eq!(transform_rotation(1, |direction| mirror.apply(direction)), 3);
+    }
+
+    #[test]
+    fn pasted_clipboards_turn_and_mirror_their_blocks_and_facings() {
+        let (mut bcs, stone, dirt) = test_bcs();
+        let furnace: u32 = bcs.register_block(String::from("test"), String::from("furnace"), DrawType::Normal, vec![], None, vec![], 0, RotationType::Horizontal);
+        let log: u32 = bcs.register_block(String::from("test"), String::from("log"), DrawType::Normal, vec![], None, vec![], 0, RotationType::Full);
+
+        /*
+        3 wide, 1 tall, 2 deep - seen from above with x to the right and z down
+
+        stone  air  dirt
+        furnace(x+)  log(along x, front z+)  air
+        */
+        let mut blocks: Vec<u32> = vec![0; 6];
+        let mut rotations: Vec<u8> = vec![0; 6];
+        for (x, z, id, rotation) in [(0, 0, stone, 0), (2, 0, dirt, 0), (0, 1, furnace, 1), (1, 1, log, 16)] {
+            blocks[Clipboard::index(IVec3::new(3, 1, 2), IVec3::new(x, 0, z))] = id;
+            rotations[Clipboard::index(IVec3::new(3, 1, 2), IVec3::new(x, 0, z))] = rotation;
+        }
+        let clipboard: Clipboard = Clipboard::new(IVec3::new(3, 1, 2), blocks, rotations);
+
+        // (transform, pasted size, stone, dirt, furnace, furnace rotation, log, log top, log front)
+        let cases = [
+            (PasteTransform::new(0, false, false), (3, 2), (0, 0), (2, 0), (0, 1), 1, (1, 1), [1, 0, 0], [0, 0, 1]),
+            (PasteTransform::new(1, false, false), (2, 3), (0, 2), (0, 0), (1, 2), 2, (1, 1), [0, 0, -1], [1, 0, 0]),
+            (PasteTransform::new(2, false, false), (3, 2), (2, 1), (0, 1), (2, 0), 3, (1, 0), [-1, 0, 0], [0, 0, -1]),
+            (PasteTransform::new(3, false, false), (2, 3), (1, 0), (1, 2), (0, 0), 0, (0, 1), [0, 0, 1], [-1, 0, 0]),
+            (PasteTransform::new(0, true, false), (3, 2), (2, 0), (0, 0), (2, 1), 3, (1, 1), [-1, 0, 0], [0, 0, 1]),
+            (PasteTransform::new(0, false, true), (3, 2), (0, 1), (2, 1), (0, 0), 1, (1, 0), [1, 0, 0], [0, 0, -1]),
+        ];
+
+        for (case, (transform, size, stone_pos, dirt_pos, furnace_pos, furnace_rotation, log_pos, log_top, log_front)) in cases.into_iter().enumerate() {
+
+            let transformed: Clipboard = clipboard.transformed(&bcs, &transform);
+            assert_eq!(transformed.get_size(), IVec3::new(size.0, 1, size.1), "case {}", case);
+            assert_eq!(transformed.get_blocks().iter().filter(|id| **id != 0).count(), 4, "case {}", case);
+
+            // pasted over the border between chunks 0 and 1
+            let mut world: World = test_world();
+            let origin: IVec3 = IVec3::new(15, 30, 5);
+
+            assert_eq!(paste(&mut world, &bcs, &clipboard, origin, &transform), 4, "case {}", case);
+
+            let at = |(x, z): (i32, i32)| -> (u32, u8) {
+                let pos: IVec3 = origin + IVec3::new(x, 0, z);
+                (world.get_block(pos.x, pos.y, pos.z).unwrap(), world.get_block_rotation(pos.x, pos.y, pos.z).unwrap())
+            };
+
+            assert_eq!(at(stone_pos), (stone, 0), "case {}", case);
+            assert_eq!(at(dirt_pos), (dirt, 0), "case {}", case);
+            assert_eq!(at(furnace_pos), (furnace, furnace_rotation), "case {}", case);
+
+            let (id, rotation) = at(log_pos);
+            assert_eq!(id, log, "case {}", case);
+            assert_eq!(rotate(rotation, FACE_DIRECTIONS[0]), log_top, "case {}", case);
+            assert_eq!(rotate(rotation, FACE_DIRECTIONS[2]), log_front, "case {}", case);
+        }
+    }
+}
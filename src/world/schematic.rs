@@ -0,0 +1,297 @@
+/*
+schematic files - a saved clipboard that can be shared between worlds and mod sets
+
+blocks are stored by name, a schematic made before the mod set changed still
+loads with every block it can still find, anything unknown comes back as air
+
+schematic file:
+    magic   4 bytes "CRSC"
+    version u32
+    data    zlib compressed
+
+data (before compression):
+    size      u32 x, u32 y, u32 z
+    palette   u32 count, then per name u32 byte length + utf8 bytes
+    block     x * y * z u32 palette indices, laid out x then z then y
+    rotation  x * y * z bytes
+
+everything is little endian
+*/
+
+use std::{
+    collections::HashMap,
+    fs::{
+        self,
+        File
+    },
+    io::{
+        self,
+        Read,
+        Write,
+        BufWriter,
+        Error,
+        ErrorKind
+    },
+    path::Path
+};
+
+use flate2::{
+    read::ZlibDecoder,
+    write::ZlibEncoder,
+    Compression
+};
+
+use glam::IVec3;
+
+use crate::blocks::block_component_system::BlockComponentSystem;
+
+use super::{
+    world::World,
+    region_edit::{
+        Clipboard,
+        Selection,
+        copy
+    }
+};
+
+const SCHEMATIC_MAGIC: &[u8; 4] = b"CRSC";
+const SCHEMATIC_VERSION: u32 = 1;
+
+// anything bigger than this is a broken file, not a build - it would take around 80MB to load
+const MAX_SCHEMATIC_VOLUME: usize = 1 << 24;
+
+// in bytes
+const MAX_BLOCK_NAME_LENGTH: usize = 1024;
+
+// packs a clipboard into schematic bytes
+pub fn write_schematic(bcs: &BlockComponentSystem, clipboard: &Clipboard) -> io::Result<Vec<u8>> {
+
+    // only the blocks that are actually used go in the palette
+    let mut palette: Vec<u32> = Vec::new();
+    let mut palette_index: HashMap<u32, u32> = HashMap::new();
+
+    let indices: Vec<u32> = clipboard.get_blocks().iter().map(|id| {
+        *palette_index.entry(*id).or_insert_with(|| {
+            palette.push(*id);
+            (palette.len() - 1) as u32
+        })
+    }).collect();
+
+    let mut encoder: ZlibEncoder<Vec<u8>> = ZlibEncoder::new(Vec::new(), Compression::default());
+
+    let size: IVec3 = clipboard.get_size();
+
+    for axis in [size.x, size.y, size.z] {
+        encoder.write_all(&(axis as u32).to_le_bytes())?;
+    }
+
+    encoder.write_all(&(palette.len() as u32).to_le_bytes())?;
+    for id in palette.iter() {
+        let name: &[u8] = bcs.get_name(*id).as_bytes();
+        encoder.write_all(&(name.len() as u32).to_le_bytes())?;
+        encoder.write_all(name)?;
+    }
+
+    for index in indices.iter() {
+        encoder.write_all(&index.to_le_bytes())?;
+    }
+
+    encoder.write_all(clipboard.get_rotations())?;
+
+    let mut data: Vec<u8> = Vec::new();
+
+    data.extend_from_slice(SCHEMATIC_MAGIC);
+    data.extend_from_slice(&SCHEMATIC_VERSION.to_le_bytes());
+    data.extend_from_slice(&encoder.finish()?);
+
+    Ok(data)
+}
+
+// a file that ends early is as broken as one with bad values in it
+fn read_bytes(decoder: &mut ZlibDecoder<&[u8]>, bytes: &mut [u8]) -> io::Result<()> {
+    decoder.read_exact(bytes).map_err(|error| match error.kind() {
+        ErrorKind::UnexpectedEof => Error::new(ErrorKind::InvalidData, "SCHEMATIC IS CUT OFF!"),
+        _ => error,
+    })
+}
+
+fn read_u32(decoder: &mut ZlibDecoder<&[u8]>) -> io::Result<u32> {
+    let mut word: [u8; 4] = [0; 4];
+    read_bytes(decoder, &mut word)?;
+    Ok(u32::from_le_bytes(word))
+}
+
+// unpacks schematic bytes into a clipboard, block names are matched to this mod set's ids
+pub fn read_schematic(bcs: &BlockComponentSystem, data: &[u8]) -> io::Result<Clipboard> {
+
+    if data.len() < 8 || &data[0..4] != SCHEMATIC_MAGIC {
+        return Err(Error::new(ErrorKind::InvalidData, "NOT A SCHEMATIC FILE!"));
+    }
+
+    let version: u32 = u32::from_le_bytes([data[4], data[5], data[6], data[7]]);
+
+    if version != SCHEMATIC_VERSION {
+        return Err(Error::new(ErrorKind::InvalidData, format!("UNSUPPORTED SCHEMATIC VERSION {}!", version)));
+    }
+
+    let mut decoder: ZlibDecoder<&[u8]> = ZlibDecoder::new(&data[8..]);
+
+    let size_x: u32 = read_u32(&mut decoder)?;
+    let size_y: u32 = read_u32(&mut decoder)?;
+    let size_z: u32 = read_u32(&mut decoder)?;
+
+    if size_x == 0 || size_y == 0 || size_z == 0 {
+        return Err(Error::new(ErrorKind::InvalidData, "SCHEMATIC HAS NO BLOCKS!"));
+    }
+
+    // the size comes straight from the file, it can't be trusted to multiply out
+    let volume: usize = match (size_x as usize).checked_mul(size_y as usize).and_then(|area| area.checked_mul(size_z as usize)) {
+        Some(volume) if volume <= MAX_SCHEMATIC_VOLUME => volume,
+        _ => return Err(Error::new(ErrorKind::InvalidData, format!("SCHEMATIC IS {} BY {} BY {}, MORE THAN {} BLOCKS!", size_x, size_y, size_z, MAX_SCHEMATIC_VOLUME))),
+    };
+
+    // every axis fits, the volume does
+    let size: IVec3 = IVec3::new(size_x as i32, size_y as i32, size_z as i32);
+
+    let palette_count: usize = read_u32(&mut decoder)? as usize;
+
+    // a palette only holds the blocks that are used
+    if palette_count > volume {
+        return Err(Error::new(ErrorKind::InvalidData, format!("SCHEMATIC HAS {} PALETTE ENTRIES FOR {} BLOCKS!", palette_count, volume)));
+    }
+
+    let mut palette: Vec<u32> = Vec::with_capacity(palette_count);
+
+    for _ in 0..palette_count {
+        let name_length: usize = read_u32(&mut decoder)? as usize;
+
+        if name_length > MAX_BLOCK_NAME_LENGTH {
+            return Err(Error::new(ErrorKind::InvalidData, format!("SCHEMATIC BLOCK NAME IS {} BYTES LONG!", name_length)));
+        }
+
+        let mut name_bytes: Vec<u8> = vec![0; name_length];
+        read_bytes(&mut decoder, &mut name_bytes)?;
+
+        let name: String = String::from_utf8(name_bytes).map_err(|_| Error::new(ErrorKind::InvalidData, "SCHEMATIC BLOCK NAME IS NOT UTF-8!"))?;
+
+        palette.push(match bcs.find_id_of(&name) {
+            Some(id) => id,
+            None => {
+                println!("SCHEMATIC CONTAINS UNKNOWN BLOCK: {}! REPLACING WITH AIR!", name);
+                0
+            },
+        });
+    }
+
+    let mut block: Vec<u32> = Vec::with_capacity(volume);
+
+    for _ in 0..volume {
+        let index: usize = read_u32(&mut decoder)? as usize;
+
+        match palette.get(index) {
+            Some(id) => block.push(*id),
+            None => return Err(Error::new(ErrorKind::InvalidData, "SCHEMATIC BLOCK IS OUTSIDE ITS PALETTE!")),
+        }
+    }
+
+    let mut rotation: Vec<u8> = vec![0; volume];
+    read_bytes(&mut decoder, &mut rotation)?;
+
+    Ok(Clipboard::new(size, block, rotation))
+}
+
+// copies the selection out of the world into a schematic file
+pub fn export_schematic(world: &World, bcs: &BlockComponentSystem, selection: &Selection, path: &Path) -> io::Result<()> {
+
+    let data: Vec<u8> = write_schematic(bcs, &copy(world, selection))?;
+
+    let mut writer: BufWriter<File> = BufWriter::new(File::create(path)?);
+
+    writer.write_all(&data)?;
+
+    writer.flush()
+}
+
+// reads a schematic file into a clipboard, ready to be pasted with region_edit::paste
+pub fn import_schematic(bcs: &BlockComponentSystem, path: &Path) -> io::Result<Clipboard> {
+    read_schematic(bcs, &fs::read(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::block_component_system::{
+        DrawType,
+        RotationType
+    };
+
+    use super::*;
+
+    // air plus the blocks in the order given, so the same names can get different ids
+    fn test_bcs(names: &[&str]) -> BlockComponentSystem {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+
+        for name in names {
+            bcs.register_block(String::from("test"), name.to_string(), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+        }
+
+        bcs
+    }
+
+    // a schematic with a hand written header and nothing after it
+    fn schematic_header(values: &[u32]) -> Vec<u8> {
+        let mut encoder: ZlibEncoder<Vec<u8>> = ZlibEncoder::new(Vec::new(), Compression::default());
+
+        for value in values {
+            encoder.write_all(&value.to_le_bytes()).unwrap();
+        }
+
+        let mut data: Vec<u8> = Vec::new();
+        data.extend_from_slice(SCHEMATIC_MAGIC);
+        data.extend_from_slice(&SCHEMATIC_VERSION.to_le_bytes());
+        data.extend_from_slice(&encoder.finish().unwrap());
+        data
+    }
+
+    fn assert_invalid(bcs: &BlockComponentSystem, data: &[u8]) {
+        match read_schematic(bcs, data) {
+            Ok(_) => panic!("A BROKEN SCHEMATIC LOADED!"),
+            Err(error) => assert_eq!(error.kind(), ErrorKind::InvalidData),
+        }
+    }
+
+    #[test]
+    fn schematics_survive_block_ids_being_renumbered() {
+        let saved_bcs: BlockComponentSystem = test_bcs(&["stone", "dirt", "lamp"]);
+        let (stone, dirt, lamp) = (saved_bcs.get_id_of(String::from("stone")), saved_bcs.get_id_of(String::from("dirt")), saved_bcs.get_id_of(String::from("lamp")));
+
+        let clipboard: Clipboard = Clipboard::new(IVec3::new(2, 2, 1), vec![stone, 0, dirt, lamp], vec![0, 0, 3, 1]);
+
+        let data: Vec<u8> = write_schematic(&saved_bcs, &clipboard).unwrap();
+
+        // the same blocks registered in another order, and the lamp mod is gone
+        let loaded_bcs: BlockComponentSystem = test_bcs(&["glass", "dirt", "stone"]);
+
+        let loaded: Clipboard = read_schematic(&loaded_bcs, &data).unwrap();
+
+        assert_eq!(loaded.get_size(), IVec3::new(2, 2, 1));
+        assert_eq!(loaded.get_blocks(), &vec![loaded_bcs.get_id_of(String::from("stone")), 0, loaded_bcs.get_id_of(String::from("dirt")), 0]);
+        assert_eq!(loaded.get_rotations(), &vec![0, 0, 3, 1]);
+    }
+
+    #[test]
+    fn broken_headers_are_invalid_data() {
+        let bcs: BlockComponentSystem = test_bcs(&["stone"]);
+
+        // multiplies past u32 and past usize on 32 bit
+        assert_invalid(&bcs, &schematic_header(&[u32::MAX, u32::MAX, u32::MAX]));
+        assert_invalid(&bcs, &schematic_header(&[4096, 4096, 4096]));
+        assert_invalid(&bcs, &schematic_header(&[0, 4, 4]));
+
+        // more palette entries than blocks, and a name that would take gigabytes
+        assert_invalid(&bcs, &schematic_header(&[2, 2, 2, 9]));
+        assert_invalid(&bcs, &schematic_header(&[2, 2, 2, 1, u32::MAX]));
+
+        // cut off before the blocks
+        assert_invalid(&bcs, &schematic_header(&[2, 2, 2, 0]));
+    }
+}
@@ -0,0 +1,359 @@
+/*
+block ticks - how blocks change on their own
+
+every game tick the scheduler hands out two kinds of ticks:
+
+random ticks - random_tick_speed random blocks in every loaded section are picked,
+the ones that have an on_random_tick get ticked, sections without such a block are
+skipped without looking at their blocks
+
+scheduled updates - a block asks for an update a number of ticks from now, they
+come out in the order they are due (the order they were scheduled on a tie)
+
+the scheduler only decides what is due, running the ticks is up to whoever owns
+it (liquids flow in liquid, the rest go to the lua callbacks in lua_block_ticks) - scheduled updates only live in memory,
+one that comes due in a chunk that isn't loaded is dropped
+*/
+
+use std::{
+    cmp::Reverse,
+    collections::{
+        BinaryHeap,
+        HashSet
+    }
+};
+
+use rand::{
+    rngs::StdRng,
+    Rng,
+    SeedableRng
+};
+
+use crate::blocks::block_component_system::BlockComponentSystem;
+
+use super::{
+    chunk_storage::{
+        section_index_to_pos,
+        SECTION_HEIGHT,
+        SECTION_SIZE
+    },
+    world::{
+        World,
+        CHUNK_WIDTH,
+        MIN_Y
+    }
+};
+
+// how many game ticks happen in a second
+pub const TICKS_PER_SECOND: u32 = 20;
+
+// how many blocks of every section get a random tick each game tick
+pub const DEFAULT_RANDOM_TICK_SPEED: u32 = 3;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TickKind {
+    Random,
+    Scheduled
+}
+
+impl TickKind {
+    // the name lua knows the tick by
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            TickKind::Random => "random",
+            TickKind::Scheduled => "scheduled",
+        }
+    }
+}
+
+// a block that is due for a tick, global position
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BlockTick {
+    kind: TickKind,
+    x: i32,
+    y: i32,
+    z: i32,
+    // the block that was there when the tick came due
+    id: u32
+}
+
+impl BlockTick {
+    pub fn get_kind(&self) -> TickKind {
+        self.kind
+    }
+
+    pub fn get_pos(&self) -> (i32, i32, i32) {
+        (self.x, self.y, self.z)
+    }
+
+    pub fn get_id(&self) -> u32 {
+        self.id
+    }
+}
+
+// (due tick, order scheduled, position)
+type ScheduledUpdate = (u64, u64, (i32, i32, i32));
+
+pub struct TickScheduler {
+    // how many ticks have run
+    tick: u64,
+
+    random_tick_speed: u32,
+
+    // Reverse turns the heap into soonest first
+    scheduled: BinaryHeap<Reverse<ScheduledUpdate>>,
+    // positions with an update waiting, a block only ever has one
+    pending: HashSet<(i32, i32, i32)>,
+    // ties on the due tick go in the order they were scheduled
+    next_order: u64,
+
+    rng: StdRng
+}
+
+impl TickScheduler {
+
+    // the seed only picks which blocks get random ticks
+    pub fn new(seed: u64) -> Self {
+        Self {
+            tick: 0,
+            random_tick_speed: DEFAULT_RANDOM_TICK_SPEED,
+            scheduled: BinaryHeap::new(),
+            pending: HashSet::new(),
+            next_order: 0,
+            rng: StdRng::seed_from_u64(seed)
+        }
+    }
+
+    pub fn get_tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn get_random_tick_speed(&self) -> u32 {
+        self.random_tick_speed
+    }
+
+    // 0 turns random ticks off
+    pub fn set_random_tick_speed(&mut self, random_tick_speed: u32) {
+        self.random_tick_speed = random_tick_speed;
+    }
+
+    // asks for an update of the block at x,y,z in delay ticks (at least 1) - false if it already has one waiting
+    pub fn schedule(&mut self, x: i32, y: i32, z: i32, delay: u64) -> bool {
+
+        if !self.pending.insert((x, y, z)) {
+            return false;
+        }
+
+        self.scheduled.push(Reverse((self.tick + delay.max(1), self.next_order, (x, y, z))));
+
+        self.next_order += 1;
+
+        true
+    }
+
+    pub fn is_scheduled(&self, x: i32, y: i32, z: i32) -> bool {
+        self.pending.contains(&(x, y, z))
+    }
+
+    pub fn get_number_of_scheduled(&self) -> usize {
+        self.scheduled.len()
+    }
+
+    // runs the clock forward one game tick - returns every tick that is due, scheduled updates first
+    pub fn advance(&mut self, world: &World, bcs: &BlockComponentSystem) -> Vec<BlockTick> {
+
+        self.tick += 1;
+
+        let mut ticks: Vec<BlockTick> = Vec::new();
+
+        self.collect_scheduled(world, bcs, &mut ticks);
+
+        self.collect_random(world, bcs, &mut ticks);
+
+        ticks
+    }
+
+    fn collect_scheduled(&mut self, world: &World, bcs: &BlockComponentSystem, ticks: &mut Vec<BlockTick>) {
+
+        while let Some(Reverse((due, _, _))) = self.scheduled.peek() {
+
+            if *due > self.tick {
+                break;
+            }
+
+            let Reverse((_, _, (x, y, z))) = self.scheduled.pop().unwrap();
+
+            self.pending.remove(&(x, y, z));
+
+            // the block may have been swapped for one that doesn't care since it was scheduled
+            match world.get_block(x, y, z) {
+                Some(id) if bcs.has_scheduled_update(id) || bcs.is_liquid(id) => ticks.push(BlockTick { kind: TickKind::Scheduled, x, y, z, id }),
+                _ => (),
+            }
+        }
+    }
+
+    fn collect_random(&mut self, world: &World, bcs: &BlockComponentSystem, ticks: &mut Vec<BlockTick>) {
+
+        if self.random_tick_speed == 0 || !bcs.any_random_tick() {
+            return;
+        }
+
+        let chunks: Vec<(i32, i32)> = world.iter_map().map(|((x, z), _)| (*x, *z)).collect();
+
+        for (chunk_x, chunk_z) in chunks {
+
+            let blocks = world.get_chunk_blocks(chunk_x, chunk_z).unwrap();
+
+            for section in 0..blocks.get_section_count() {
+
+                let section_blocks = blocks.get_section(section);
+
+                // pure air and pure stone sections are the common case, the palette says if anything in here ticks
+                if !section_blocks.get_used_values().any(|id| bcs.has_random_tick(id)) {
+                    continue;
+                }
+
+                for _ in 0..self.random_tick_speed {
+
+                    let section_index: usize = self.rng.gen_range(0..SECTION_SIZE);
+
+                    let id: u32 = section_blocks.get(section_index);
+
+                    if !bcs.has_random_tick(id) {
+                        continue;
+                    }
+
+                    let (local_x, local_y, local_z) = section_index_to_pos(section_index);
+
+                    ticks.push(BlockTick {
+                        kind: TickKind::Random,
+                        x: chunk_x * CHUNK_WIDTH + local_x as i32,
+                        y: MIN_Y + section as i32 * SECTION_HEIGHT + local_y as i32,
+                        z: chunk_z * CHUNK_WIDTH + local_z as i32,
+                        id
+                    });
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::block_component_system::{
+        DrawType,
+        RotationType
+    };
+
+    use super::*;
+
+    // air, stone, a block with scheduled updates and one with random ticks
+    fn test_bcs() -> (BlockComponentSystem, u32, u32, u32) {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+
+        let stone: u32 = bcs.register_block(String::from("test"), String::from("stone"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+        let observer: u32 = bcs.register_block(String::from("test"), String::from("observer"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+        let grass: u32 = bcs.register_block(String::from("test"), String::from("grass"), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+
+        bcs.set_block_ticks(observer, false, true);
+        bcs.set_block_ticks(grass, true, false);
+
+        (bcs, stone, observer, grass)
+    }
+
+    fn positions(ticks: &[BlockTick]) -> Vec<(i32, i32, i32)> {
+        ticks.iter().map(|tick| tick.get_pos()).collect()
+    }
+
+    #[test]
+    fn scheduled_updates_come_out_by_due_tick_then_scheduling_order() {
+        let (bcs, _, observer, _) = test_bcs();
+        let mut world: World = World::initialize();
+        world.add_chunk(0, 0);
+
+        for x in 0..4 {
+            world.set_block(x, 10, 0, observer);
+        }
+
+        let mut tick_scheduler: TickScheduler = TickScheduler::new(0);
+
+        tick_scheduler.schedule(0, 10, 0, 2);
+        tick_scheduler.schedule(1, 10, 0, 1);
+        tick_scheduler.schedule(2, 10, 0, 2);
+        // a delay of 0 still waits for the next tick
+        tick_scheduler.schedule(3, 10, 0, 0);
+
+        let first: Vec<BlockTick> = tick_scheduler.advance(&world, &bcs);
+        assert_eq!(positions(&first), vec![(1, 10, 0), (3, 10, 0)]);
+        assert!(first.iter().all(|tick| tick.get_kind() == TickKind::Scheduled && tick.get_id() == observer));
+
+        assert_eq!(positions(&tick_scheduler.advance(&world, &bcs)), vec![(0, 10, 0), (2, 10, 0)]);
+        assert!(tick_scheduler.advance(&world, &bcs).is_empty());
+    }
+
+    #[test]
+    fn a_block_only_has_one_update_waiting() {
+        let (bcs, stone, observer, _) = test_bcs();
+        let mut world: World = World::initialize();
+        world.add_chunk(0, 0);
+        world.set_block(0, 10, 0, observer);
+        world.set_block(1, 10, 0, stone);
+
+        let mut tick_scheduler: TickScheduler = TickScheduler::new(0);
+
+        assert!(tick_scheduler.schedule(0, 10, 0, 1));
+        assert!(!tick_scheduler.schedule(0, 10, 0, 5));
+        assert!(tick_scheduler.schedule(1, 10, 0, 1));
+        assert_eq!(tick_scheduler.get_number_of_scheduled(), 2);
+
+        // the stone doesn't care about updates, it is dropped
+        assert_eq!(positions(&tick_scheduler.advance(&world, &bcs)), vec![(0, 10, 0)]);
+
+        assert!(!tick_scheduler.is_scheduled(0, 10, 0));
+        assert!(tick_scheduler.schedule(0, 10, 0, 1));
+    }
+
+    #[test]
+    fn random_ticks_skip_sections_without_ticking_blocks() {
+        let (bcs, stone, _, grass) = test_bcs();
+
+        // one section of grass in both, the second world has stone in every other section
+        let mut world: World = World::initialize();
+        let mut stone_world: World = World::initialize();
+        world.add_chunk(0, 0);
+        stone_world.add_chunk(0, 0);
+
+        let grass_section = MIN_Y + SECTION_HEIGHT..MIN_Y + 2 * SECTION_HEIGHT;
+
+        for x in 0..CHUNK_WIDTH {
+            for z in 0..CHUNK_WIDTH {
+                for y in MIN_Y..MIN_Y + 3 * SECTION_HEIGHT {
+                    let id: u32 = if grass_section.contains(&y) { grass } else { stone };
+
+                    if id == grass {
+                        world.set_block(x, y, z, id);
+                    }
+                    stone_world.set_block(x, y, z, id);
+                }
+            }
+        }
+
+        let mut tick_scheduler: TickScheduler = TickScheduler::new(7);
+        let mut stone_tick_scheduler: TickScheduler = TickScheduler::new(7);
+
+        for _ in 0..10 {
+            let ticks: Vec<BlockTick> = tick_scheduler.advance(&world, &bcs);
+
+            assert_eq!(ticks.len(), DEFAULT_RANDOM_TICK_SPEED as usize);
+            assert!(ticks.iter().all(|tick| tick.get_kind() == TickKind::Random && tick.get_id() == grass));
+            assert!(ticks.iter().all(|tick| grass_section.contains(&tick.get_pos().1)));
+
+            // the stone sections never draw from the rng, the same seed picks the same blocks
+            assert_eq!(ticks, stone_tick_scheduler.advance(&stone_world, &bcs));
+        }
+
+        tick_scheduler.set_random_tick_speed(0);
+        assert!(tick_scheduler.advance(&world, &bcs).is_empty());
+    }
+}
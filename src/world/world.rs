@@ -1,33 +1,217 @@
 use std::{
     collections::{
-        hash_map::Values
-    }, slice::Iter, iter::Zip, vec
+        HashMap
+    }, slice::Iter, iter::Zip, vec, sync::Arc
 };
 
 use glam::{Vec3, Vec2};
 
+use super::{
+    chunk_storage::{
+        ChunkStorage,
+        SECTION_HEIGHT,
+        SECTION_COUNT
+    },
+    world_event::{
+        WorldEvent,
+        WorldObserver
+    }
+};
+
+#[cfg(feature = "client")]
 use crate::graphics::mesh_component_system::MeshComponentSystem;
 
+/*
+world parameters
+
+how tall the world is and where its floor sits are set here and nowhere else, the
+chunk layout, sections, generation, meshing, lighting and saving all follow them
+
+CHUNK_HEIGHT has to be a multiple of SECTION_HEIGHT
+
+inside a chunk y always runs from 0 to CHUNK_HEIGHT - 1, global y is that plus MIN_Y
+*/
+
+// chunk dimensions in blocks
+pub const CHUNK_WIDTH: i32 = 16;
+pub const CHUNK_HEIGHT: i32 = 128;
+
+// y of the lowest block in the world, negative to dig below 0
+pub const MIN_Y: i32 = 0;
+
+// y of the highest block in the world
+pub const MAX_Y: i32 = MIN_Y + CHUNK_HEIGHT - 1;
+
+// blocks in a chunk
+pub const CHUNK_SIZE: usize = (CHUNK_WIDTH * CHUNK_WIDTH * CHUNK_HEIGHT) as usize;
+
+// columns in a chunk
+pub const CHUNK_AREA: usize = (CHUNK_WIDTH * CHUNK_WIDTH) as usize;
+
+// blocks in one x slice of a chunk
+const X_STRIDE: usize = (CHUNK_WIDTH * CHUNK_HEIGHT) as usize;
+
+// Converts local x,y,z 3D position into 1D position.
+pub fn pos_to_index ( x: usize, y: usize, z: usize ) -> usize {
+    (x * X_STRIDE) + (z * CHUNK_HEIGHT as usize) + y
+}
+
+// Converts 1D position back into local (x, y, z).
+pub fn index_to_pos ( index: usize ) -> (usize, usize, usize) {
+    (index / X_STRIDE, index % CHUNK_HEIGHT as usize, (index % X_STRIDE) / CHUNK_HEIGHT as usize)
+}
+
+// local x,z inside a chunk -> index into the chunk's heightmap
+pub fn column_index(x: usize, z: usize) -> usize {
+    (x * CHUNK_WIDTH as usize) + z
+}
+
+// a chunk position and a local x,y,z inside that chunk
+pub(crate) type ChunkLocal = ((i32, i32), (usize, usize, usize));
+
+// splits a global block position into the chunk it's in and the index inside that chunk
+pub(crate) fn global_to_chunk(x: i32, y: i32, z: i32) -> Option<ChunkLocal> {
+
+    if !(MIN_Y..=MAX_Y).contains(&y) {
+        return None;
+    }
+
+    Some((
+        (x.div_euclid(CHUNK_WIDTH), z.div_euclid(CHUNK_WIDTH)),
+        (x.rem_euclid(CHUNK_WIDTH) as usize, (y - MIN_Y) as usize, z.rem_euclid(CHUNK_WIDTH) as usize)
+    ))
+}
+
+// ((x, z), section mesh ids) of every loaded chunk
+pub type MapIter<'a> = Zip<Zip<Iter<'a, i32>, Iter<'a, i32>>, Iter<'a, Vec<Option<u32>>>>;
+
+/*
+a whole chunk outside of any world
+
+chunks are generated (or loaded) into one of these and only go into a World once
+they are complete, so nothing can ever read a chunk that is half way through generating
+*/
+pub struct ChunkData {
+    block:     ChunkStorage<u32>,
+    rotation:  ChunkStorage<u8>,
+    light:     ChunkStorage<u8>,
+    heightmap: Vec<u16>
+}
+
+impl ChunkData {
+
+    // a chunk of pure air with no light
+    pub fn new() -> Self {
+        Self {
+            block:     ChunkStorage::new(0),
+            rotation:  ChunkStorage::new(0),
+            light:     ChunkStorage::new(0),
+            heightmap: vec![0; CHUNK_AREA]
+        }
+    }
+
+    pub fn from_parts(block: ChunkStorage<u32>, rotation: ChunkStorage<u8>, light: ChunkStorage<u8>, heightmap: Vec<u16>) -> Self {
+        Self {
+            block,
+            rotation,
+            light,
+            heightmap
+        }
+    }
+
+    // the block data and heightmap together, so the generator can fill both
+    pub fn get_blocks_and_heightmap_mut(&mut self) -> (&mut ChunkStorage<u32>, &mut Vec<u16>) {
+        (&mut self.block, &mut self.heightmap)
+    }
+
+    // local position, y is y - MIN_Y
+    pub fn get_block(&self, x: usize, y: usize, z: usize) -> u32 {
+        self.block.get(pos_to_index(x, y, z))
+    }
+
+    // local y of the highest non-air block in the column, 0 if the column is empty
+    pub fn get_height(&self, x: usize, z: usize) -> usize {
+        self.heightmap[column_index(x, z)] as usize
+    }
+
+    // local position, y is y - MIN_Y - the heightmap follows along, the light does not
+    pub fn set_block(&mut self, x: usize, y: usize, z: usize, id: u32, rotation: u8) {
+
+        self.block.set(pos_to_index(x, y, z), id);
+        self.rotation.set(pos_to_index(x, y, z), rotation);
+
+        let column: usize = column_index(x, z);
+
+        if id != 0 && y > self.heightmap[column] as usize {
+            self.heightmap[column] = y as u16;
+        } else if id == 0 && y == self.heightmap[column] as usize {
+            self.heightmap[column] = (0..y)
+                .rev()
+                .find(|below| self.block.get(pos_to_index(x, *below, z)) != 0)
+                .unwrap_or(0) as u16;
+        }
+    }
+}
+
+impl Default for ChunkData {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/*
+thread safety
+
+the main thread owns the World and is the only one that writes to it, other
+threads never touch it directly - they get a snapshot instead
+
+every chunk array sits behind an Arc, a snapshot only copies the Arcs so it is
+cheap and can be sent to another thread - writing to a chunk a snapshot still
+holds copies that one chunk first (copy on write), the snapshot never sees the
+change and the rest of the world is never copied
 
+generation works on a ChunkData that nothing else can see, and it is handed to
+the world whole with insert_chunk - meshes are built on the generation pool from
+snapshots (see ChunkMeshService), lighting still runs on the main thread against the world itself
+*/
 pub struct World {
 
+    // (x, z) -> index into the parallel vectors below
+    index:      HashMap<(i32, i32), usize>,
+
     position_x: Vec<i32>,
     position_z: Vec<i32>,
-    block:      Vec<Vec<u32>>,
-    rotation:   Vec<Vec<u8>>,
-    light:      Vec<Vec<u8>>,
-    heightmap:  Vec<Vec<u8>>,
+    block:      Vec<Arc<ChunkStorage<u32>>>,
+    rotation:   Vec<Arc<ChunkStorage<u8>>>,
+    light:      Vec<Arc<ChunkStorage<u8>>>,
+    // local y (y - MIN_Y) of the highest non-air block in each column, 0 if the column is empty
+    heightmap:  Vec<Arc<Vec<u16>>>,
 
     // chunks NEED to have data, but their mesh COULD not be generated yet
-    mesh_id:    Vec<Option<u32>>,
+    // one mesh per section, empty sections never get one
+    mesh_id:    Vec<Vec<Option<u32>>>,
+
+    // chunks that changed since they were last saved to disk
+    modified:   Vec<bool>,
 
-    sorted_chunks: Vec<(Option<u32>, (i32, i32))>
+    sorted_chunks: Vec<(Vec<u32>, (i32, i32))>,
+
+    // everything that happened since the last dispatch, in order
+    events: Vec<WorldEvent>
 }
 
+// snapshots are sent to other threads, this breaks the build if the world ever stops allowing it
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync>() {}
+    assert_send_sync::<World>();
+    assert_send_sync::<ChunkData>();
+};
+
 impl World {
 
     pub fn initialize() -> Self {
         Self {
+            index:      HashMap::new(),
             position_x: Vec::new(),
             position_z: Vec::new(),
             block:      Vec::new(),
@@ -36,42 +220,71 @@ impl World {
             heightmap:  Vec::new(),
 
             mesh_id:    Vec::new(),
-            sorted_chunks: Vec::new()
+            modified:   Vec::new(),
+            sorted_chunks: Vec::new(),
+
+            events:     Vec::new()
         }
     }
 
+    // records an event for the next dispatch
+    pub(crate) fn emit(&mut self, event: WorldEvent) {
+        self.events.push(event);
+    }
 
-    pub fn remove_chunk(&mut self, x: i32, z: i32) -> bool {
-        match self.get_index(x, z) {
-            Some(index) => {
-                self.position_x.remove(index);
-                self.position_z.remove(index);
-            },
-            None => return false,
+    // hands the events that piled up to the observers in order, then forgets them - observers see the world as it is now
+    pub fn dispatch_events(&mut self, observers: &mut [&mut dyn WorldObserver]) {
+        let events: Vec<WorldEvent> = std::mem::take(&mut self.events);
+
+        for event in events.iter() {
+            for observer in observers.iter_mut() {
+                observer.on_world_event(self, event);
+            }
         }
+    }
 
-        true
+    // takes the events that piled up without dispatching them
+    pub fn take_events(&mut self) -> Vec<WorldEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+
+    /*
+    takes a chunk out of the world, every parallel vector loses it together
+
+    returns the meshes the chunk still had so the caller can free them, None if the chunk wasn't in the world
+    */
+    pub fn remove_chunk(&mut self, x: i32, z: i32) -> Option<Vec<u32>> {
+
+        let index: usize = self.index.remove(&(x, z))?;
+
+        self.emit(WorldEvent::ChunkRemoved { x, z });
+
+        self.position_x.swap_remove(index);
+        self.position_z.swap_remove(index);
+        self.block.swap_remove(index);
+        self.rotation.swap_remove(index);
+        self.light.swap_remove(index);
+        self.heightmap.swap_remove(index);
+        let mesh_ids: Vec<Option<u32>> = self.mesh_id.swap_remove(index);
+        self.modified.swap_remove(index);
+        self.reindex_swapped(index);
+
+        // the renderer draws from the sorted list, it can't keep meshes that are about to be freed
+        self.sorted_chunks.retain(|(_, position)| *position != (x, z));
+
+        Some(mesh_ids.into_iter().flatten().collect())
     }
     
-    // this is how we get the id - let's walk through it
+    // this is how we get the id - a hashed lookup so it stays flat as render distance grows
     fn get_index(&self, x: i32, z: i32) -> Option<usize> {
+        self.index.get(&(x, z)).copied()
+    }
 
-        // this match statement tells us if we found anything
-        match 
-        self.position_x
-            // iterate all X positions
-            .iter()
-            // zip them into a simple tuple (x, z)
-            .zip(&self.position_z)
-            // enumerate that tuple (index, (x,z)), this allows us to work with the index
-            .enumerate()
-            // we filter all chunks by x and z, then get the first value if found as an option
-            .find(| value | {
-                *value.1.0 == x && *value.1.1 == z
-            }) {
-            // now we finally match to see if we found it, zipped_value (index, (x, z)) where .0 is the index
-            Some(zipped_value) => return Some(zipped_value.0),
-            None => return None,
+    // swap_remove moved the last chunk into the hole at index, point the hash at its new home
+    fn reindex_swapped(&mut self, index: usize) {
+        if index < self.position_x.len() {
+            self.index.insert((self.position_x[index], self.position_z[index]), index);
         }
     }
 
@@ -79,30 +292,115 @@ impl World {
         (self.position_x[index], self.position_z[index])
     }
 
-    // adds a chunk to the map - returns success
+    // adds an empty chunk of air to the map - returns success
     pub fn add_chunk(&mut self, x: i32, z: i32) -> bool {
+        self.push_chunk(x, z, ChunkData::new(), true, WorldEvent::ChunkAdded { x, z })
+    }
+
+    // adds a chunk that already has data, such as one loaded from disk - returns success
+    pub fn insert_chunk(&mut self, x: i32, z: i32, chunk: ChunkData) -> bool {
+        self.push_chunk(x, z, chunk, false, WorldEvent::ChunkAdded { x, z })
+    }
+
+    // adds a chunk the generator just finished, it was never saved - returns success
+    pub fn insert_generated_chunk(&mut self, x: i32, z: i32, chunk: ChunkData) -> bool {
+        self.push_chunk(x, z, chunk, true, WorldEvent::ChunkGenerated { x, z })
+    }
+
+    fn push_chunk(&mut self, x: i32, z: i32, chunk: ChunkData, modified: bool, event: WorldEvent) -> bool {
 
         match self.get_index(x, z) {
-            Some(_) => {
-                return false;
-            }
+            Some(_) => false,
             None => {
+                self.emit(event);
+
+                self.index.insert((x, z), self.position_x.len());
+
                 self.position_x.push(x);
                 self.position_z.push(z);
 
-                self.block.push(vec![0; 32768]);
-                self.rotation.push(vec![0; 32768]);
-                self.light.push(vec![0; 32768]);
-                self.heightmap.push(vec![0; 256]);
-                self.mesh_id.push(None);
+                self.block.push(Arc::new(chunk.block));
+                self.rotation.push(Arc::new(chunk.rotation));
+                self.light.push(Arc::new(chunk.light));
+                self.heightmap.push(Arc::new(chunk.heightmap));
+                self.mesh_id.push(vec![None; SECTION_COUNT]);
+                self.modified.push(modified);
 
-                return true;
+                true
             }
         }
     }
 
+    /*
+    a read only copy of the chunk at x,z and the 8 around it, everything a chunk's mesh or light is worked out from
+
+    only the chunks that are loaded make it in, copying is just the Arcs - nothing
+    done to either world afterwards shows up in the other
+    */
+    pub fn snapshot(&self, x: i32, z: i32) -> World {
+
+        let mut snapshot: World = World::initialize();
+
+        for chunk_x in x - 1..=x + 1 {
+            for chunk_z in z - 1..=z + 1 {
+
+                let index: usize = match self.get_index(chunk_x, chunk_z) {
+                    Some(index) => index,
+                    None => continue,
+                };
+
+                snapshot.index.insert((chunk_x, chunk_z), snapshot.position_x.len());
+
+                snapshot.position_x.push(chunk_x);
+                snapshot.position_z.push(chunk_z);
+
+                snapshot.block.push(Arc::clone(&self.block[index]));
+                snapshot.rotation.push(Arc::clone(&self.rotation[index]));
+                snapshot.light.push(Arc::clone(&self.light[index]));
+                snapshot.heightmap.push(Arc::clone(&self.heightmap[index]));
+                snapshot.mesh_id.push(vec![None; SECTION_COUNT]);
+                snapshot.modified.push(false);
+            }
+        }
+
+        snapshot
+    }
+
+    // returns if the chunk changed since it was last saved
+    pub fn is_chunk_modified(&self, x: i32, z: i32) -> bool {
+        match self.get_index(x, z) {
+            Some(index) => self.modified[index],
+            None => false,
+        }
+    }
+
+    // flags the chunk as matching what is on disk
+    pub fn set_chunk_saved(&mut self, x: i32, z: i32) {
+        if let Some(index) = self.get_index(x, z) {
+            self.modified[index] = false;
+        }
+    }
+
+    // flags the chunk as changed since it was last saved
+    pub fn set_chunk_modified(&mut self, x: i32, z: i32) {
+        if let Some(index) = self.get_index(x, z) {
+            self.modified[index] = true;
+        }
+    }
+
+    // returns the positions of all chunks that need saving
+    pub fn get_modified_chunks(&self) -> Vec<(i32, i32)> {
+        self.modified
+            .iter()
+            .enumerate()
+            .filter(|(_, modified)| **modified)
+            .map(|(index, _)| self.get_pos_from_index(index))
+            .collect()
+    }
+
+    #[cfg(feature = "client")]
     pub fn clean_up(&mut self, mcs: &mut MeshComponentSystem){
-        self.mesh_id.iter().for_each( | this_mesh_option: &Option<u32> | {
+        self.mesh_id.iter().flatten().for_each( | this_mesh_option: &Option<u32> | {
             match this_mesh_option {
                 Some(mesh_id) => mcs.delete_mesh(*mesh_id, false),
                 None => (),
@@ -111,30 +409,51 @@ impl World {
     }
 
 
-    fn delete_old_mesh(&mut self, mcs: &mut MeshComponentSystem, index: usize) {
-        match self.mesh_id[index] {
+    #[cfg(feature = "client")]
+    fn delete_old_mesh(&mut self, mcs: &mut MeshComponentSystem, index: usize, section: usize) {
+        match self.mesh_id[index][section] {
             Some(mesh_id) => mcs.delete_mesh(mesh_id, false),
             None => ()
         }
     }
 
-    pub fn set_chunk_mesh(&mut self, mcs: &mut MeshComponentSystem, x: i32, z: i32, mesh_id: u32) {
+    // None clears the section, it became empty or buried
+    #[cfg(feature = "client")]
+    pub fn set_section_mesh(&mut self, mcs: &mut MeshComponentSystem, x: i32, section: usize, z: i32, mesh_id_option: Option<u32>) {
         // does the chunk exist?
         match self.get_index(x, z) {
 
             Some(index) => {
                 // clean up old mesh
-                self.delete_old_mesh(mcs, index);
+                self.delete_old_mesh(mcs, index, section);
                 // apply new mesh
-                self.mesh_id[index] = Some(mesh_id);
+                self.mesh_id[index][section] = mesh_id_option;
             },
             // chunk does not exist, this mesh must be deleted
-            None => mcs.delete_mesh(mesh_id, false),
+            None => {
+                match mesh_id_option {
+                    Some(mesh_id) => mcs.delete_mesh(mesh_id, false),
+                    None => (),
+                }
+            },
         }
     }
+
+    pub fn chunk_exists(&self, x: i32, z: i32) -> bool {
+        self.get_index(x, z).is_some()
+    }
+
+    // where every loaded chunk is, in no particular order
+    pub fn get_chunk_positions(&self) -> Vec<(i32, i32)> {
+        self.position_x.iter().copied().zip(self.position_z.iter().copied()).collect()
+    }
+
+    pub fn get_number_of_chunks(&self) -> usize {
+        self.position_x.len()
+    }
     
     // returns a map iterator
-    pub fn iter_map(&self) -> Zip<Zip<Iter<i32>, Iter<i32>>, Iter<Option<u32>>> {
+    pub fn iter_map(&self) -> MapIter<'_> {
         self.position_x.iter().zip(self.position_z.iter()).zip(self.mesh_id.iter())
     }
 
@@ -148,7 +467,8 @@ impl World {
         self.sorted_chunks.clear();
 
         for x in self.position_x.iter() {                        
-            self.sorted_chunks.push((self.mesh_id[index], (*x, self.position_z[index])));
+            let section_meshes: Vec<u32> = self.mesh_id[index].iter().flatten().copied().collect();
+            self.sorted_chunks.push((section_meshes, (*x, self.position_z[index])));
             index += 1;
         }
 
@@ -171,35 +491,255 @@ impl World {
         //self.position_x.iter().zip(self.position_z.iter()).zip(self.mesh_id.iter())
     }
 
-    pub fn get_map_sorted(&self) -> &Vec<(Option<u32>, (i32, i32))> {
+    pub fn get_map_sorted(&self) -> &Vec<(Vec<u32>, (i32, i32))> {
         &self.sorted_chunks
     }
 
-    // returns the vector block data - mutably
-    pub fn get_chunk_blocks_mut(&mut self, x: i32, z: i32) -> Option<&mut Vec<u32>> {
+    // returns the chunk block data - mutably, this flags the chunk as modified
+    pub fn get_chunk_blocks_mut(&mut self, x: i32, z: i32) -> Option<&mut ChunkStorage<u32>> {
+        match self.get_index(x, z) {
+            Some(index) => {
+                self.modified[index] = true;
+                Some(Arc::make_mut(&mut self.block[index]))
+            },
+            None => None,
+        }
+    }
+
+    // the block and rotation data together for bulk edits - this does NOT flag the chunk, call set_chunk_modified if anything changed
+    pub(crate) fn get_chunk_blocks_and_rotation_mut(&mut self, x: i32, z: i32) -> Option<(&mut ChunkStorage<u32>, &mut ChunkStorage<u8>)> {
+        match self.get_index(x, z) {
+            Some(index) => Some((Arc::make_mut(&mut self.block[index]), Arc::make_mut(&mut self.rotation[index]))),
+            None => None,
+        }
+    }
+
+    // returns the chunk block data - immutably
+    pub fn get_chunk_blocks(&self, x: i32, z: i32) -> Option<&ChunkStorage<u32>> {
+        match self.get_index(x, z) {
+            Some(index) => Some(&*self.block[index]),
+            None => None,
+        }
+    }
+
+    /*
+    global block position getters and setters
+
+    these all return None/false if the chunk is not loaded or Y is outside the world
+
+    the setters emit an event when something actually changed
+    */
+
+    // (chunk index, block index) of a global block position
+    fn resolve_block(&self, x: i32, y: i32, z: i32) -> Option<(usize, usize)> {
+        let ((chunk_x, chunk_z), (local_x, local_y, local_z)) = global_to_chunk(x, y, z)?;
+
+        let index: usize = self.get_index(chunk_x, chunk_z)?;
+
+        Some((index, pos_to_index(local_x, local_y, local_z)))
+    }
+
+    // calls on_section for the section holding this block, and the neighboring sections that can see it
+    pub(crate) fn for_each_remesh_section<F: FnMut(i32, usize, i32)>(x: i32, y: i32, z: i32, mut on_section: F) {
+
+        let ((chunk_x, chunk_z), (local_x, local_y, local_z)) = match global_to_chunk(x, y, z) {
+            Some(resolved) => resolved,
+            None => return,
+        };
+
+        let section_height: usize = SECTION_HEIGHT as usize;
+
+        let section: usize = local_y / section_height;
+
+        on_section(chunk_x, section, chunk_z);
+
+        if local_x == 0 {
+            on_section(chunk_x - 1, section, chunk_z);
+        } else if local_x == (CHUNK_WIDTH - 1) as usize {
+            on_section(chunk_x + 1, section, chunk_z);
+        }
+
+        if local_z == 0 {
+            on_section(chunk_x, section, chunk_z - 1);
+        } else if local_z == (CHUNK_WIDTH - 1) as usize {
+            on_section(chunk_x, section, chunk_z + 1);
+        }
+
+        if local_y % section_height == 0 && section > 0 {
+            on_section(chunk_x, section - 1, chunk_z);
+        } else if local_y % section_height == section_height - 1 && section + 1 < SECTION_COUNT {
+            on_section(chunk_x, section + 1, chunk_z);
+        }
+    }
+
+    pub fn get_block(&self, x: i32, y: i32, z: i32) -> Option<u32> {
+        let (index, block_index) = self.resolve_block(x, y, z)?;
+        Some(self.block[index].get(block_index))
+    }
+
+    pub fn set_block(&mut self, x: i32, y: i32, z: i32, id: u32) -> bool {
+        match self.resolve_block(x, y, z) {
+            Some((index, block_index)) => {
+                let old_id: u32 = self.block[index].get(block_index);
+                if old_id != id {
+                    Arc::make_mut(&mut self.block[index]).set(block_index, id);
+                    self.update_column_height(index, block_index, id);
+                    self.modified[index] = true;
+                    self.emit(WorldEvent::BlockSet { x, y, z, old_id, new_id: id });
+                }
+                true
+            },
+            None => false,
+        }
+    }
+
+    // keeps the heightmap right after the block at block_index became id
+    fn update_column_height(&mut self, index: usize, block_index: usize, id: u32) {
+
+        let (local_x, local_y, local_z) = index_to_pos(block_index);
+
+        let column: usize = column_index(local_x, local_z);
+
+        let height: usize = self.heightmap[index][column] as usize;
+
+        if id != 0 {
+            if local_y > height {
+                Arc::make_mut(&mut self.heightmap[index])[column] = local_y as u16;
+            }
+        }
+        // the top block was removed, find the next one down
+        else if local_y == height {
+            let block: &ChunkStorage<u32> = &self.block[index];
+
+            Arc::make_mut(&mut self.heightmap[index])[column] = (0..local_y)
+                .rev()
+                .find(|test_y| block.get(pos_to_index(local_x, *test_y, local_z)) != 0)
+                .unwrap_or(0) as u16;
+        }
+    }
+
+    // rebuilds the whole heightmap of a chunk, for edits that went around set_block
+    pub fn recalculate_heightmap(&mut self, x: i32, z: i32) {
+
+        let index: usize = match self.get_index(x, z) {
+            Some(index) => index,
+            None => return,
+        };
+
+        let block: &ChunkStorage<u32> = &self.block[index];
+
+        let heightmap: &mut Vec<u16> = Arc::make_mut(&mut self.heightmap[index]);
+
+        for local_x in 0..CHUNK_WIDTH as usize {
+            for local_z in 0..CHUNK_WIDTH as usize {
+                heightmap[column_index(local_x, local_z)] = (0..CHUNK_HEIGHT as usize)
+                    .rev()
+                    .find(|y| block.get(pos_to_index(local_x, *y, local_z)) != 0)
+                    .unwrap_or(0) as u16;
+            }
+        }
+    }
+
+    // y of the highest non-air block in the column at global x,z - None if the chunk is not loaded
+    pub fn get_surface_height(&self, x: i32, z: i32) -> Option<i32> {
+        let index: usize = self.get_index(x.div_euclid(CHUNK_WIDTH), z.div_euclid(CHUNK_WIDTH))?;
+
+        let column: usize = column_index(x.rem_euclid(CHUNK_WIDTH) as usize, z.rem_euclid(CHUNK_WIDTH) as usize);
+
+        Some(self.heightmap[index][column] as i32 + MIN_Y)
+    }
+
+    pub fn get_block_rotation(&self, x: i32, y: i32, z: i32) -> Option<u8> {
+        let (index, block_index) = self.resolve_block(x, y, z)?;
+        Some(self.rotation[index].get(block_index))
+    }
+
+    pub fn set_block_rotation(&mut self, x: i32, y: i32, z: i32, rotation: u8) -> bool {
+        match self.resolve_block(x, y, z) {
+            Some((index, block_index)) => {
+                let old_rotation: u8 = self.rotation[index].get(block_index);
+                if old_rotation != rotation {
+                    Arc::make_mut(&mut self.rotation[index]).set(block_index, rotation);
+                    self.modified[index] = true;
+                    self.emit(WorldEvent::BlockRotated { x, y, z, old_rotation, new_rotation: rotation });
+                }
+                true
+            },
+            None => false,
+        }
+    }
+
+    pub fn get_block_light(&self, x: i32, y: i32, z: i32) -> Option<u8> {
+        let (index, block_index) = self.resolve_block(x, y, z)?;
+        Some(self.light[index].get(block_index))
+    }
+
+    pub fn set_block_light(&mut self, x: i32, y: i32, z: i32, light: u8) -> bool {
+        if !self.set_block_light_unqueued(x, y, z, light) {
+            return self.resolve_block(x, y, z).is_some();
+        }
+
+        // the sections next to it draw with this light too
+        let mut sections: Vec<(i32, usize, i32)> = Vec::new();
+
+        Self::for_each_remesh_section(x, y, z, |chunk_x, section, chunk_z| sections.push((chunk_x, section, chunk_z)));
+
+        for (chunk_x, section, chunk_z) in sections {
+            self.emit(WorldEvent::LightChanged { x: chunk_x, section, z: chunk_z });
+        }
+
+        true
+    }
+
+    // writes light without an event, the lighting engine batches its own - returns if the light changed
+    pub(crate) fn set_block_light_unqueued(&mut self, x: i32, y: i32, z: i32, light: u8) -> bool {
+        match self.resolve_block(x, y, z) {
+            Some((index, block_index)) => {
+                if self.light[index].get(block_index) == light {
+                    return false;
+                }
+                Arc::make_mut(&mut self.light[index]).set(block_index, light);
+                self.modified[index] = true;
+                true
+            },
+            None => false,
+        }
+    }
+
+    // returns the chunk rotation data - immutably
+    pub fn get_chunk_rotation(&self, x: i32, z: i32) -> Option<&ChunkStorage<u8>> {
         match self.get_index(x, z) {
-            Some(index) => return Some(&mut self.block[index]),
+            Some(index) => Some(&*self.rotation[index]),
             None => None,
         }
     }
 
-    // returns the vector block data - immutably
-    pub fn get_chunk_blocks(&self, x: i32, z: i32) -> Option<&Vec<u32>> {
+    // returns the chunk light data - immutably
+    pub fn get_chunk_light(&self, x: i32, z: i32) -> Option<&ChunkStorage<u8>> {
         match self.get_index(x, z) {
-            Some(index) => return Some(&self.block[index]),
+            Some(index) => Some(&*self.light[index]),
             None => None,
         }
     }
 
-    // returns the vector block data as slice - immutably
-    pub fn get_chunk_blocks_slice(&self, x: i32, z: i32) -> Option<&[u32]> {
+    // returns the chunk light data - mutably, this flags the chunk as modified
+    pub fn get_chunk_light_mut(&mut self, x: i32, z: i32) -> Option<&mut ChunkStorage<u8>> {
         match self.get_index(x, z) {
             Some(index) => {
-                return Some(&self.block[index][0..32768])
+                self.modified[index] = true;
+                Some(Arc::make_mut(&mut self.light[index]))
             },
             None => None,
         }
     }
+
+    // returns the vector heightmap data (local y) - immutably
+    pub fn get_chunk_heightmap(&self, x: i32, z: i32) -> Option<&Vec<u16>> {
+        match self.get_index(x, z) {
+            Some(index) => Some(&*self.heightmap[index]),
+            None => None,
+        }
+    }
     
     /*
     pub fn iter_map_sorted(&self, camera_pos: Vec3) -> Vec<&Chunk> {
@@ -220,22 +760,6 @@ impl World {
     }
     */
 
-    // removes a chunk from the world
-    pub fn remove(&mut self, x: i32, z: i32) {
-        match self.get_index(x, z) {
-            Some(index) => {
-                self.position_x.remove(index);
-                self.position_z.remove(index);
-                self.block.remove(index);
-                self.rotation.remove(index);
-                self.light.remove(index);
-                self.heightmap.remove(index);
-                self.mesh_id.remove(index);
-            },
-            None => ()
-        }
-    }
-
     // gets a chunk
     /*
     pub fn get_chunk(&self, key: String) -> Option<&Chunk> {
@@ -247,4 +771,78 @@ impl World {
         self.map.get_mut(&key).unwrap()
     }
     */
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // every chunk gets its own block, rotation and light so a mixed up chunk shows
+    fn fill_chunk(world: &mut World, x: i32, z: i32, id: u32) {
+        let (global_x, global_z) = (x * CHUNK_WIDTH + 3, z * CHUNK_WIDTH + 5);
+
+        world.set_block(global_x, 20 + id as i32, global_z, id);
+        world.set_block_rotation(global_x, 20 + id as i32, global_z, id as u8);
+        world.set_block_light(global_x, 21 + id as i32, global_z, id as u8);
+    }
+
+    fn assert_chunk(world: &World, x: i32, z: i32, id: u32) {
+        let (global_x, global_z) = (x * CHUNK_WIDTH + 3, z * CHUNK_WIDTH + 5);
+
+        assert_eq!(world.get_block(global_x, 20 + id as i32, global_z), Some(id));
+        assert_eq!(world.get_block_rotation(global_x, 20 + id as i32, global_z), Some(id as u8));
+        assert_eq!(world.get_block_light(global_x, 21 + id as i32, global_z), Some(id as u8));
+        assert_eq!(world.get_surface_height(global_x, global_z), Some(20 + id as i32));
+    }
+
+    #[test]
+    fn removing_a_middle_chunk_keeps_the_moved_chunk_intact() {
+        let mut world: World = World::initialize();
+
+        for x in 0..3 {
+            world.add_chunk(x, 0);
+            fill_chunk(&mut world, x, 0, x as u32 + 1);
+        }
+
+        // the last chunk is swapped into the hole
+        assert!(world.remove_chunk(1, 0).is_some());
+
+        assert!(!world.chunk_exists(1, 0));
+        assert_eq!(world.get_block(CHUNK_WIDTH + 3, 22, 5), None);
+        assert_eq!(world.get_number_of_chunks(), 2);
+
+        assert_chunk(&world, 0, 0, 1);
+        assert_chunk(&world, 2, 0, 3);
+
+        assert!(world.remove_chunk(1, 0).is_none());
+    }
+
+    #[test]
+    fn a_snapshot_keeps_its_contents_when_the_world_changes() {
+        let mut world: World = World::initialize();
+
+        for x in -1..=2 {
+            world.add_chunk(x, 0);
+            fill_chunk(&mut world, x, 0, (x + 2) as u32);
+        }
+
+        let snapshot: World = world.snapshot(0, 0);
+
+        // only the chunk and its loaded neighbors come along
+        assert_eq!(snapshot.get_number_of_chunks(), 3);
+        assert!(!snapshot.chunk_exists(2, 0));
+
+        world.set_block(3, 22, 5, 9);
+        world.set_block_rotation(3, 22, 5, 0);
+        world.set_block_light(3, 23, 5, 0);
+        world.set_block(CHUNK_WIDTH + 3, 40, 5, 9);
+        fill_chunk(&mut world, -1, 0, 4);
+
+        assert_eq!(world.get_block(3, 22, 5), Some(9));
+        assert_eq!(world.get_surface_height(CHUNK_WIDTH + 3, 5), Some(40));
+
+        for x in -1..=1 {
+            assert_chunk(&snapshot, x, 0, (x + 2) as u32);
+        }
+        assert_eq!(snapshot.get_block(-CHUNK_WIDTH + 3, 24, 5), Some(0));
+    }
+}
@@ -0,0 +1,111 @@
+/*
+what a world is - everything chosen when it was created
+
+the descriptor is written into the world's world.txt by WorldSave and read back
+when the world is reopened, so the same world always comes back with the same
+terrain no matter what seed or mods the game was started with
+
+a new world takes every mod in the mods folder, an existing world only loads the
+mods it was made with
+*/
+
+use std::time::{
+    SystemTime,
+    UNIX_EPOCH
+};
+
+// how the terrain of a world is made
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum GeneratorType {
+    // noise terrain with biomes and caves
+    Biomes,
+    // every column stops at the base height, biomes still pick the surface blocks, no caves
+    Flat
+}
+
+impl GeneratorType {
+    // the name written into world.txt
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            GeneratorType::Biomes => "biomes",
+            GeneratorType::Flat => "flat",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "biomes" => Some(GeneratorType::Biomes),
+            "flat" => Some(GeneratorType::Flat),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct WorldDescriptor {
+    // also the name of the world's directory
+    name: String,
+    seed: u64,
+    generator: GeneratorType,
+    // seconds since the unix epoch
+    creation_time: u64,
+    // the mods the world is played with
+    mods: Vec<String>
+}
+
+impl WorldDescriptor {
+
+    // a brand new world - no seed picks a random one, the mods are filled in once lua has loaded them
+    pub fn new(name: String, seed_option: Option<u64>, generator: GeneratorType) -> Self {
+        Self {
+            name,
+            seed: seed_option.unwrap_or_else(rand::random::<u64>),
+            generator,
+            creation_time: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0),
+            mods: Vec::new()
+        }
+    }
+
+    // a world that already exists, WorldSave builds these when it reads world.txt
+    pub(crate) fn from_saved(name: String, seed: u64, generator: GeneratorType, creation_time: u64, mods: Vec<String>) -> Self {
+        Self {
+            name,
+            seed,
+            generator,
+            creation_time,
+            mods
+        }
+    }
+
+    pub fn get_name(&self) -> &String {
+        &self.name
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    // the seed as opensimplex wants it
+    pub fn get_noise_seed(&self) -> i64 {
+        self.seed as i64
+    }
+
+    pub fn get_generator(&self) -> GeneratorType {
+        self.generator
+    }
+
+    pub fn get_creation_time(&self) -> u64 {
+        self.creation_time
+    }
+
+    pub fn get_mods(&self) -> &Vec<String> {
+        &self.mods
+    }
+
+    pub fn set_mods(&mut self, mods: Vec<String>) {
+        self.mods = mods;
+    }
+}
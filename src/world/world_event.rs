@@ -0,0 +1,55 @@
+/*
+world events - what happened to a world since the last dispatch
+
+the world never calls anyone back while it is changing, it only records events,
+whoever owns the world hands them to the observers with World::dispatch_events
+once it's done changing things - so an observer always sees finished chunks
+(generated AND lit) and never runs in the middle of an edit
+
+chunk positions are chunk coordinates, block positions are global
+*/
+
+use super::world::World;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum WorldEvent {
+    // an empty chunk was added or a saved one was loaded
+    ChunkAdded { x: i32, z: i32 },
+    // a chunk came straight from the generator
+    ChunkGenerated { x: i32, z: i32 },
+    ChunkRemoved { x: i32, z: i32 },
+
+    BlockSet { x: i32, y: i32, z: i32, old_id: u32, new_id: u32 },
+    BlockRotated { x: i32, y: i32, z: i32, old_rotation: u8, new_rotation: u8 },
+    // many blocks in the chunk changed at once (region edits), there is no event per block
+    ChunkEdited { x: i32, z: i32 },
+    // a falling block landed somewhere it can't stay, it's gone from the world - what it leaves behind is up to the mods
+    BlockDropped { x: i32, y: i32, z: i32, id: u32 },
+
+    // light in or right next to this section changed
+    LightChanged { x: i32, section: usize, z: i32 },
+    // the whole chunk was lit from scratch
+    ChunkRelit { x: i32, z: i32 }
+}
+
+impl WorldEvent {
+    // the name lua knows the event by
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            WorldEvent::ChunkAdded { .. } => "chunk_added",
+            WorldEvent::ChunkGenerated { .. } => "chunk_generated",
+            WorldEvent::ChunkRemoved { .. } => "chunk_removed",
+            WorldEvent::BlockSet { .. } => "block_set",
+            WorldEvent::BlockRotated { .. } => "block_rotated",
+            WorldEvent::ChunkEdited { .. } => "chunk_edited",
+            WorldEvent::BlockDropped { .. } => "block_dropped",
+            WorldEvent::LightChanged { .. } => "light_changed",
+            WorldEvent::ChunkRelit { .. } => "chunk_relit",
+        }
+    }
+}
+
+// anything that wants to react to a world changing - the mesh queue, lua, saving, networking
+pub trait WorldObserver {
+    fn on_world_event(&mut self, world: &World, event: &WorldEvent);
+}
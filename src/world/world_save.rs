@@ -0,0 +1,980 @@
+use std::{
+    collections::{
+        HashMap,
+        HashSet
+    },
+    fs::{
+        self,
+        File
+    },
+    io::{
+        self,
+        Read,
+        Write,
+        BufReader,
+        BufWriter,
+        Error,
+        ErrorKind
+    },
+    path::{
+        Path,
+        PathBuf
+    }
+};
+
+use flate2::{
+    read::ZlibDecoder,
+    write::ZlibEncoder,
+    Compression
+};
+
+use crate::blocks::block_component_system::BlockComponentSystem;
+
+use super::{
+    world_descriptor::{
+        WorldDescriptor,
+        GeneratorType
+    },
+    world::{
+        World,
+        ChunkData,
+        CHUNK_HEIGHT,
+        CHUNK_SIZE,
+        CHUNK_AREA,
+        MIN_Y
+    },
+    chunk_storage::ChunkStorage
+};
+
+/*
+on-disk world layout
+
+worlds/<name>/world.txt          - metadata, the world descriptor, the world height and the block name -> id table
+worlds/<name>/regions/r.X.Z.region - 32x32 chunks grouped into one file
+worlds/<name>/regions/r.X.Z.remap  - a region translated to new block ids, only there while the ids are being remapped
+
+region file:
+    magic   4 bytes "CRRG"
+    version u32
+    header  1024 * (offset u32, length u32) - length 0 means the chunk was never saved
+    data    zlib compressed chunks
+
+chunk (before compression) - palettes are unpacked, zlib does better on flat arrays:
+    block     u32 count, then u32 ids
+    rotation  u32 count, then bytes
+    light     u32 count, then bytes
+    heightmap u32 count, then u16 local y
+
+everything is little endian
+*/
+
+const REGION_MAGIC: &[u8; 4] = b"CRRG";
+const REGION_VERSION: u32 = 2;
+
+// chunks per region on each axis
+pub const REGION_SIZE: i32 = 32;
+
+const REGION_CHUNKS: usize = (REGION_SIZE * REGION_SIZE) as usize;
+
+const HEADER_SIZE: usize = 8 + (REGION_CHUNKS * 8);
+
+// a region held in memory, each chunk is kept compressed until it is loaded into the world
+struct Region {
+    chunks: Vec<Option<Vec<u8>>>
+}
+
+impl Region {
+    fn new() -> Self {
+        Self {
+            chunks: vec![None; REGION_CHUNKS]
+        }
+    }
+
+    fn read(path: &Path) -> io::Result<Self> {
+
+        let mut data: Vec<u8> = Vec::new();
+
+        BufReader::new(File::open(path)?).read_to_end(&mut data)?;
+
+        if data.len() < HEADER_SIZE || &data[0..4] != REGION_MAGIC {
+            return Err(Error::new(ErrorKind::InvalidData, format!("{} IS NOT A REGION FILE!", path.display())));
+        }
+
+        let version: u32 = read_u32(&data, 4);
+
+        if version != REGION_VERSION {
+            return Err(Error::new(ErrorKind::InvalidData, format!("{} HAS UNSUPPORTED REGION VERSION {}!", path.display(), version)));
+        }
+
+        let mut region: Region = Region::new();
+
+        for i in 0..REGION_CHUNKS {
+            let offset: usize = read_u32(&data, 8 + (i * 8)) as usize;
+            let length: usize = read_u32(&data, 12 + (i * 8)) as usize;
+
+            if length == 0 {
+                continue;
+            }
+
+            if offset + length > data.len() {
+                return Err(Error::new(ErrorKind::InvalidData, format!("{} HAS A TRUNCATED CHUNK!", path.display())));
+            }
+
+            region.chunks[i] = Some(data[offset..offset + length].to_vec());
+        }
+
+        Ok(region)
+    }
+
+    // the whole region is rewritten, chunk sizes change every time they are compressed
+    fn write(&self, path: &Path) -> io::Result<()> {
+
+        let mut header: Vec<u8> = Vec::with_capacity(HEADER_SIZE);
+
+        header.extend_from_slice(REGION_MAGIC);
+        header.extend_from_slice(&REGION_VERSION.to_le_bytes());
+
+        let mut offset: usize = HEADER_SIZE;
+
+        for chunk_option in self.chunks.iter() {
+            let length: usize = match chunk_option {
+                Some(chunk) => chunk.len(),
+                None => 0,
+            };
+            header.extend_from_slice(&(offset as u32).to_le_bytes());
+            header.extend_from_slice(&(length as u32).to_le_bytes());
+            offset += length;
+        }
+
+        // write next to the old file then swap it in so a crash can't leave half a region
+        let temporary_path: PathBuf = path.with_extension("region.tmp");
+
+        {
+            let mut writer: BufWriter<File> = BufWriter::new(File::create(&temporary_path)?);
+
+            writer.write_all(&header)?;
+
+            for chunk in self.chunks.iter().flatten() {
+                writer.write_all(chunk)?;
+            }
+
+            writer.flush()?;
+        }
+
+        fs::rename(&temporary_path, path)
+    }
+}
+
+fn read_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes([data[offset], data[offset + 1], data[offset + 2], data[offset + 3]])
+}
+
+// chunk position -> (region position, index within region)
+fn region_of(x: i32, z: i32) -> ((i32, i32), usize) {
+    (
+        (x.div_euclid(REGION_SIZE), z.div_euclid(REGION_SIZE)),
+        (x.rem_euclid(REGION_SIZE) + (z.rem_euclid(REGION_SIZE) * REGION_SIZE)) as usize
+    )
+}
+
+fn compress_chunk(block: &[u32], rotation: &[u8], light: &[u8], heightmap: &[u16]) -> io::Result<Vec<u8>> {
+
+    let mut encoder: ZlibEncoder<Vec<u8>> = ZlibEncoder::new(Vec::new(), Compression::fast());
+
+    encoder.write_all(&(block.len() as u32).to_le_bytes())?;
+    for id in block.iter() {
+        encoder.write_all(&id.to_le_bytes())?;
+    }
+
+    for byte_array in [rotation, light] {
+        encoder.write_all(&(byte_array.len() as u32).to_le_bytes())?;
+        encoder.write_all(byte_array)?;
+    }
+
+    encoder.write_all(&(heightmap.len() as u32).to_le_bytes())?;
+    for height in heightmap.iter() {
+        encoder.write_all(&height.to_le_bytes())?;
+    }
+
+    encoder.finish()
+}
+
+// block, rotation, light, heightmap - a chunk unpacked into flat arrays
+type ChunkArrays = (Vec<u32>, Vec<u8>, Vec<u8>, Vec<u16>);
+
+// reads how many values the next array of a chunk has, a chunk never holds more than max of anything
+fn read_count(decoder: &mut ZlibDecoder<&[u8]>, max: usize) -> io::Result<usize> {
+
+    let mut word: [u8; 4] = [0; 4];
+
+    decoder.read_exact(&mut word)?;
+
+    let count: usize = u32::from_le_bytes(word) as usize;
+
+    if count > max {
+        return Err(Error::new(ErrorKind::InvalidData, format!("CHUNK HAS {} VALUES IN AN ARRAY THAT HOLDS AT MOST {}!", count, max)));
+    }
+
+    Ok(count)
+}
+
+fn decompress_chunk(compressed: &[u8]) -> io::Result<ChunkArrays> {
+
+    let mut decoder: ZlibDecoder<&[u8]> = ZlibDecoder::new(compressed);
+
+    let mut word: [u8; 4] = [0; 4];
+
+    let block_count: usize = read_count(&mut decoder, CHUNK_SIZE)?;
+
+    let mut block: Vec<u32> = Vec::with_capacity(block_count);
+    for _ in 0..block_count {
+        decoder.read_exact(&mut word)?;
+        block.push(u32::from_le_bytes(word));
+    }
+
+    let mut byte_arrays: Vec<Vec<u8>> = Vec::with_capacity(2);
+
+    for _ in 0..2 {
+        let mut byte_array: Vec<u8> = vec![0; read_count(&mut decoder, CHUNK_SIZE)?];
+        decoder.read_exact(&mut byte_array)?;
+        byte_arrays.push(byte_array);
+    }
+
+    let heightmap_count: usize = read_count(&mut decoder, CHUNK_AREA)?;
+
+    let mut half_word: [u8; 2] = [0; 2];
+    let mut heightmap: Vec<u16> = Vec::with_capacity(heightmap_count);
+    for _ in 0..heightmap_count {
+        decoder.read_exact(&mut half_word)?;
+        heightmap.push(u16::from_le_bytes(half_word));
+    }
+
+    let light: Vec<u8> = byte_arrays.pop().unwrap();
+    let rotation: Vec<u8> = byte_arrays.pop().unwrap();
+
+    Ok((block, rotation, light, heightmap))
+}
+
+
+// decompresses a chunk that came out of get_saved_chunk, it doesn't need the world save so a worker can do it
+pub fn read_saved_chunk(x: i32, z: i32, compressed: &[u8]) -> io::Result<ChunkData> {
+
+    let (block, rotation, light, heightmap) = decompress_chunk(compressed)?;
+
+    if block.len() != CHUNK_SIZE || rotation.len() != CHUNK_SIZE || light.len() != CHUNK_SIZE || heightmap.len() != CHUNK_AREA {
+        return Err(Error::new(ErrorKind::InvalidData, format!("CHUNK {} {} DOES NOT MATCH THE WORLD SIZE!", x, z)));
+    }
+
+    Ok(ChunkData::from_parts(
+        ChunkStorage::from_slice(&block),
+        ChunkStorage::from_slice(&rotation),
+        ChunkStorage::from_slice(&light),
+        heightmap
+    ))
+}
+
+
+// the world's metadata - this is what makes a saved world reopen the same
+pub struct WorldMetadata {
+    descriptor: WorldDescriptor,
+    // the world parameters the chunks were saved with, chunks of another height can't be read
+    height: i32,
+    min_y: i32,
+    // index is the block id the world was saved with
+    block_names: Vec<String>,
+    // the regions were translated to these block names but not all of them were moved into place yet
+    remapping: bool
+}
+
+impl WorldMetadata {
+    pub fn new(descriptor: WorldDescriptor, bcs: &BlockComponentSystem) -> Self {
+        Self {
+            descriptor,
+            height: CHUNK_HEIGHT,
+            min_y: MIN_Y,
+            block_names: (0..bcs.get_number_of_blocks()).map(|id| bcs.get_name(id).clone()).collect(),
+            remapping: false
+        }
+    }
+
+    pub fn get_descriptor(&self) -> &WorldDescriptor {
+        &self.descriptor
+    }
+
+    pub fn get_seed(&self) -> u64 {
+        self.descriptor.get_seed()
+    }
+
+    pub fn get_height(&self) -> i32 {
+        self.height
+    }
+
+    pub fn get_min_y(&self) -> i32 {
+        self.min_y
+    }
+
+    pub fn get_block_names(&self) -> &Vec<String> {
+        &self.block_names
+    }
+
+    fn read(path: &Path) -> io::Result<Self> {
+
+        let text: String = fs::read_to_string(path)?;
+
+        // worlds from before the descriptor only wrote down their seed
+        let mut name: String = path.parent()
+            .and_then(|directory| directory.file_name())
+            .map(|directory_name| directory_name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut seed_option: Option<u64> = None;
+        let mut generator: GeneratorType = GeneratorType::Biomes;
+        let mut creation_time: u64 = 0;
+        let mut mods: Vec<String> = Vec::new();
+        let mut height_option: Option<i32> = None;
+        let mut min_y_option: Option<i32> = None;
+        let mut block_names: Vec<String> = Vec::new();
+        let mut remapping: bool = false;
+
+        for line in text.lines() {
+
+            let mut parts = line.split_whitespace();
+
+            match parts.next() {
+                // names can have spaces, it's the rest of the line
+                Some("name") => {
+                    name = line.trim_start()["name".len()..].trim().to_string();
+                },
+                Some("seed") => {
+                    seed_option = parts.next().and_then(|seed| seed.parse::<u64>().ok());
+                },
+                Some("generator") => {
+                    generator = match parts.next().and_then(GeneratorType::from_name) {
+                        Some(generator) => generator,
+                        None => return Err(Error::new(ErrorKind::InvalidData, format!("{} HAS AN UNKNOWN GENERATOR: {}", path.display(), line))),
+                    };
+                },
+                Some("created") => {
+                    creation_time = parts.next().and_then(|created| created.parse::<u64>().ok()).unwrap_or(0);
+                },
+                Some("mod") => {
+                    match parts.next() {
+                        Some(mod_name) => mods.push(mod_name.to_string()),
+                        None => return Err(Error::new(ErrorKind::InvalidData, format!("{} HAS A BROKEN MOD LINE: {}", path.display(), line))),
+                    }
+                },
+                Some("height") => {
+                    height_option = match parts.next().and_then(|height| height.parse::<i32>().ok()) {
+                        Some(height) => Some(height),
+                        None => return Err(Error::new(ErrorKind::InvalidData, format!("{} HAS A BROKEN HEIGHT LINE: {}", path.display(), line))),
+                    };
+                },
+                Some("min_y") => {
+                    min_y_option = match parts.next().and_then(|min_y| min_y.parse::<i32>().ok()) {
+                        Some(min_y) => Some(min_y),
+                        None => return Err(Error::new(ErrorKind::InvalidData, format!("{} HAS A BROKEN MIN_Y LINE: {}", path.display(), line))),
+                    };
+                },
+                Some("remapping") => {
+                    remapping = true;
+                },
+                Some("block") => {
+                    let id_option: Option<usize> = parts.next().and_then(|id| id.parse::<usize>().ok());
+                    let name_option: Option<&str> = parts.next();
+
+                    match (id_option, name_option) {
+                        (Some(id), Some(name)) => {
+                            if block_names.len() <= id {
+                                block_names.resize(id + 1, String::new());
+                            }
+                            block_names[id] = name.to_string();
+                        },
+                        _ => return Err(Error::new(ErrorKind::InvalidData, format!("{} HAS A BROKEN BLOCK LINE: {}", path.display(), line))),
+                    }
+                },
+                _ => (),
+            }
+        }
+
+        match (seed_option, height_option, min_y_option) {
+            (Some(seed), Some(height), Some(min_y)) => Ok(Self {
+                descriptor: WorldDescriptor::from_saved(name, seed, generator, creation_time, mods),
+                height,
+                min_y,
+                block_names,
+                remapping
+            }),
+            (None, ..) => Err(Error::new(ErrorKind::InvalidData, format!("{} IS MISSING THE SEED!", path.display()))),
+            _ => Err(Error::new(ErrorKind::InvalidData, format!("{} IS MISSING THE WORLD HEIGHT!", path.display()))),
+        }
+    }
+
+    fn write(&self, path: &Path) -> io::Result<()> {
+
+        let mut text: String = String::new();
+
+        text.push_str(&format!("name {}\n", self.descriptor.get_name()));
+        text.push_str(&format!("seed {}\n", self.descriptor.get_seed()));
+        text.push_str(&format!("generator {}\n", self.descriptor.get_generator().get_name()));
+        text.push_str(&format!("created {}\n", self.descriptor.get_creation_time()));
+
+        for mod_name in self.descriptor.get_mods().iter() {
+            text.push_str(&format!("mod {}\n", mod_name));
+        }
+
+        text.push_str(&format!("height {}\n", self.height));
+        text.push_str(&format!("min_y {}\n", self.min_y));
+
+        if self.remapping {
+            text.push_str("remapping\n");
+        }
+
+        for (id, name) in self.block_names.iter().enumerate() {
+            text.push_str(&format!("block {} {}\n", id, name));
+        }
+
+        // write next to the old file then swap it in, the metadata is what says which ids the regions use
+        let temporary_path: PathBuf = path.with_extension("txt.tmp");
+
+        fs::write(&temporary_path, text)?;
+
+        fs::rename(&temporary_path, path)
+    }
+}
+
+
+// reads and writes one world directory
+pub struct WorldSave {
+    directory: PathBuf,
+
+    metadata: WorldMetadata,
+
+    // regions are cached so loading a whole render distance doesn't reread the same file
+    regions: HashMap<(i32, i32), Region>
+}
+
+impl WorldSave {
+
+    /*
+    reads the descriptor of the world in directory without opening it - None if there is no world there yet
+
+    this is how the game finds out which mods to load before anything is registered
+    */
+    pub fn read_descriptor(directory: &Path) -> io::Result<Option<WorldDescriptor>> {
+
+        let metadata_path: PathBuf = directory.join("world.txt");
+
+        if !metadata_path.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(WorldMetadata::read(&metadata_path)?.descriptor))
+    }
+
+    /*
+    opens a world directory, creating it from the supplied descriptor if it's new
+
+    an existing world keeps the descriptor it was created with, if it never wrote down
+    its mods it takes the supplied ones - a world made with a different height or min y
+    than this build can't be opened
+
+    if the registered blocks changed since the world was saved, every saved chunk
+    is translated to the new ids by name before anything else touches it
+    */
+    pub fn open(directory: &Path, descriptor: WorldDescriptor, bcs: &BlockComponentSystem) -> io::Result<Self> {
+
+        fs::create_dir_all(directory.join("regions"))?;
+
+        let metadata_path: PathBuf = directory.join("world.txt");
+
+        let current_metadata: WorldMetadata = WorldMetadata::new(descriptor, bcs);
+
+        let mut world_save: WorldSave = Self {
+            directory: directory.to_path_buf(),
+            metadata: current_metadata,
+            regions: HashMap::new()
+        };
+
+        if metadata_path.exists() {
+
+            let saved_metadata: WorldMetadata = WorldMetadata::read(&metadata_path)?;
+
+            // a remap that got cut short is finished if it was written down, otherwise it never happened
+            world_save.finish_remap(saved_metadata.remapping)?;
+
+            if saved_metadata.height != CHUNK_HEIGHT || saved_metadata.min_y != MIN_Y {
+                return Err(Error::new(ErrorKind::InvalidData, format!(
+                    "{} IS {} BLOCKS TALL FROM Y {}, THIS WORLD HEIGHT IS {} FROM Y {}!",
+                    directory.display(),
+                    saved_metadata.height,
+                    saved_metadata.min_y,
+                    CHUNK_HEIGHT,
+                    MIN_Y
+                )));
+            }
+
+            let current_mods: Vec<String> = world_save.metadata.descriptor.get_mods().clone();
+
+            world_save.metadata.descriptor = saved_metadata.descriptor.clone();
+
+            if world_save.metadata.descriptor.get_mods().is_empty() {
+                world_save.metadata.descriptor.set_mods(current_mods);
+            }
+
+            if saved_metadata.block_names == world_save.metadata.block_names && saved_metadata.descriptor == world_save.metadata.descriptor && !saved_metadata.remapping {
+                return Ok(world_save);
+            }
+
+            // the mod set changed, translate ids through their names
+            if saved_metadata.block_names != world_save.metadata.block_names {
+                let block_remap: Vec<u32> = saved_metadata.block_names.iter().map(|name| {
+                    match bcs.find_id_of(name) {
+                        Some(id) => id,
+                        None => {
+                            println!("WORLD CONTAINS UNKNOWN BLOCK: {}! REPLACING WITH AIR!", name);
+                            0
+                        }
+                    }
+                }).collect();
+
+                world_save.remap_regions(&block_remap, &metadata_path)?;
+            }
+        }
+
+        world_save.metadata.write(&metadata_path)?;
+
+        Ok(world_save)
+    }
+
+    // every file in the regions directory with the extension
+    fn region_files(&self, extension: &str) -> io::Result<Vec<PathBuf>> {
+        let mut paths: Vec<PathBuf> = Vec::new();
+
+        for entry in fs::read_dir(self.directory.join("regions"))? {
+
+            let path: PathBuf = entry?.path();
+
+            if path.extension().and_then(|extension| extension.to_str()) == Some(extension) {
+                paths.push(path);
+            }
+        }
+
+        Ok(paths)
+    }
+
+    /*
+    rewrites every saved chunk with the new block ids
+
+    a region can only ever be translated once, so the translated regions are written next to
+    the old ones first - the metadata with the new ids going down marked as remapping is the
+    point the remap happened, only after that are the translated regions moved into place
+    */
+    fn remap_regions(&mut self, block_remap: &[u32], metadata_path: &Path) -> io::Result<()> {
+
+        self.write_remapped_regions(block_remap)?;
+
+        self.metadata.remapping = true;
+        self.metadata.write(metadata_path)?;
+        self.metadata.remapping = false;
+
+        self.finish_remap(true)
+    }
+
+    // writes every region translated to the new block ids next to the old one
+    fn write_remapped_regions(&self, block_remap: &[u32]) -> io::Result<()> {
+
+        for path in self.region_files("region")? {
+
+            let mut region: Region = Region::read(&path)?;
+
+            for compressed in region.chunks.iter_mut().flatten() {
+                let (mut block, rotation, light, heightmap) = decompress_chunk(compressed)?;
+
+                block.iter_mut().for_each(|id| {
+                    *id = block_remap.get(*id as usize).copied().unwrap_or(0);
+                });
+
+                *compressed = compress_chunk(&block, &rotation, &light, &heightmap)?;
+            }
+
+            region.write(&path.with_extension("remap"))?;
+        }
+
+        Ok(())
+    }
+
+    // moves translated regions into place if the remap was written down, throws them away if it wasn't
+    fn finish_remap(&mut self, remapping: bool) -> io::Result<()> {
+
+        for path in self.region_files("remap")? {
+            if remapping {
+                fs::rename(&path, path.with_extension("region"))?;
+            } else {
+                fs::remove_file(&path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_directory(&self) -> &Path {
+        &self.directory
+    }
+
+    pub fn get_metadata(&self) -> &WorldMetadata {
+        &self.metadata
+    }
+
+    fn region_path(&self, region_x: i32, region_z: i32) -> PathBuf {
+        self.directory.join("regions").join(format!("r.{}.{}.region", region_x, region_z))
+    }
+
+    fn get_region_mut(&mut self, region_x: i32, region_z: i32) -> io::Result<&mut Region> {
+
+        if !self.regions.contains_key(&(region_x, region_z)) {
+
+            let path: PathBuf = self.region_path(region_x, region_z);
+
+            let region: Region = if path.exists() {
+                Region::read(&path)?
+            } else {
+                Region::new()
+            };
+
+            self.regions.insert((region_x, region_z), region);
+        }
+
+        Ok(self.regions.get_mut(&(region_x, region_z)).unwrap())
+    }
+
+    // forgets the cached regions none of the world's chunks are in, they're read again when a chunk in them is needed
+    pub fn release_regions(&mut self, world: &World) {
+        let regions_in_use: HashSet<(i32, i32)> = world
+            .get_chunk_positions()
+            .into_iter()
+            .map(|(x, z)| region_of(x, z).0)
+            .collect();
+
+        self.regions.retain(|region_position, _| regions_in_use.contains(region_position));
+    }
+
+    // returns if the chunk exists on disk
+    pub fn has_chunk(&mut self, x: i32, z: i32) -> io::Result<bool> {
+        let ((region_x, region_z), index) = region_of(x, z);
+        Ok(self.get_region_mut(region_x, region_z)?.chunks[index].is_some())
+    }
+
+    /*
+    the compressed chunk at x,z as it is on disk, None if it was never saved
+
+    it comes out of the cached region as a copy, read_saved_chunk turns it into chunk data on any thread
+    */
+    pub fn get_saved_chunk(&mut self, x: i32, z: i32) -> io::Result<Option<Vec<u8>>> {
+        let ((region_x, region_z), index) = region_of(x, z);
+        Ok(self.get_region_mut(region_x, region_z)?.chunks[index].clone())
+    }
+
+    /*
+    loads a chunk from disk into the world
+
+    returns false if the chunk was never saved or is already in the world
+    */
+    pub fn load_chunk(&mut self, world: &mut World, x: i32, z: i32) -> io::Result<bool> {
+
+        let chunk: ChunkData = match self.get_saved_chunk(x, z)? {
+            Some(compressed) => read_saved_chunk(x, z, &compressed)?,
+            None => return Ok(false),
+        };
+
+        Ok(world.insert_chunk(x, z, chunk))
+    }
+
+    // compresses a chunk into its cached region without touching the disk
+    fn store_chunk(&mut self, world: &World, x: i32, z: i32) -> io::Result<bool> {
+
+        let compressed: Vec<u8> = match (
+            world.get_chunk_blocks(x, z),
+            world.get_chunk_rotation(x, z),
+            world.get_chunk_light(x, z),
+            world.get_chunk_heightmap(x, z)
+        ) {
+            (Some(block), Some(rotation), Some(light), Some(heightmap)) => compress_chunk(&block.to_vec(), &rotation.to_vec(), &light.to_vec(), heightmap)?,
+            _ => return Ok(false),
+        };
+
+        let ((region_x, region_z), index) = region_of(x, z);
+
+        self.get_region_mut(region_x, region_z)?.chunks[index] = Some(compressed);
+
+        Ok(true)
+    }
+
+    // saves a single chunk to disk - returns false if the chunk is not in the world
+    pub fn save_chunk(&mut self, world: &mut World, x: i32, z: i32) -> io::Result<bool> {
+
+        if !self.store_chunk(world, x, z)? {
+            return Ok(false);
+        }
+
+        let (region_position, _) = region_of(x, z);
+
+        self.write_region(region_position)?;
+
+        world.set_chunk_saved(x, z);
+
+        Ok(true)
+    }
+
+    // saves every modified chunk, each touched region is only written once
+    pub fn save_unsaved(&mut self, world: &mut World) -> io::Result<usize> {
+        let modified_chunks: Vec<(i32, i32)> = world.get_modified_chunks();
+
+        self.save_chunks(world, &modified_chunks)
+    }
+
+    // saves the chunks out of chunks that were modified, each touched region is only written once - returns how many were saved
+    pub fn save_chunks(&mut self, world: &mut World, chunks: &[(i32, i32)]) -> io::Result<usize> {
+
+        let modified_chunks: Vec<(i32, i32)> = chunks
+            .iter()
+            .filter(|(x, z)| world.is_chunk_modified(*x, *z))
+            .copied()
+            .collect();
+
+        let mut touched_regions: HashSet<(i32, i32)> = HashSet::new();
+
+        for (x, z) in modified_chunks.iter() {
+            self.store_chunk(world, *x, *z)?;
+            touched_regions.insert(region_of(*x, *z).0);
+        }
+
+        for region_position in touched_regions {
+            self.write_region(region_position)?;
+        }
+
+        for (x, z) in modified_chunks.iter() {
+            world.set_chunk_saved(*x, *z);
+        }
+
+        Ok(modified_chunks.len())
+    }
+
+    fn write_region(&self, region_position: (i32, i32)) -> io::Result<()> {
+        match self.regions.get(&region_position) {
+            Some(region) => region.write(&self.region_path(region_position.0, region_position.1)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::blocks::block_component_system::{
+        DrawType,
+        RotationType
+    };
+
+    use super::*;
+
+    // air plus the blocks in the order given, so the same names can get different ids
+    fn test_bcs(names: &[&str]) -> BlockComponentSystem {
+        let mut bcs: BlockComponentSystem = BlockComponentSystem::new();
+
+        for name in names {
+            bcs.register_block(String::from("test"), name.to_string(), DrawType::Normal, vec![], None, vec![], 0, RotationType::None);
+        }
+
+        bcs
+    }
+
+    fn test_directory(name: &str) -> PathBuf {
+        let directory: PathBuf = std::env::temp_dir().join(format!("crafter_world_save_{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&directory);
+        directory
+    }
+
+    fn test_descriptor() -> WorldDescriptor {
+        WorldDescriptor::new(String::from("test"), Some(1), GeneratorType::Flat)
+    }
+
+    // saves a chunk with one stone in it, stone is id 1 and dirt id 2
+    fn save_stone(directory: &Path) {
+        let bcs: BlockComponentSystem = test_bcs(&["stone", "dirt"]);
+        let mut world_save: WorldSave = WorldSave::open(directory, test_descriptor(), &bcs).unwrap();
+
+        let mut world: World = World::initialize();
+        world.add_chunk(0, 0);
+        world.set_block(1, 2, 3, bcs.get_id_of(String::from("stone")));
+
+        world_save.save_unsaved(&mut world).unwrap();
+    }
+
+    // opens the world with stone and dirt swapped - translating twice turns the stone into dirt
+    fn assert_stone_once(directory: &Path) {
+        let bcs: BlockComponentSystem = test_bcs(&["dirt", "stone"]);
+        let mut world_save: WorldSave = WorldSave::open(directory, test_descriptor(), &bcs).unwrap();
+
+        let mut world: World = World::initialize();
+        assert!(world_save.load_chunk(&mut world, 0, 0).unwrap());
+
+        assert_eq!(world.get_block(1, 2, 3), Some(bcs.get_id_of(String::from("stone"))));
+        assert!(world_save.region_files("remap").unwrap().is_empty());
+    }
+
+    // gets as far as writing the translated regions, like a crash right before the metadata
+    fn interrupted_remap(directory: &Path) -> WorldSave {
+        let bcs: BlockComponentSystem = test_bcs(&["dirt", "stone"]);
+        let world_save: WorldSave = WorldSave {
+            directory: directory.to_path_buf(),
+            metadata: WorldMetadata::new(test_descriptor(), &bcs),
+            regions: HashMap::new()
+        };
+
+        // air, stone and dirt as they were saved
+        world_save.write_remapped_regions(&[0, 2, 1]).unwrap();
+
+        world_save
+    }
+
+    #[test]
+    fn a_remap_cut_short_before_the_metadata_is_done_over_once() {
+        let directory: PathBuf = test_directory("uncommitted_remap");
+
+        save_stone(&directory);
+        interrupted_remap(&directory);
+
+        assert_stone_once(&directory);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn a_remap_cut_short_after_the_metadata_is_finished_not_repeated() {
+        let directory: PathBuf = test_directory("committed_remap");
+
+        save_stone(&directory);
+
+        let mut world_save: WorldSave = interrupted_remap(&directory);
+        world_save.metadata.remapping = true;
+        world_save.metadata.write(&directory.join("world.txt")).unwrap();
+
+        assert_stone_once(&directory);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn regions_without_loaded_chunks_are_released() {
+        let directory: PathBuf = test_directory("release_regions");
+        let bcs: BlockComponentSystem = test_bcs(&["stone"]);
+        let mut world_save: WorldSave = WorldSave::open(&directory, test_descriptor(), &bcs).unwrap();
+
+        let mut world: World = World::initialize();
+        world.add_chunk(0, 0);
+        world.add_chunk(REGION_SIZE, 0);
+
+        world_save.save_unsaved(&mut world).unwrap();
+        assert_eq!(world_save.regions.len(), 2);
+
+        world.remove_chunk(REGION_SIZE, 0);
+        world_save.release_regions(&world);
+
+        assert_eq!(world_save.regions.len(), 1);
+        assert!(world_save.has_chunk(REGION_SIZE, 0).unwrap());
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn chunks_claiming_more_values_than_fit_are_rejected() {
+        let mut encoder: ZlibEncoder<Vec<u8>> = ZlibEncoder::new(Vec::new(), Compression::fast());
+        encoder.write_all(&u32::MAX.to_le_bytes()).unwrap();
+        let compressed: Vec<u8> = encoder.finish().unwrap();
+
+        let error: Error = decompress_chunk(&compressed).unwrap_err();
+
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn a_saved_chunk_reads_back_the_same() {
+        let directory: PathBuf = test_directory("round_trip");
+        let bcs: BlockComponentSystem = test_bcs(&["stone", "dirt"]);
+        let (stone, dirt) = (bcs.get_id_of(String::from("stone")), bcs.get_id_of(String::from("dirt")));
+
+        let mut world: World = World::initialize();
+        world.add_chunk(1, -1);
+
+        for x in 16..32 {
+            for z in -16..0 {
+                world.set_block(x, 10 + (x + z).rem_euclid(7), z, stone);
+            }
+        }
+        world.set_block(20, 60, -3, dirt);
+        world.set_block_rotation(20, 60, -3, 13);
+        world.set_block_light(21, 61, -4, 9);
+
+        {
+            let mut world_save: WorldSave = WorldSave::open(&directory, test_descriptor(), &bcs).unwrap();
+            assert_eq!(world_save.save_chunks(&mut world, &[(1, -1)]).unwrap(), 1);
+        }
+
+        // a fresh save has nothing cached, it comes off the disk
+        let mut world_save: WorldSave = WorldSave::open(&directory, test_descriptor(), &bcs).unwrap();
+        let compressed: Vec<u8> = world_save.get_saved_chunk(1, -1).unwrap().unwrap();
+        assert!(world_save.get_saved_chunk(0, 0).unwrap().is_none());
+
+        let mut loaded: World = World::initialize();
+        assert!(loaded.insert_chunk(1, -1, read_saved_chunk(1, -1, &compressed).unwrap()));
+
+        assert_eq!(loaded.get_chunk_blocks(1, -1).unwrap().to_vec(), world.get_chunk_blocks(1, -1).unwrap().to_vec());
+        assert_eq!(loaded.get_chunk_rotation(1, -1).unwrap().to_vec(), world.get_chunk_rotation(1, -1).unwrap().to_vec());
+        assert_eq!(loaded.get_chunk_light(1, -1).unwrap().to_vec(), world.get_chunk_light(1, -1).unwrap().to_vec());
+        assert_eq!(loaded.get_chunk_heightmap(1, -1), world.get_chunk_heightmap(1, -1));
+        assert_eq!(loaded.get_surface_height(20, -3), Some(60));
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn regions_of_any_other_version_are_rejected() {
+        let directory: PathBuf = test_directory("version");
+        fs::create_dir_all(&directory).unwrap();
+
+        let path: PathBuf = directory.join("r.0.0.region");
+        let mut data: Vec<u8> = vec![0; HEADER_SIZE];
+        data[0..4].copy_from_slice(REGION_MAGIC);
+        data[4..8].copy_from_slice(&(REGION_VERSION + 1).to_le_bytes());
+        fs::write(&path, data).unwrap();
+
+        assert_eq!(Region::read(&path).err().unwrap().kind(), ErrorKind::InvalidData);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+
+    #[test]
+    fn a_descriptor_reads_back_the_same() {
+        let directory: PathBuf = test_directory("descriptor");
+        let bcs: BlockComponentSystem = test_bcs(&["stone"]);
+
+        assert!(WorldSave::read_descriptor(&directory).unwrap().is_none());
+
+        let mut descriptor: WorldDescriptor = WorldDescriptor::from_saved(String::from("round trip"), u64::MAX - 7, GeneratorType::Biomes, 1_600_000_000, Vec::new());
+        descriptor.set_mods(vec![String::from("crafter"), String::from("extra_trees")]);
+
+        WorldSave::open(&directory, descriptor.clone(), &bcs).unwrap();
+
+        let read: WorldDescriptor = WorldSave::read_descriptor(&directory).unwrap().unwrap();
+        assert_eq!(read.get_name(), "round trip");
+        assert_eq!(read.get_seed(), u64::MAX - 7);
+        assert_eq!(read.get_generator(), GeneratorType::Biomes);
+        assert_eq!(read.get_creation_time(), 1_600_000_000);
+        assert_eq!(read.get_mods(), &vec![String::from("crafter"), String::from("extra_trees")]);
+        assert_eq!(read, descriptor);
+
+        // reopening with a different descriptor keeps the one the world was made with
+        let world_save: WorldSave = WorldSave::open(&directory, test_descriptor(), &bcs).unwrap();
+        assert_eq!(world_save.metadata.get_descriptor(), &descriptor);
+
+        fs::remove_dir_all(&directory).unwrap();
+    }
+}